@@ -1,21 +1,26 @@
 
 use clap::Parser;
 use dash_compiler::{
-    shared::logger::Logger,
-    shared::src::SrcPool,
-    parser::parse::{Node, NodePool},
-    tokenize,
-    checker::pool::ASTPool, check_coherency,
+    shared::{logger::{Logger, JsonSink, Level, Message}, html::html_document},
+    shared::timing::Phase,
+    shared::src::{Src, SrcPool},
+    parser::{parse::Node, config::Edition, profile::ParseProfiler, export::textmate_grammar},
+    session::{Session, ProgressHandler},
+    artifacts::{ArtifactKind, EmitTarget},
+    index::SymbolIndex,
+    project::Project,
     // check_coherency
 };
 use normalize_path::NormalizePath;
-use std::path::PathBuf;
+use std::{io, path::PathBuf, sync::{Arc, Mutex}};
 
 /// Simple program to greet a person
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
-    /// Project directory. Uses current working directory if not provided
+    /// Project directory or source file. Uses current working directory if 
+    /// not provided. If the directory is inside a project with a manifest, 
+    /// the source directories listed in the manifest are compiled
     dir: Option<PathBuf>,
 
     #[clap(long)]
@@ -29,39 +34,216 @@ struct Args {
 
     #[clap(long)]
     debug_log_matches: bool,
+
+    /// Language edition to compile with. Overrides the edition in the 
+    /// project manifest
+    #[clap(long)]
+    edition: Option<Edition>,
+
+    /// Enable code marked with a `@cfg` attribute. Flags are given like 
+    /// `--cfg debug` and values like `--cfg target=wasm`
+    #[clap(long)]
+    cfg: Vec<String>,
+
+    /// Standard library directory to load before the sources. Overrides the 
+    /// standard library in the project manifest
+    #[clap(long)]
+    std: Option<PathBuf>,
+
+    /// Also write all diagnostics to an HTML file
+    #[clap(long)]
+    html_report: Option<PathBuf>,
+
+    /// Also write all diagnostics to a file as JSON, one object per line
+    #[clap(long)]
+    json_diagnostics: Option<PathBuf>,
+
+    /// Profile the parser, printing how often each rule was attempted and 
+    /// writing the rule stacks to a file in the folded format used by 
+    /// flamegraph tools
+    #[clap(long)]
+    profile_parser: Option<PathBuf>,
+
+    /// Write a TextMate grammar for the configured language to a file, for 
+    /// editor syntax highlighting
+    #[clap(long)]
+    textmate_grammar: Option<PathBuf>,
+
+    /// Always produce the same diagnostics and outputs, in the same order, 
+    /// for the same sources. Disables parallel tokenizing
+    #[clap(long)]
+    deterministic: bool,
+
+    /// After checking, list the items declared in the sources whose names 
+    /// fuzzily match the query, best matches first
+    #[clap(long)]
+    symbols: Option<String>,
+
+    /// Update the symbol index at this path with the items declared in the 
+    /// compiled sources, creating it if it doesn't exist
+    #[clap(long)]
+    symbol_index: Option<PathBuf>,
+
+    /// Print which file is being processed in each phase
+    #[clap(long)]
+    progress: bool,
+
+    /// Write the phases and files the compiler goes through to a file as 
+    /// newline-delimited JSON, for analyzing builds in CI
+    #[clap(long)]
+    event_log: Option<PathBuf>,
+
+    /// Extra outputs to produce, out of `tokens`, `ast` and `timings`
+    #[clap(long, value_delimiter = ',')]
+    emit: Vec<ArtifactKind>,
+
+    /// Directory to write the outputs requested with `--emit` to. They are 
+    /// printed if this isn't given
+    #[clap(long)]
+    out_dir: Option<PathBuf>,
+}
+
+/// Prints a line to stderr for every file done in each phase
+struct ConsoleProgress;
+
+impl ProgressHandler for ConsoleProgress {
+    fn on_file_done(&mut self, phase: Phase, src: &Src, n: usize, total: usize) {
+        eprintln!("[{n}/{total}] {phase} {src}");
+    }
+}
+
+/// Print the artifacts kept in memory, and where the others were written to
+fn print_artifacts(session: &mut Session) {
+    for artifact in session.finish() {
+        match (artifact.path, artifact.contents) {
+            (Some(path), _) => println!("Wrote {} to {}", artifact.kind, path.display()),
+            (None, Some(contents)) => {
+                if let Some(src) = artifact.src {
+                    println!(":: {} for {src} ::", artifact.kind);
+                }
+                print!("{contents}");
+            }
+            (None, None) => {}
+        }
+    }
 }
 
 fn main() {
     let args = Args::parse();
     let cur_dir = std::env::current_dir().expect("Unable to get current directory");
 
+    let html_report = Arc::new(Mutex::new(Vec::new()));
     let logger = Logger::default();
+    if args.html_report.is_some() {
+        let html_report = html_report.clone();
+        logger.lock().unwrap().add_sink(
+            move |msg: &Message| html_report.lock().unwrap().push(msg.to_html()),
+            Level::Info
+        );
+    }
+    if let Some(ref path) = args.json_diagnostics {
+        let file = std::fs::File::create(path).expect("Unable to create JSON diagnostics file");
+        logger.lock().unwrap().add_sink(JsonSink::new(file), Level::Info);
+    }
+    let std_dir = args.std.map(|d| cur_dir.join(d).normalize());
+    let out_dir = args.out_dir.map(|d| cur_dir.join(d).normalize());
     let src_dir = args.dir.map(|d| cur_dir.join(d).normalize()).unwrap_or(cur_dir);
-    let src_pool = SrcPool::new_from_dir(src_dir).expect("Unable to find sources");
+    let project_dir = src_dir.parent().filter(|_| src_dir.is_file()).unwrap_or(&src_dir);
+    let project = Project::discover(project_dir).expect("Unable to load project manifest");
+    let mut config = project.as_ref().map(|p| p.language_config()).unwrap_or_default();
+    if let Some(edition) = args.edition {
+        config = config.with_edition(edition);
+    }
+    for option in &args.cfg {
+        config.cfg = config.cfg.with_option(option);
+    }
+    if let Some(ref path) = args.textmate_grammar {
+        std::fs::write(path, format!("{:#}", textmate_grammar(&config))).expect("Unable to write TextMate grammar");
+    }
+    let config = Arc::new(config);
+    let src_pool = match project {
+        Some(ref project) if src_dir.is_dir() => project.src_pool(),
+        _ => SrcPool::new_from_dir(src_dir),
+    }.expect("Unable to find sources");
+    let std_pool = match (std_dir, &project) {
+        (Some(dir), _) => Some(SrcPool::new_from_dir(dir)),
+        (None, Some(project)) => project.std_src_pool(),
+        (None, None) => None,
+    }.transpose().expect("Unable to find standard library sources");
     
-    if args.debug_tokens {
-        for src in &src_pool {
+    let mut session = Session::new(config, logger.clone());
+    if args.debug_log_matches {
+        session.set_debug_log_matches(io::stdout());
+    }
+    session.set_deterministic(args.deterministic);
+    session.set_emit(
+        args.emit.iter().copied(),
+        out_dir.map(EmitTarget::Dir).unwrap_or_default()
+    );
+    if args.progress {
+        session.set_progress_handler(ConsoleProgress);
+    }
+    if let Some(ref path) = args.event_log {
+        let file = std::fs::File::create(path).expect("Unable to create event log");
+        session.set_event_log(file);
+    }
+    if args.debug_tokens || session.emits(ArtifactKind::Tokens) {
+        let tokens = session.tokenize(&src_pool).expect("Compilation is never cancelled");
+        for (src, tokens) in src_pool.iter().zip(tokens).filter(|_| args.debug_tokens) {
             println!(":: Tokens for {src} ::");
-            for t in tokenize(src.as_ref(), logger.clone()) {
+            for t in tokens {
                 println!("{t:#?}");
             }
         }
     }
     if args.no_ast {
+        print_artifacts(&mut session);
         return;
     }
-    let mut node_pool = NodePool::new();
-    let mut ast_pool = ASTPool::parse_src_pool(&mut node_pool, &src_pool, logger.clone());
+    let profiler = args.profile_parser.is_some().then(ParseProfiler::new);
+    session.set_profiler(profiler.clone());
+    let mut std_ast_pool = std_pool.map(|std_pool| session.parse(&std_pool))
+        .transpose().expect("Compilation is never cancelled");
+    let mut ast_pool = session.parse(&src_pool).expect("Compilation is never cancelled");
+
+    if let (Some(profiler), Some(path)) = (profiler, args.profile_parser) {
+        let profiler = profiler.lock().unwrap();
+        print!("{profiler}");
+        profiler.write_folded(std::fs::File::create(path).expect("Unable to create profile file"))
+            .expect("Unable to write profile");
+    }
 
     if args.debug_ast {
         for ast in &ast_pool {
-            println!("AST for {}", ast.get(&node_pool).span_or_builtin(&node_pool).0);
-            println!("{ast:#?}");
+            println!("AST for {}", ast.get(session.pool()).span_or_builtin(session.pool()).0);
+            print!("{}", session.pool().dump(ast));
+        }
+    }
+
+    session.check(
+        std_ast_pool.as_mut().map(|p| p.as_mut_slice()).unwrap_or_default(),
+        ast_pool.as_mut_slice(),
+    ).expect("Compilation is never cancelled");
+
+    print_artifacts(&mut session);
+
+    if let Some(ref path) = args.symbol_index {
+        let mut index = SymbolIndex::load(path).expect("Unable to load symbol index");
+        session.update_symbol_index(&mut index);
+        index.retain_files(|file| std::path::Path::new(file).exists());
+        index.save(path).expect("Unable to save symbol index");
+    }
+
+    if let Some(ref query) = args.symbols {
+        for symbol in session.workspace_symbols(query) {
+            let signature = symbol.signature.lines().next().unwrap_or_default();
+            println!("{} {signature} ({})", symbol.path, symbol.span.as_ref());
         }
     }
 
-    for ast in &mut ast_pool {
-        check_coherency(ast, &mut node_pool, logger.clone());
+    if let Some(path) = args.html_report {
+        std::fs::write(path, html_document(html_report.lock().unwrap().drain(..)))
+            .expect("Unable to write HTML report");
     }
 
     let ref_logger = logger.lock().unwrap();
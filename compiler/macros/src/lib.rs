@@ -6,9 +6,9 @@ extern crate quote;
 extern crate darling;
 
 use darling::{FromDeriveInput, ast, FromField, FromVariant};
-use darling::{FromMeta, ast::NestedMeta};
+use darling::{FromMeta, ast::NestedMeta, util::{SpannedValue, Flag}};
 use proc_macro::TokenStream;
-use proc_macro2::{TokenStream as TokenStream2, Ident};
+use proc_macro2::{TokenStream as TokenStream2, Ident, Span};
 use quote::{quote, quote_spanned, ToTokens, format_ident};
 use syn::parse::Parse;
 use syn::{Generics, Type, Path};
@@ -114,7 +114,12 @@ fn impl_ast_item(
                 src: std::sync::Arc<crate::shared::src::Src>,
                 tokenizer: &mut crate::parser::tokenizer::TokenIterator<'s>
             ) -> Result<crate::parser::parse::NodeID, crate::parser::parse::FatalParseError> {
-                #parse_impl
+                tokenizer.enter_rule(stringify!(#target_name))?;
+                let res: Result<
+                    crate::parser::parse::NodeID, crate::parser::parse::FatalParseError
+                > = (|| { #parse_impl })();
+                tokenizer.exit_rule(stringify!(#target_name), res.is_ok());
+                res
             }
             fn peek<'s>(
                 pos: usize,
@@ -140,16 +145,25 @@ fn impl_ast_struct(
     impl_ast_item(target, &target.ident, &target.generics, parse_impl, peek_impl, children_impl, span_impl).into()
 }
 
+/// Create the identifier for a `TokenKind` variant, pointing errors at the 
+/// `kind` argument it was written in
+fn token_kind_ident(kind: &str, span: Span) -> syn::Result<Ident> {
+    match syn::parse_str::<Ident>(kind) {
+        Ok(mut ident) => {
+            ident.set_span(span);
+            Ok(ident)
+        }
+        Err(_) => Err(syn::Error::new(span, format!("'{kind}' is not a valid token kind"))),
+    }
+}
+
 #[derive(Debug, FromMeta)]
 struct TokenArgs {
-    kind: String,
+    kind: SpannedValue<String>,
     raw: Option<String>,
-    #[darling(default)]
-    value_is_token_tree: bool,
-    #[darling(default)]
-    include_raw: bool,
-    #[darling(default)]
-    no_default_resolve: bool,
+    value_is_token_tree: Flag,
+    include_raw: Flag,
+    no_default_resolve: Flag,
 }
 
 #[proc_macro_attribute]
@@ -164,10 +178,12 @@ pub fn token(args: TokenStream, stream: TokenStream) -> TokenStream {
     let destruct_kind;
     let destruct_drop;
     if let Some(path) = args.kind.strip_suffix("(_)") {
-        let path = Ident::new(path, args.kind.span());
-        if args.value_is_token_tree {
+        let path = unwrap_macro_input!(token_kind_ident(path, args.kind.span()));
+        if args.value_is_token_tree.is_present() {
             expected_construct = quote!{ #path(tokenizer.empty_tree()) };
-            value_field = quote! { value: crate::parser::parse::ParseRef::parse_complete(pool, src.clone(), value)?, };
+            value_field = quote! { value: crate::parser::parse::ParseRef::parse_complete(
+                pool, src.clone(), tokenizer.subtree(value)
+            )?, };
         }
         else {
             expected_construct = quote!{ #path(Default::default()) };
@@ -177,18 +193,26 @@ pub fn token(args: TokenStream, stream: TokenStream) -> TokenStream {
         destruct_kind = quote! { #path(value) };
     }
     else {
-        let path = Ident::new(&args.kind, args.kind.span());
+        let path = unwrap_macro_input!(token_kind_ident(&args.kind, args.kind.span()));
         expected_construct = quote! { #path };
         value_field = quote! {};
-        destruct_drop = quote! { #path };
-        destruct_kind = quote! { #path };
+        // Whether a word is tokenized as a keyword or an identifier depends 
+        // on the language config, so specific words should match either
+        if args.raw.is_some() && matches!(args.kind.as_str(), "Keyword" | "Ident") {
+            destruct_drop = quote! { Keyword | TokenKind::Ident };
+        }
+        else {
+            destruct_drop = quote! { #path };
+        }
+        destruct_kind = destruct_drop.clone();
     }
     let expected_kind = {
         let raw = args.raw.as_deref().unwrap_or("");
         quote! { crate::parser::tokenizer::Token {
             kind: crate::parser::tokenizer::TokenKind::#expected_construct,
-            raw: #raw,
+            raw: std::borrow::Cow::Borrowed(#raw),
             span: crate::shared::src::Span::builtin(),
+            hygiene: 0,
         } }
     };
     let test_raw = if let Some(ref raw) = args.raw {
@@ -197,11 +221,11 @@ pub fn token(args: TokenStream, stream: TokenStream) -> TokenStream {
     else {
         quote! { true }
     };
-    let raw_field = if args.include_raw {
+    let raw_field = if args.include_raw.is_present() {
         get_named_fields!(&mut target).push(
-            Field::parse_named.parse2(quote! { raw: String }).unwrap()
+            Field::parse_named.parse2(quote! { raw: ::std::string::String }).unwrap()
         );
-        quote! { raw: token.raw.to_string(), }
+        quote! { raw: token.text().into_owned(), }
     }
     else {
         quote! {}
@@ -211,15 +235,18 @@ pub fn token(args: TokenStream, stream: TokenStream) -> TokenStream {
         quote! {
             use crate::parser::tokenizer::TokenKind;
             use crate::shared::src::ArcSpan;
+            use crate::shared::ice::Ice;
             if let Some(peek) = tokenizer.peek(0) {
                 if let TokenKind::#destruct_drop = peek.kind {
                     if #test_raw {
-                        let token = tokenizer.next().unwrap();
-                        let TokenKind::#destruct_kind = token.kind else { unreachable!() };
-                        let r = Self {
-                            #value_field
-                            #raw_field
-                            span: ArcSpan(src, token.span.1)
+                        let token = tokenizer.next().ice("peeked token disappeared");
+                        let r = match token.kind {
+                            TokenKind::#destruct_kind => Self {
+                                #value_field
+                                #raw_field
+                                span: ArcSpan(src, token.span.1)
+                            },
+                            _ => crate::ice!("token kind changed after peeking"),
                         };
                         return Ok(pool.add(r));
                     }
@@ -237,7 +264,7 @@ pub fn token(args: TokenStream, stream: TokenStream) -> TokenStream {
                 false
             }
         },
-        if args.value_is_token_tree {
+        if args.value_is_token_tree.is_present() {
             quote! { vec![&self.value] }
         }
         else {
@@ -251,7 +278,7 @@ pub fn token(args: TokenStream, stream: TokenStream) -> TokenStream {
     ).into();
     let name = target.ident;
     let (impl_generics, ty_generics, where_clause) = target.generics.split_for_impl();
-    let resolve_node = (!args.no_default_resolve).then(|| 
+    let resolve_node = (!args.no_default_resolve.is_present()).then(|| 
         quote! {
             impl #impl_generics crate::checker::resolve::ResolveNode for #name #ty_generics #where_clause {
                 fn try_resolve_node(
@@ -278,9 +305,8 @@ struct ParseReceiver {
     ident: syn::Ident,
     generics: syn::Generics,
     data: ast::Data<ParseVariant, ParseField>,
-    expected: Option<String>,
-    #[darling(default)]
-    no_peek: bool,
+    expected: Option<SpannedValue<String>>,
+    no_peek: Flag,
 }
 
 #[derive(FromVariant)]
@@ -294,15 +320,19 @@ struct ParseVariant {
 struct ParseField {
     ident: Option<syn::Ident>,
     ty: Type,
-    #[darling(default)]
-    peek_point: bool,
-    #[darling(default)]
-    skip: bool,
-    #[darling(default)]
+    peek_point: Flag,
+    skip: Flag,
     skip_with: Option<String>,
+    /// Custom message for when this field is not found
+    expected: Option<SpannedValue<String>>,
 }
 
-fn field_to_tokens(data: &ast::Fields<ParseField>, self_name: Path) -> (TokenStream2, TokenStream2, TokenStream2) {
+/// Generate the parse, peek and children impls for the fields of a struct or 
+/// enum variant. Errors in the field attributes are added to `errors` 
+/// instead, since the impls would only produce more confusing errors
+fn field_to_tokens(
+    data: &ast::Fields<ParseField>, self_name: Path, errors: &mut TokenStream2
+) -> (TokenStream2, TokenStream2, TokenStream2) {
     let mut children_impl = quote! {};
     let mut parse_impl = quote! {};
     let mut peek_checks = quote! {};
@@ -311,10 +341,10 @@ fn field_to_tokens(data: &ast::Fields<ParseField>, self_name: Path) -> (TokenStr
     // Find peek point if it was manually set
     let mut encountered_peek_end = false;
     let mut peek_count = 0;
-    for field in data.iter().filter(|d| !d.skip && d.skip_with.is_none()) {
-        if field.peek_point {
+    for field in data.iter().filter(|d| !d.skip.is_present() && d.skip_with.is_none()) {
+        if field.peek_point.is_present() {
             if extract_type_from_option(&field.ty).is_some() {
-                parse_impl.extend(
+                errors.extend(
                     syn::Error::new(
                         field.ty.span(),
                         "peek point may not be an optional field"
@@ -322,9 +352,9 @@ fn field_to_tokens(data: &ast::Fields<ParseField>, self_name: Path) -> (TokenStr
                 );
             }
             if encountered_peek_end {
-                parse_impl.extend(
+                errors.extend(
                     syn::Error::new(
-                        field.ident.span(),
+                        field.ident.as_ref().map(|i| i.span()).unwrap_or(field.ty.span()),
                         "only one field may be marked as the peek point"
                     ).to_compile_error()
                 );
@@ -371,11 +401,11 @@ fn field_to_tokens(data: &ast::Fields<ParseField>, self_name: Path) -> (TokenStr
                 }
                 Err(e) => {
                     let e = e.to_compile_error().to_token_stream();
-                    parse_impl.extend(quote_spanned!(skip.span() => #e));
+                    errors.extend(quote_spanned!(skip.span() => #e));
                 }
             }
         }
-        else if field.skip {
+        else if field.skip.is_present() {
             if let Some(ref i) = field.ident {
                 parse_impl.extend(quote! { #i: Default::default(), });
             }
@@ -385,9 +415,30 @@ fn field_to_tokens(data: &ast::Fields<ParseField>, self_name: Path) -> (TokenStr
         }
         else {
             let t = &field.ty;
+            let parse_field = if let Some(ref expected) = field.expected {
+                if extract_type_from_option(t).is_some() {
+                    errors.extend(
+                        syn::Error::new(
+                            expected.span(),
+                            "cannot use \"expected\" on an optional field"
+                        ).to_compile_error()
+                    );
+                }
+                let expected = expected.as_str();
+                quote! { {
+                    if !<#t as crate::parser::parse::ParseRef>::peek(0, tokenizer) {
+                        tokenizer.expected(#expected);
+                        return Err(crate::parser::parse::FatalParseError);
+                    }
+                    crate::parser::parse::ParseRef::parse_ref(pool, src.clone(), tokenizer)?
+                } }
+            }
+            else {
+                quote! { crate::parser::parse::ParseRef::parse_ref(pool, src.clone(), tokenizer)? }
+            };
             if let Some(ref i) = field.ident {
                 parse_impl.extend(quote! {
-                    #i: crate::parser::parse::ParseRef::parse_ref(pool, src.clone(), tokenizer)?,
+                    #i: #parse_field,
                 });
                 children_impl.extend(quote! {
                     (&self.#i as &dyn crate::checker::resolve::ResolveRef),
@@ -395,7 +446,7 @@ fn field_to_tokens(data: &ast::Fields<ParseField>, self_name: Path) -> (TokenStr
             }
             else {
                 parse_impl.extend(quote! {
-                    crate::parser::parse::ParseRef::parse_ref(pool, src.clone(), tokenizer)?,
+                    #parse_field,
                 });
                 children_impl.extend(quote! {
                     (&self.#field_ix as &dyn crate::checker::resolve::ResolveRef),
@@ -463,22 +514,27 @@ impl ToTokens for ParseReceiver {
     fn to_tokens(&self, tokens: &mut TokenStream2) {
         match &self.data {
             ast::Data::Struct(data) => {
-                if self.expected.is_some() {
-                    tokens.extend(
+                let mut errors = quote! {};
+                if let Some(ref expected) = self.expected {
+                    errors.extend(
                         syn::Error::new(
-                            self.expected.span(),
+                            expected.span(),
                             "cannot use \"expected\" on a struct"
                         ).to_compile_error()
                     );
                 }
                 // note to self: don't call `self.span()` - it causes rustc to crash
                 let (parse, peek, span) = field_to_tokens(
-                    data, Path::from_string("Self").unwrap()
+                    data, Path::from_string("Self").unwrap(), &mut errors
                 );
+                if !errors.is_empty() {
+                    tokens.extend(errors);
+                    return;
+                }
                 tokens.extend(impl_ast_item(
                     &quote!{}, &self.ident, &self.generics,
                     parse,
-                    if self.no_peek { quote! { false } } else { peek },
+                    if self.no_peek.is_present() { quote! { false } } else { peek },
                     span,
                     None
                 ));
@@ -487,6 +543,7 @@ impl ToTokens for ParseReceiver {
                 let mut parse_impl = quote! {};
                 let mut peek_impl = quote! {};
                 let mut children_impl = quote! {};
+                let mut errors = quote! {};
                 for variant in data {
                     let v = &variant.ident;
                     if variant.fields.is_unit() {
@@ -496,7 +553,8 @@ impl ToTokens for ParseReceiver {
                     else {
                         let (parse, peek, _) = field_to_tokens(
                             &variant.fields,
-                            Path::from_string(&format!("Self::{v}")).unwrap()
+                            Path::from_string(&format!("Self::{v}")).unwrap(),
+                            &mut errors
                         );
                         parse_impl.extend(quote! {
                             if { #peek } {
@@ -514,7 +572,7 @@ impl ToTokens for ParseReceiver {
                         if variant.fields.is_struct() {
                             for field in variant.fields.fields.iter() {
                                 let name = &field.ident;
-                                if field.skip || field.skip_with.is_some() {
+                                if field.skip.is_present() || field.skip_with.is_some() {
                                     names.extend(quote! { #name: _, });
                                 }
                                 else {
@@ -531,7 +589,7 @@ impl ToTokens for ParseReceiver {
                             for (field, c) in variant.fields.fields.iter().zip(
                                 ('a'..='z').map(|c| Ident::new(&c.to_string(), v.span()))
                             ) {
-                                if field.skip || field.skip_with.is_some() {
+                                if field.skip.is_present() || field.skip_with.is_some() {
                                     names.extend(quote! { _, });
                                 }
                                 else {
@@ -549,7 +607,20 @@ impl ToTokens for ParseReceiver {
                     }
                 }
                 
-                let expected = &self.expected;
+                let Some(expected) = self.expected.as_ref().map(|e| e.as_str()) else {
+                    errors.extend(
+                        syn::Error::new(
+                            self.ident.span(),
+                            "enums must specify what they expect using #[parse(expected = \"...\")]"
+                        ).to_compile_error()
+                    );
+                    tokens.extend(errors);
+                    return;
+                };
+                if !errors.is_empty() {
+                    tokens.extend(errors);
+                    return;
+                }
                 tokens.extend(impl_ast_item(
                     &quote!{}, &self.ident, &self.generics,
                     quote! {
@@ -558,7 +629,7 @@ impl ToTokens for ParseReceiver {
                         tokenizer.expected(#expected);
                         Err(crate::parser::parse::FatalParseError)
                     },
-                    if self.no_peek {
+                    if self.no_peek.is_present() {
                         quote! { false }
                     }
                     else {
@@ -0,0 +1,9 @@
+extern crate trybuild;
+
+/// Malformed `#[token]` and `#[derive(ParseNode)]` input should be reported 
+/// at the attribute or field that is wrong. Run with `TRYBUILD=overwrite` to 
+/// update the expected errors
+#[test]
+fn compile_fail() {
+    trybuild::TestCases::new().compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,18 @@
+#[macro_use]
+extern crate dash_macros;
+
+#[derive(ParseNode)]
+struct CallNode(
+    #[parse(peek_point)] u32,
+    #[parse(peek_point)] Vec<u32>,
+);
+
+#[derive(ParseNode)]
+struct IndexNode {
+    #[parse(peek_point)]
+    target: u32,
+    #[parse(peek_point)]
+    index: u32,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+#[macro_use]
+extern crate dash_macros;
+
+#[derive(ParseNode)]
+enum ExprNode {
+    Int(u32),
+    Float(f32),
+}
+
+fn main() {}
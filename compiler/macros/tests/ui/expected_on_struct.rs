@@ -0,0 +1,10 @@
+#[macro_use]
+extern crate dash_macros;
+
+#[derive(ParseNode)]
+#[parse(expected = "a let statement")]
+struct LetNode {
+    name: u32,
+}
+
+fn main() {}
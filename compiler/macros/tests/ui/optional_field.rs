@@ -0,0 +1,12 @@
+#[macro_use]
+extern crate dash_macros;
+
+#[derive(ParseNode)]
+struct ReturnNode {
+    #[parse(peek_point)]
+    value: Option<u32>,
+    #[parse(expected = "a semicolon")]
+    semicolon: Option<u32>,
+}
+
+fn main() {}
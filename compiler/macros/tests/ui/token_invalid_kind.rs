@@ -0,0 +1,10 @@
+#[macro_use]
+extern crate dash_macros;
+
+#[token(kind = "Not A Kind")]
+struct Plus;
+
+#[token(kind = "1nt(_)")]
+struct Int;
+
+fn main() {}
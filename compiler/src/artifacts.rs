@@ -0,0 +1,102 @@
+use std::{fmt::Display, path::PathBuf, str::FromStr};
+
+/// A kind of output that a compilation can emit besides its diagnostics
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ArtifactKind {
+    /// The tokens of each source file
+    Tokens,
+    /// A dump of the AST of each source file that could be parsed
+    Ast,
+    /// How long each phase took, for the whole compilation
+    Timings,
+}
+
+impl ArtifactKind {
+    pub const ALL: [ArtifactKind; 3] = [ArtifactKind::Tokens, ArtifactKind::Ast, ArtifactKind::Timings];
+
+    /// Whether one artifact of this kind is emitted per source file, rather 
+    /// than one for the whole compilation
+    pub fn is_per_file(self) -> bool {
+        !matches!(self, Self::Timings)
+    }
+}
+
+impl Display for ArtifactKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Tokens => "tokens",
+            Self::Ast => "ast",
+            Self::Timings => "timings",
+        })
+    }
+}
+
+impl FromStr for ArtifactKind {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ArtifactKind::ALL.iter()
+            .find(|k| k.to_string() == s)
+            .copied()
+            .ok_or_else(|| format!(
+                "Unknown artifact kind '{s}', expected one of {}",
+                ArtifactKind::ALL.iter().map(|k| format!("'{k}'")).collect::<Vec<_>>().join(", ")
+            ))
+    }
+}
+
+/// Where emitted artifacts are put
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum EmitTarget {
+    /// Kept in memory, see `Artifact::contents`
+    #[default]
+    Memory,
+    /// Written to files in a directory. Per-file artifacts are named after 
+    /// their source file with the kind as an extension, like `main.dash.ast`, 
+    /// and the others after their kind, like `timings.txt`
+    Dir(PathBuf),
+}
+
+/// An output emitted by a compilation
+#[derive(Debug, Clone)]
+pub struct Artifact {
+    pub kind: ArtifactKind,
+    /// Name of the source the artifact was produced from, or None if it 
+    /// covers the whole compilation
+    pub src: Option<String>,
+    /// The file the artifact was written to, if it was emitted to a directory
+    pub path: Option<PathBuf>,
+    /// The contents of the artifact, if it was emitted in memory
+    pub contents: Option<String>,
+}
+
+/// Every artifact emitted by a session, in the order they were emitted
+#[derive(Debug, Clone, Default)]
+pub struct Artifacts {
+    artifacts: Vec<Artifact>,
+}
+
+impl Artifacts {
+    pub(crate) fn push(&mut self, artifact: Artifact) {
+        self.artifacts.push(artifact);
+    }
+    pub fn iter(&self) -> impl Iterator<Item = &Artifact> {
+        self.artifacts.iter()
+    }
+    pub fn of_kind(&self, kind: ArtifactKind) -> impl Iterator<Item = &Artifact> {
+        self.artifacts.iter().filter(move |a| a.kind == kind)
+    }
+    pub fn len(&self) -> usize {
+        self.artifacts.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.artifacts.is_empty()
+    }
+}
+
+impl IntoIterator for Artifacts {
+    type Item = Artifact;
+    type IntoIter = std::vec::IntoIter<Artifact>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.artifacts.into_iter()
+    }
+}
@@ -1,35 +1,54 @@
 
+use std::sync::Arc;
+
 use dash_macros::{ParseNode, ResolveNode};
-use super::{expr::{Expr, IdentPath, ExprList}, token::{lit, kw}};
+use super::{expr::{Expr, IdentPath, ExprList}, token::{lit, kw, punct}};
 use crate::{
     ast::token::delim,
-    checker::{resolve::ResolveNode, coherency::Checker, ty::Ty, path}, parser::parse::{NodePool, Node}, shared::logger::{Message, Level, LoggerRef}
+    checker::{
+        resolve::{ResolveNode, ResolveRef}, coherency::Checker, ty::Ty, path,
+        consteval::{EvalConst, ConstValue, ConstError}
+    },
+    parser::{
+        parse::{NodePool, Node, NodeID, ParseNode, ParseRef, RefToNode, SeparatedWithTrailing, FatalParseError},
+        tokenizer::{TokenIterator, TokenKind}
+    },
+    shared::{src::Src, logger::{Message, Level, Note, LoggerRef}}
 };
 
 #[derive(Debug, ParseNode)]
 #[parse(expected = "identifier")]
 pub enum ItemUseNode {
     This(kw::This),
-    Ident(IdentPath),
+    /// The value of the item is filled in when it refers to a constant
+    Ident(IdentPath, #[parse(skip)] Option<ConstValue>),
 }
 
 impl ResolveNode for ItemUseNode {
     fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
-        for scope in checker.scopes() {
-            if let Some(ent) = scope.entities().find(
-                &match self {
-                    Self::Ident(i) => i.get(pool).to_path(pool),
-                    Self::This(_) => path::IdentPath::new([path::Ident::from("this")], false)
-                }
-            ) {
-                return Some(ent.ty());
-            }
+        let name = match self {
+            Self::Ident(i, _) => i.get(pool).to_path(pool),
+            Self::This(_) => path::IdentPath::new([path::Ident::from("this")], false)
+        };
+        let found = checker.scopes().find_map(|scope| scope.entities().find(&name).map(|ent| {
+            ent.mark_used();
+            (ent.ty(), ent.span(), ent.value())
+        }));
+        let Some((ty, span, value)) = found else {
+            checker.add_const_dependency(name.to_string());
+            return None;
+        };
+        checker.lint_deprecated_use(&name.to_string(), &span, &self.span_or_builtin(pool));
+        if let Self::Ident(_, v) = self {
+            *v = value;
         }
-        None
+        Some(ty)
     }
-    fn log_unresolved_reason(&self, pool: &NodePool, _checker: &Checker, logger: LoggerRef) {
+    fn log_unresolved_reason(&self, pool: &NodePool, checker: &Checker, logger: LoggerRef) {
         match self {
-            Self::Ident(i) => logger.lock().unwrap().log(Message::new(
+            // Constants that could not be resolved report why themselves
+            Self::Ident(i, _) if checker.is_const_pending(&i.get(pool).to_path(pool).to_string()) => {}
+            Self::Ident(i, _) => logger.lock().unwrap().log(Message::new(
                 Level::Error,
                 format!("Unknown item {}", i.get(pool).to_path(pool)),
                 i.get(pool).span_or_builtin(pool).as_ref()
@@ -43,15 +62,137 @@ impl ResolveNode for ItemUseNode {
     }
 }
 
+#[derive(Debug, ParseNode)]
+#[parse(expected = "array item")]
+pub enum ArrayItemNode {
+    Spread(punct::Spread, Expr),
+    Item(Expr),
+}
+
+impl ResolveNode for ArrayItemNode {
+    /// Resolves to the type of the items this adds to the array
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        match self {
+            Self::Item(value) => value.try_resolve_ref(pool, checker),
+            Self::Spread(_, value) => match value.try_resolve_ref(pool, checker)?.reduce() {
+                Ty::Array(item) => Some(item.as_ref().clone()),
+                other if other.is_unreal() => Some(Ty::Invalid),
+                other => {
+                    checker.logger().lock().unwrap().log(Message::new(
+                        Level::Error,
+                        format!("Cannot spread value of type {other}"),
+                        value.get(pool).span_or_builtin(pool).as_ref()
+                    ).note(Note::new("Only arrays can be spread", false)));
+                    Some(Ty::Invalid)
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, ParseNode)]
+pub struct ArrayNode {
+    items: delim::Bracketed<SeparatedWithTrailing<ArrayItem, punct::Comma>>,
+}
+
+impl ResolveNode for ArrayNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        let items = self.items.get(pool).value.iter()
+            .map(|item| Some((item.try_resolve_ref(pool, checker)?, item.get(pool).span(pool))))
+            .collect::<Option<Vec<_>>>()?;
+        // The first item decides the type of the array, and an empty array 
+        // can be converted to an array of any type
+        let Some((item_ty, _)) = items.first() else {
+            return Some(Ty::Array(Ty::Never.into()));
+        };
+        let item_ty = item_ty.clone();
+        for (ty, span) in items.into_iter().skip(1) {
+            checker.expect_ty_eq(item_ty.clone(), ty, span);
+        }
+        Some(Ty::Array(item_ty.into()))
+    }
+}
+
+/// A tuple of values, like `(1, "a")`. Parentheses without a comma inside 
+/// them are just grouping, see `AtomNode::ClosedExpr`
+#[derive(Debug)]
+pub struct TupleNode {
+    items: delim::Parenthesized<SeparatedWithTrailing<Expr, punct::Comma>>,
+}
+pub type Tuple = RefToNode<TupleNode>;
+
+impl Node for TupleNode {
+    fn children(&self) -> Vec<&dyn ResolveRef> {
+        vec![&self.items]
+    }
+}
+
+impl ParseNode for TupleNode {
+    fn parse_node(
+        pool: &mut NodePool,
+        src: Arc<Src>,
+        tokenizer: &mut TokenIterator
+    ) -> Result<NodeID, FatalParseError> {
+        let res = Self { items: ParseRef::parse_ref(pool, src, tokenizer)? };
+        Ok(pool.add(res))
+    }
+    fn peek(pos: usize, tokenizer: &TokenIterator) -> bool {
+        tokenizer.peek(pos).is_some_and(|token| match &token.kind {
+            TokenKind::Parentheses(tree) => tree.items().iter()
+                .any(|t| matches!(t.kind, TokenKind::Punct) && t.raw == ","),
+            _ => false,
+        })
+    }
+}
+
+impl ResolveNode for TupleNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        let items = self.items.get(pool).value.iter()
+            .map(|item| item.try_resolve_ref(pool, checker))
+            .collect::<Option<Vec<_>>>()?;
+        Some(Ty::Tuple(items))
+    }
+}
+
 #[derive(Debug, ParseNode, ResolveNode)]
 #[parse(expected = "expression")]
 pub enum AtomNode {
+    Tuple(Tuple),
     ClosedExpr(delim::Parenthesized<Expr>),
     Block(delim::Braced<ExprList>),
+    Array(Array),
     ItemUse(ItemUse),
     String(lit::String),
+    Bytes(lit::Bytes),
+    Char(lit::Char),
     Float(lit::Float),
     Int(lit::Int),
     Bool(lit::Bool),
     Void(lit::Void),
 }
+
+impl EvalConst for ItemUseNode {
+    fn eval_const(&self, pool: &NodePool) -> Result<ConstValue, ConstError> {
+        match self {
+            Self::Ident(_, Some(value)) => Ok(value.clone()),
+            _ => Err(ConstError::not_const(self, pool)),
+        }
+    }
+}
+
+impl EvalConst for AtomNode {
+    fn eval_const(&self, pool: &NodePool) -> Result<ConstValue, ConstError> {
+        match self {
+            Self::ItemUse(item) => item.eval_const(pool),
+            Self::ClosedExpr(expr) => expr.get(pool).value.eval_const(pool),
+            Self::String(s) => Ok(ConstValue::String(s.get(pool).value().to_string())),
+            Self::Bytes(b) => Ok(ConstValue::Bytes(b.get(pool).value().to_vec())),
+            Self::Char(c) => Ok(ConstValue::Char(c.get(pool).value())),
+            Self::Float(f) => Ok(ConstValue::Float(f.get(pool).value())),
+            Self::Int(i) => Ok(ConstValue::Int(i.get(pool).value())),
+            Self::Bool(b) => Ok(ConstValue::Bool(b.get(pool).value())),
+            Self::Void(_) => Ok(ConstValue::Void),
+            _ => Err(ConstError::not_const(self, pool)),
+        }
+    }
+}
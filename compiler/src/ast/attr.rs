@@ -0,0 +1,140 @@
+
+use std::sync::Arc;
+
+use dash_macros::ParseNode;
+use crate::{
+    parser::{
+        parse::{ParseRef, Ref, Node, Separated, NodePool, NodeID, FatalParseError},
+        tokenizer::TokenIterator
+    },
+    checker::{resolve::{ResolveNode, ResolveRef}, coherency::Checker, ty::Ty, lint::{find_lint, LINTS}},
+    shared::{src::Src, logger::{Message, Level, Note}}
+};
+use super::token::{punct, attr, delim, lit, op, Ident};
+
+/// The condition of a `@cfg` attribute, like `debug` or `target = "wasm"`
+#[derive(Debug, ParseNode)]
+pub struct CfgPredicateNode {
+    #[parse(expected = "configuration option")]
+    name: Ident,
+    value: Option<(op::Seq, lit::String)>,
+}
+
+impl CfgPredicateNode {
+    /// Whether the item this is attached to should be compiled
+    fn is_enabled(&self, pool: &NodePool, checker: &Checker) -> bool {
+        let name = self.name.get(pool).to_string();
+        let value = self.value.as_ref().map(|(_, v)| v.get(pool).value().to_string());
+        checker.config().cfg.is_set(&name, value.as_deref())
+    }
+}
+
+impl ResolveNode for CfgPredicateNode {
+    fn try_resolve_node(&mut self, _: &NodePool, _: &mut Checker) -> Option<Ty> {
+        Some(Ty::Invalid)
+    }
+}
+
+/// An attribute like `@allow(unused_variable)` placed before an expression
+#[derive(Debug, ParseNode)]
+#[parse(expected = "attribute")]
+pub enum AttributeNode {
+    Allow(
+        punct::At,
+        #[parse(peek_point)] attr::Allow,
+        delim::Parenthesized<Separated<Ident, punct::Comma>>
+    ),
+    Deprecated(
+        punct::At,
+        #[parse(peek_point)] attr::Deprecated,
+        Option<delim::Parenthesized<lit::String>>
+    ),
+    Cfg(
+        punct::At,
+        #[parse(peek_point)] attr::Cfg,
+        delim::Parenthesized<CfgPredicate>
+    ),
+}
+
+impl ResolveNode for AttributeNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        match self {
+            Self::Allow(_, _, lints) => {
+                for lint in lints.get(pool).value.iter() {
+                    let name = lint.get(pool).to_string();
+                    if find_lint(&name).is_none() {
+                        checker.logger().lock().unwrap().log(Message::new(
+                            Level::Warning,
+                            format!("Unknown lint '{name}'"),
+                            lint.get(pool).span_or_builtin(pool).as_ref()
+                        ).note(Note::new(
+                            format!("Known lints are {}", LINTS.iter().map(|l| l.name).collect::<Vec<_>>().join(", ")),
+                            false
+                        )));
+                    }
+                }
+            }
+            Self::Deprecated(_, _, _) | Self::Cfg(_, _, _) => {}
+        }
+        Some(Ty::Invalid)
+    }
+}
+
+/// An item preceded by any number of attributes
+#[derive(Debug)]
+pub struct Attributed<T: Ref> {
+    pub attrs: Vec<Attribute>,
+    pub item: T,
+}
+
+impl<T: Ref> Ref for Attributed<T> {
+    fn ids(&self) -> Vec<NodeID> {
+        self.attrs.ids().into_iter().chain(self.item.ids()).collect()
+    }
+}
+
+impl<T: ParseRef> ParseRef for Attributed<T> {
+    fn parse_ref(pool: &mut NodePool, src: Arc<Src>, tokenizer: &mut TokenIterator) -> Result<Self, FatalParseError> {
+        Ok(Self {
+            attrs: ParseRef::parse_ref(pool, src.clone(), tokenizer)?,
+            item: T::parse_ref(pool, src, tokenizer)?,
+        })
+    }
+    fn peek(pos: usize, tokenizer: &TokenIterator) -> bool {
+        Attribute::peek(pos, tokenizer) || T::peek(pos, tokenizer)
+    }
+}
+
+impl<T: ResolveRef> ResolveRef for Attributed<T> {
+    fn try_resolve_ref(&self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        // Items disabled by `@cfg` are parsed but never checked
+        let disabled = self.attrs.iter().any(|attr| match &*attr.get(pool) {
+            AttributeNode::Cfg(_, _, pred) => !pred.get(pool).value.get(pool).is_enabled(pool, checker),
+            _ => false,
+        });
+        if disabled {
+            pool.mark_skipped(self);
+            return Some(Ty::Void);
+        }
+        // Suppressions have to be registered before the item is checked, so 
+        // lints emitted while checking it can see them
+        self.attrs.try_resolve_ref(pool, checker);
+        if let Some(span) = pool.span_of(&self.item) {
+            for attr in &self.attrs {
+                match &*attr.get(pool) {
+                    AttributeNode::Allow(_, _, lints) => {
+                        for lint in lints.get(pool).value.iter() {
+                            checker.allow_lint(span.clone(), lint.get(pool).to_string());
+                        }
+                    }
+                    AttributeNode::Deprecated(_, _, reason) => {
+                        let reason = reason.map(|r| r.get(pool).value.get(pool).value().to_string());
+                        checker.deprecate(span.clone(), reason);
+                    }
+                    AttributeNode::Cfg(_, _, _) => {}
+                }
+            }
+        }
+        self.item.try_resolve_ref(pool, checker)
+    }
+}
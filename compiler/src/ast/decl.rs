@@ -1,16 +1,29 @@
 
+use std::sync::Arc;
+
 use crate::{
-    parser::parse::{SeparatedWithTrailing, DontExpect, Node, NodePool},
+    parser::{
+        parse::{SeparatedWithTrailing, DontExpect, Node, NodePool, NodeID, ParseNode, ParseRef, RefToNode, FatalParseError},
+        tokenizer::{TokenIterator, TokenKind, MAX_PEEK_COUNT}
+    },
     add_compile_message,
-    checker::{resolve::{ResolveNode, ResolveRef}, coherency::{Checker, ScopeID}, ty::Ty, entity::Entity, path},
-    shared::{src::ArcSpan, logger::{Message, Level, Note}}, try_resolve_ref
+    checker::{
+        resolve::{ResolveNode, ResolveRef}, coherency::{Checker, ScopeID},
+        ty::{Ty, ParamTy, TyDisplay}, entity::Entity, path, intrinsic::{find_intrinsic, signature_matches},
+        consteval::{ConstValue, EvalConst}
+    },
+    shared::{src::{ArcSpan, Src}, logger::{Message, Level, Note, LoggerRef}}, try_resolve_ref
+};
+use super::{
+    token::{kw, op, punct, delim, Ident}, ty::TypeExpr, expr::{Expr, IdentPath, ExprList},
+    flow::AssertArgs, attr::Attributed, pattern::{Pattern, declare_pattern_vars}
 };
-use super::{token::{kw, op, punct, delim, Ident}, ty::TypeExpr, expr::{Expr, IdentPath, ExprList}};
 use dash_macros::{ParseNode, ResolveNode};
 
 #[derive(Debug, ParseNode)]
 pub struct LetDeclNode {
     let_kw: kw::Let,
+    #[parse(expected = "variable name")]
     name: IdentPath,
     ty: Option<(punct::Colon, TypeExpr)>,
     value: Option<(op::Seq, Expr)>,
@@ -20,31 +33,158 @@ impl ResolveNode for LetDeclNode {
     fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
         let ty = try_resolve_ref!(self.ty, (pool, checker), Some((_, ty)) => ty);
         let value = try_resolve_ref!(self.value, (pool, checker), Some((_, ty)) => ty);
-        let vty = checker.expect_ty_eq(value, ty, self.span(pool));
+        let value = match self.value {
+            Some((_, expr)) => expr.get(pool).adapted_ty(value, &ty, pool),
+            None => value,
+        };
+        let vty = checker.expect_ty_eq(ty, value.clone(), self.span(pool));
         let name = self.name.get(pool).to_path(pool);
-        match checker.scope().entities_mut().try_push(
-            &name,
-            Entity::new(
-                if self.ty.is_some() || self.value.is_some() {
-                    vty
+        // Variables can't be changed, so ones outside functions can be used 
+        // as constants if their value is known at compile time
+        let const_value = match self.value {
+            Some((_, ref expr)) if checker.return_ty().is_none() && !value.is_unreal() => {
+                expr.eval_const(pool).ok().map(|v| v.adapted(&vty))
+            }
+            _ => None,
+        };
+        checker.declare_variable(&name, Entity::new(
+            if self.ty.is_some() || self.value.is_some() {
+                vty
+            }
+            else {
+                Ty::Undecided(name.to_string(), self.span_or_builtin(pool))
+            },
+            self.span_or_builtin(pool),
+            true
+        ).with_value(const_value));
+        Some(if value.is_never() { Ty::Never } else { Ty::Void })
+    }
+}
+
+/// Declaration of a value known at compile time, like `const SIZE = 4 * 4;`. 
+/// Unlike variables, constants can be used before they are declared
+#[derive(Debug, ParseNode)]
+pub struct ConstDeclNode {
+    const_kw: kw::Const,
+    #[parse(expected = "constant name")]
+    name: Ident,
+    ty: Option<(punct::Colon, TypeExpr)>,
+    #[parse(expected = "'='")]
+    value: (op::Seq, Expr),
+}
+
+impl ResolveNode for ConstDeclNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        let ty = try_resolve_ref!(self.ty, (pool, checker), Some((_, ty)) => ty);
+        let name = self.name.get(pool).to_string();
+        let span = self.span_or_builtin(pool);
+        let prev = checker.start_const(name.clone(), span.clone());
+        let value = self.value.1.try_resolve_ref(pool, checker);
+        checker.end_const(prev);
+        let value = value?;
+        checker.set_const_resolved(&name);
+
+        let value = self.value.1.get(pool).adapted_ty(value, &ty, pool);
+        let vty = checker.expect_ty_eq(ty, value.clone(), self.span(pool));
+        // Don't try to evaluate values that failed to typecheck
+        let const_value = if value.is_unreal() {
+            None
+        }
+        else {
+            match self.value.1.eval_const(pool) {
+                Ok(v) => Some(v.adapted(&vty)),
+                Err(e) => {
+                    checker.logger().lock().unwrap().log(Message::new(
+                        Level::Error,
+                        format!("Value of constant {name} must be a constant expression"),
+                        span.as_ref()
+                    ).note(Note::new_at(e.info, e.span.as_ref())));
+                    None
                 }
-                else {
-                    Ty::Undecided(name.to_string(), self.span_or_builtin(pool))
-                },
-                self.span_or_builtin(pool),
-                true
-            )
-        ) {
-            Ok(_) => {}
-            Err(old) => {
-                let old_span = old.span();
-                checker.logger().lock().unwrap().log(Message::new(
-                    Level::Error,
-                    format!("Item {} has already been defined in this scope", name),
-                    self.span_or_builtin(pool).as_ref()
-                ).note(Note::new_at("Previous definition here", old_span.as_ref())));
             }
+        };
+        let path = path::IdentPath::new([path::Ident::from(name.as_str())], false);
+        if let Err(old) = checker.scope().entities_mut().try_push(
+            &path,
+            Entity::new(vty, span.clone(), false).with_value(const_value)
+        ) {
+            let old_span = old.span();
+            checker.logger().lock().unwrap().log(Message::new(
+                Level::Error,
+                format!("Name {name} has already been defined"),
+                span.as_ref()
+            ).note(Note::new_at("Previous definition here", old_span.as_ref())));
+        }
+        Some(Ty::Void)
+    }
+    fn log_unresolved_reason(&self, pool: &NodePool, checker: &Checker, logger: LoggerRef) {
+        let name = self.name.get(pool).to_string();
+        let Some(cycle) = checker.const_cycle(&name) else { return };
+        // The cycle is reported by the constant in it that was declared first
+        if cycle[0].0 != name {
+            return;
+        }
+        let chain = cycle.iter().map(|(n, _)| n.as_str()).chain([name.as_str()]);
+        let mut msg = Message::new(
+            Level::Error,
+            format!("Value of constant {name} depends on itself"),
+            cycle[0].1.as_ref()
+        ).note(Note::new(chain.collect::<Vec<_>>().join(" -> "), false));
+        for (n, span) in cycle.iter().skip(1) {
+            msg = msg.note(Note::new_at(format!("{n} declared here"), span.as_ref()));
         }
+        logger.lock().unwrap().log(msg);
+    }
+}
+
+/// Declaration of variables by taking apart a value, like 
+/// `let (a, b) = pair;` or `let { x, y } = point;`
+#[derive(Debug)]
+pub struct DestructureDeclNode {
+    let_kw: kw::Let,
+    pattern: Pattern,
+    ty: Option<(punct::Colon, TypeExpr)>,
+    value: (op::Seq, Expr),
+}
+pub type DestructureDecl = RefToNode<DestructureDeclNode>;
+
+impl Node for DestructureDeclNode {
+    fn children(&self) -> Vec<&dyn ResolveRef> {
+        vec![&self.let_kw, &self.pattern, &self.ty, &self.value]
+    }
+}
+
+impl ParseNode for DestructureDeclNode {
+    fn parse_node(
+        pool: &mut NodePool,
+        src: Arc<Src>,
+        tokenizer: &mut TokenIterator
+    ) -> Result<NodeID, FatalParseError> {
+        let res = Self {
+            let_kw: ParseRef::parse_ref(pool, src.clone(), tokenizer)?,
+            pattern: ParseRef::parse_ref(pool, src.clone(), tokenizer)?,
+            ty: ParseRef::parse_ref(pool, src.clone(), tokenizer)?,
+            value: ParseRef::parse_ref(pool, src, tokenizer)?,
+        };
+        Ok(pool.add(res))
+    }
+    /// A plain `let name` is a `LetDecl`, so this is only a destructuring 
+    /// declaration if the pattern starts with brackets
+    fn peek(pos: usize, tokenizer: &TokenIterator) -> bool {
+        kw::Let::peek(pos, tokenizer) && pos + 1 < MAX_PEEK_COUNT && tokenizer.peek(pos + 1).is_some_and(
+            |t| matches!(t.kind, TokenKind::Parentheses(_) | TokenKind::Braces(_))
+        )
+    }
+}
+
+impl ResolveNode for DestructureDeclNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        let ty = try_resolve_ref!(self.ty, (pool, checker), Some((_, ty)) => ty);
+        let value = self.value.1.try_resolve_ref(pool, checker)?;
+        let vty = checker.expect_ty_eq(ty, value, self.span(pool));
+        let mut vars = Vec::new();
+        self.pattern.get(pool).bind(&vty, pool, checker, &mut vars);
+        declare_pattern_vars(&vars, checker);
         Some(Ty::Void)
     }
 }
@@ -79,6 +219,7 @@ pub struct FunDeclNode {
     name: Option<IdentPath>,
     params: delim::Parenthesized<SeparatedWithTrailing<FunParam, punct::Comma>>,
     ret_ty: Option<(punct::Arrow, TypeExpr)>,
+    #[parse(expected = "function body")]
     body: delim::Braced<ExprList>,
     #[parse(skip)]
     scope: Option<ScopeID>,
@@ -87,63 +228,474 @@ pub struct FunDeclNode {
 impl ResolveNode for FunDeclNode {
     fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
         let mut params = Vec::new();
-        for param in self.params.get(pool).value.iter() {
+        for (i, param) in self.params.get(pool).value.iter().enumerate() {
+            let span = param.get(pool).span(pool);
             match *param.get(pool) {
                 FunParamNode::NamedParam { name, ty, default_value } => {
-                    let span = param.get(pool).span(pool);
                     let ty = ty.1.try_resolve_ref(pool, checker)?;
                     let v = try_resolve_ref!(default_value, (pool, checker), Some((_, ty)) => ty);
+                    let v = match default_value {
+                        Some((_, value)) => value.get(pool).adapted_ty(v, &ty, pool),
+                        None => v,
+                    };
                     checker.expect_ty_eq(ty.clone(), v, span.clone());
-                    params.push((name.get(pool).to_string(), ty, span.unwrap_or(ArcSpan::builtin())));
+                    params.push(ParamTy {
+                        name: Some(name.get(pool).to_string()),
+                        ty,
+                        has_default: default_value.is_some(),
+                        decl_span: span.unwrap_or(ArcSpan::builtin()),
+                    });
+                }
+                FunParamNode::ThisParam { this_kw: _, ty, _invalid_value: _ } => {
+                    let extends_ty = checker.extends_ty().cloned();
+                    let ty = match ty {
+                        Some((_, ty)) => ty.try_resolve_ref(pool, checker)?,
+                        None => extends_ty.clone().unwrap_or(Ty::Invalid),
+                    };
+                    let span = span.unwrap_or(ArcSpan::builtin());
+                    if extends_ty.is_none() {
+                        checker.logger().lock().unwrap().log(Message::new(
+                            Level::Error,
+                            "Only methods declared in an extends block can have a 'this' parameter",
+                            span.as_ref()
+                        ));
+                    }
+                    else if i != 0 {
+                        checker.logger().lock().unwrap().log(Message::new(
+                            Level::Error,
+                            "The 'this' parameter must come first",
+                            span.as_ref()
+                        ));
+                    }
+                    params.push(ParamTy {
+                        name: Some(String::from("this")),
+                        ty,
+                        has_default: false,
+                        decl_span: span,
+                    });
                 }
-                FunParamNode::ThisParam { this_kw: _, ty, _invalid_value: _ } => todo!()
             }
         }
         let ret_ty = try_resolve_ref!(self.ret_ty, (pool, checker), Some((_, ty)) => ty);
         let body = {
             let _scope = checker.enter_scope(&mut self.scope);
-            for (name, ty, span) in &params {
+            checker.scope().set_return_ty(ret_ty.clone());
+            for param in &params {
+                let name = param.name.as_deref().unwrap_or_default();
                 if let Err(old) = checker.scope().entities_mut().try_push(
-                    &path::IdentPath::new([path::Ident::from(name.as_str())], false),
-                    Entity::new(ty.clone(), self.span_or_builtin(pool), true)
+                    &path::IdentPath::new([path::Ident::from(name)], false),
+                    Entity::new(param.ty.clone(), self.span_or_builtin(pool), true)
                 ) {
                     let old_span = old.span();
                     checker.logger().lock().unwrap().log(Message::new(
                         Level::Error,
                         format!("Parameter {name} defined multiple times"),
-                        span.as_ref()
+                        param.decl_span.as_ref()
                     ).note(Note::new_at("Previous definition here", old_span.as_ref())));
                 }
             }
             self.body.try_resolve_ref(pool, checker)?
         };
+        let body = match self.body.get(pool).value.get(pool).tail(pool) {
+            Some(tail) => tail.get(pool).adapted_ty(body, &ret_ty, pool),
+            None => body,
+        };
         checker.expect_ty_eq(ret_ty.clone(), body.clone(), self.body.get(pool).span(pool));
 
         let fty = Ty::Function {
-            params: params.into_iter().map(|p| (Some(p.0), p.1)).collect(),
+            params,
             ret_ty: ret_ty.into(),
         };
         if let Some(ref name) = self.name.as_ref().map(|n| n.get(pool).to_path(pool)) {
-            if let Err(old) = checker.scope().entities_mut().try_push(
-                name,
-                Entity::new(fty.clone(), self.span_or_builtin(pool), false)
-            ) {
-                let old_span = old.span();
+            checker.declare_function(name, fty.clone(), self.span_or_builtin(pool));
+        }
+        Some(fty)
+    }
+}
+
+/// Resolved signature of a function declared without a body
+struct BodylessSignature {
+    params: Vec<ParamTy>,
+    ret_ty: Ty,
+}
+
+impl BodylessSignature {
+    fn to_fun_ty(&self) -> Ty {
+        Ty::Function {
+            params: self.params.clone(),
+            ret_ty: self.ret_ty.clone().into(),
+        }
+    }
+}
+
+/// Resolve the parameters and return type of a function declaration that has 
+/// no body, like intrinsics and extern functions. `kind` is the plural name 
+/// of the declaration used in diagnostics. If no return type is given, the 
+/// function returns void
+fn resolve_bodyless_signature(
+    params: &delim::Parenthesized<SeparatedWithTrailing<FunParam, punct::Comma>>,
+    ret_ty: &Option<(punct::Arrow, TypeExpr)>,
+    kind: &str,
+    pool: &NodePool,
+    checker: &mut Checker
+) -> Option<BodylessSignature> {
+    let mut res = Vec::new();
+    for param in params.get(pool).value.iter() {
+        let span = param.get(pool).span_or_builtin(pool);
+        match *param.get(pool) {
+            FunParamNode::NamedParam { name, ty, default_value } => {
+                let ty = ty.1.try_resolve_ref(pool, checker)?;
+                if default_value.is_some() {
+                    checker.logger().lock().unwrap().log(Message::new(
+                        Level::Error,
+                        format!("Parameters of {kind} may not have default values"),
+                        span.as_ref()
+                    ));
+                }
+                res.push(ParamTy {
+                    name: Some(name.get(pool).to_string()),
+                    ty,
+                    has_default: false,
+                    decl_span: span,
+                });
+            }
+            FunParamNode::ThisParam { .. } => {
+                checker.logger().lock().unwrap().log(Message::new(
+                    Level::Error,
+                    format!("{} may not have a 'this' parameter", capitalize(kind)),
+                    span.as_ref()
+                ));
+                res.push(ParamTy { name: None, ty: Ty::Invalid, has_default: false, decl_span: span });
+            }
+        }
+    }
+    let ret_ty = match ret_ty {
+        Some((_, ty)) => ty.try_resolve_ref(pool, checker)?,
+        None => Ty::Void,
+    };
+    Some(BodylessSignature { params: res, ret_ty })
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    chars.next().map(|c| c.to_uppercase().chain(chars).collect()).unwrap_or_default()
+}
+
+/// Declaration of a function implemented by the compiler, like 
+/// `compiler_intrinsic fun print(value: string);`
+#[derive(Debug, ParseNode)]
+pub struct IntrinsicDeclNode {
+    intrinsic_kw: kw::CompilerIntrinsic,
+    fun_kw: kw::Fun,
+    #[parse(expected = "function name")]
+    name: IdentPath,
+    params: delim::Parenthesized<SeparatedWithTrailing<FunParam, punct::Comma>>,
+    ret_ty: Option<(punct::Arrow, TypeExpr)>,
+}
+
+impl ResolveNode for IntrinsicDeclNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        let fty = resolve_bodyless_signature(
+            &self.params, &self.ret_ty, "compiler intrinsics", pool, checker
+        )?.to_fun_ty();
+
+        let name = self.name.get(pool).to_path(pool);
+        match find_intrinsic(&name.to_full()) {
+            Some(intrinsic) => if !signature_matches(&fty, &intrinsic.ty) {
                 checker.logger().lock().unwrap().log(Message::new(
                     Level::Error,
-                    format!("Name {} has already been defined", name),
+                    format!("Signature of compiler intrinsic {name} does not match the compiler's"),
                     self.span_or_builtin(pool).as_ref()
-                ).note(Note::new_at("Previous definition here", old_span.as_ref())));
+                ).note(Note::new(format!("Expected {}, got {fty}", intrinsic.ty), false)));
             }
+            None => {
+                checker.logger().lock().unwrap().log(Message::new(
+                    Level::Error,
+                    format!("Unknown compiler intrinsic {name}"),
+                    self.name.get(pool).span_or_builtin(pool).as_ref()
+                ));
+            }
+        }
+        checker.declare_function(&name, fty.clone(), self.span_or_builtin(pool));
+        Some(fty)
+    }
+}
+
+/// Declaration of a foreign function, like 
+/// `extern fun puts(s: string) -> int;`
+#[derive(Debug, ParseNode)]
+pub struct ExternDeclNode {
+    extern_kw: kw::Extern,
+    fun_kw: kw::Fun,
+    #[parse(expected = "function name")]
+    name: IdentPath,
+    params: delim::Parenthesized<SeparatedWithTrailing<FunParam, punct::Comma>>,
+    ret_ty: Option<(punct::Arrow, TypeExpr)>,
+}
+
+impl ResolveNode for ExternDeclNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        let sig = resolve_bodyless_signature(
+            &self.params, &self.ret_ty, "extern functions", pool, checker
+        )?;
+        let ffi_error = |ty: &Ty, what: &str, span: ArcSpan| {
+            checker.logger().lock().unwrap().log(Message::new(
+                Level::Error,
+                format!("Type {ty} can not be used as {what} of an extern function"),
+                span.as_ref()
+            ).note(Note::new(
+                "Only int, float, bool and string values can be passed to \
+                and from foreign functions", true
+            )));
+        };
+        for param in &sig.params {
+            if !param.ty.is_ffi_safe() {
+                ffi_error(&param.ty, "a parameter", param.decl_span.clone());
+            }
+        }
+        // Functions that don't return anything are fine too
+        if !sig.ret_ty.is_ffi_safe() && !matches!(sig.ret_ty.reduce(), Ty::Void | Ty::Never) {
+            ffi_error(
+                &sig.ret_ty, "the return type",
+                self.ret_ty.as_ref().map(|(_, ty)| ty.get(pool).span_or_builtin(pool))
+                    .unwrap_or(self.span_or_builtin(pool))
+            );
         }
+        let fty = sig.to_fun_ty();
+        checker.declare_function(&self.name.get(pool).to_path(pool), fty.clone(), self.span_or_builtin(pool));
         Some(fty)
     }
 }
 
+/// Declaration of another name for a type, like `type Name = string;`, or 
+/// of a new type, like `type Meters = new int;`
+/// 
+/// An alias can be implicitly converted to and from the type it names. A 
+/// new type can not, and is instead converted using the functions 
+/// `Meters(value)` and `Meters::value(meters)` that come with it
+#[derive(Debug, ParseNode)]
+pub struct TypeAliasDeclNode {
+    type_kw: kw::Type,
+    #[parse(expected = "type name")]
+    name: IdentPath,
+    #[parse(expected = "'='")]
+    eq: op::Seq,
+    new_kw: Option<kw::New>,
+    #[parse(expected = "type")]
+    ty: TypeExpr,
+}
+
+impl ResolveNode for TypeAliasDeclNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        let name = self.name.get(pool).to_path(pool);
+        let Some(ty) = self.ty.try_resolve_ref(pool, checker) else {
+            checker.set_type_alias_pending(
+                name.to_string(), self.ty.get(pool).referenced_name(pool)
+            );
+            return None;
+        };
+        checker.set_type_alias_resolved(&name.to_string());
+        let span = self.span_or_builtin(pool);
+        let new_ty = if self.new_kw.is_some() {
+            Ty::Named { name: name.to_string(), ty: ty.clone().into(), decl_span: span.clone() }
+        }
+        else {
+            Ty::Alias { name: name.to_string(), ty: ty.clone().into(), decl_span: span.clone() }
+        };
+        if let Err(old) = checker.scope().types_mut().try_push(&name, new_ty.clone()) {
+            let old_span = old.span();
+            checker.logger().lock().unwrap().log(Message::new(
+                Level::Error,
+                format!("Type {} has already been defined", name),
+                span.as_ref()
+            ).note(Note::new_at("Previous definition here", old_span.as_ref())));
+            return Some(Ty::Void);
+        }
+        if self.new_kw.is_some() {
+            let convert = |from: &Ty, to: &Ty| Ty::Function {
+                params: vec![ParamTy::new(Some("value"), from.clone())],
+                ret_ty: to.clone().into(),
+            };
+            checker.declare_function(&name, convert(&ty, &new_ty), span.clone());
+            checker.declare_function(&name.join_ident(path::Ident::from("value")), convert(&new_ty, &ty), span);
+        }
+        Some(Ty::Void)
+    }
+    fn log_unresolved_reason(&self, pool: &NodePool, checker: &Checker, logger: LoggerRef) {
+        let name = self.name.get(pool).to_path(pool).to_string();
+        if let Some(cycle) = checker.type_alias_cycle(&name) {
+            logger.lock().unwrap().log(Message::new(
+                Level::Error,
+                format!("Type alias {name} is defined in terms of itself"),
+                self.span_or_builtin(pool).as_ref()
+            ).note(Note::new(
+                format!("{} -> {name}", cycle.join(" -> ")), false
+            )));
+        }
+    }
+}
+
+/// Declaration of methods for a type, like 
+/// `extends string { fun shout(this) -> string { this + "!" } }`
+/// 
+/// Methods are added as functions in the namespace of the type, so the 
+/// above declares `string::shout`, which can be called as `"hi".shout()`
+#[derive(Debug, ParseNode)]
+pub struct ExtendsNode {
+    extends_kw: kw::Extends,
+    #[parse(expected = "type")]
+    ty: TypeExpr,
+    #[parse(expected = "method declarations")]
+    methods: delim::Braced<Vec<Attributed<FunDecl>>>,
+}
+
+impl ResolveNode for ExtendsNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        let ty = self.ty.try_resolve_ref(pool, checker)?.reduce().clone();
+        let namespace = ty.display(TyDisplay::qualified()).to_string().split("::").map(path::Ident::from).collect::<Vec<_>>();
+        for name in &namespace {
+            checker.enter_namespace(name.clone());
+        }
+        let prev = checker.set_extends_ty(Some(ty));
+        let res = self.methods.try_resolve_ref(pool, checker);
+        checker.set_extends_ty(prev);
+        for _ in &namespace {
+            checker.leave_namespace();
+        }
+        res.map(|_| Ty::Void)
+    }
+}
+
+/// Assertion evaluated at compile time, like `static_assert(1 + 1 == 2)`
+#[derive(Debug, ParseNode)]
+pub struct StaticAssertNode {
+    static_assert_kw: kw::StaticAssert,
+    #[parse(expected = "assertion condition")]
+    args: delim::Parenthesized<AssertArgs>,
+}
+
+impl ResolveNode for StaticAssertNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        self.args.try_resolve_ref(pool, checker)?;
+        let args = self.args.get(pool);
+        let args = args.value.get(pool);
+        // Don't try to evaluate expressions that failed to typecheck
+        if !args.is_well_typed(pool) {
+            return Some(Ty::Void);
+        }
+        let span = self.span_or_builtin(pool);
+        match args.eval_const(pool) {
+            Ok((ConstValue::Bool(false), message)) => {
+                checker.logger().lock().unwrap().log(Message::new(
+                    Level::Error,
+                    match message {
+                        Some(ConstValue::String(message)) => format!("Static assertion failed: {message}"),
+                        _ => String::from("Static assertion failed"),
+                    },
+                    span.as_ref()
+                ));
+            }
+            Ok(_) => {}
+            Err(e) => {
+                checker.logger().lock().unwrap().log(Message::new(
+                    Level::Error,
+                    "Arguments of static_assert must be constant expressions",
+                    span.as_ref()
+                ).note(Note::new_at(e.info, e.span.as_ref())));
+            }
+        }
+        Some(Ty::Void)
+    }
+}
+
+/// A field of a struct declaration, like `x: int`
+#[derive(Debug, ParseNode)]
+pub struct FieldDeclNode {
+    #[parse(expected = "field name")]
+    name: Ident,
+    ty: (punct::Colon, TypeExpr),
+}
+
+impl ResolveNode for FieldDeclNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        self.ty.1.try_resolve_ref(pool, checker)
+    }
+}
+
+/// Declaration of a type with named fields, like 
+/// `struct Point { x: int, y: int }`. Also declares the constructor 
+/// `Point(x, y)` and an accessor like `Point::x(point)` for every field
+#[derive(Debug, ParseNode)]
+pub struct StructDeclNode {
+    struct_kw: kw::Struct,
+    #[parse(expected = "struct name")]
+    name: IdentPath,
+    #[parse(expected = "struct fields")]
+    fields: delim::Braced<SeparatedWithTrailing<FieldDecl, punct::Comma>>,
+}
+
+impl ResolveNode for StructDeclNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        let name = self.name.get(pool).to_path(pool);
+        let tys = self.fields.get(pool).value.iter()
+            .map(|field| field.try_resolve_ref(pool, checker))
+            .collect::<Option<Vec<_>>>()?;
+        let mut fields: Vec<(String, Ty)> = Vec::new();
+        for (field, ty) in self.fields.get(pool).value.iter().zip(tys) {
+            let field_name = field.get(pool).name.get(pool).to_string();
+            if fields.iter().any(|(n, _)| *n == field_name) {
+                checker.logger().lock().unwrap().log(Message::new(
+                    Level::Error,
+                    format!("Field {field_name} defined multiple times"),
+                    field.get(pool).span_or_builtin(pool).as_ref()
+                ));
+                continue;
+            }
+            fields.push((field_name, ty));
+        }
+
+        let span = self.span_or_builtin(pool);
+        let struct_ty = Ty::Struct { name: name.to_string(), fields: fields.clone(), decl_span: span.clone() };
+        if let Err(old) = checker.scope().types_mut().try_push(&name, struct_ty.clone()) {
+            let old_span = old.span();
+            checker.logger().lock().unwrap().log(Message::new(
+                Level::Error,
+                format!("Type {} has already been defined", name),
+                span.as_ref()
+            ).note(Note::new_at("Previous definition here", old_span.as_ref())));
+            return Some(Ty::Void);
+        }
+        let constructor = Ty::Function {
+            params: fields.iter().map(|(n, ty)| ParamTy::new(Some(n), ty.clone())).collect(),
+            ret_ty: struct_ty.clone().into(),
+        };
+        checker.declare_function(&name, constructor, span.clone());
+        for (field_name, ty) in fields {
+            let accessor = Ty::Function {
+                params: vec![ParamTy::new(Some("value"), struct_ty.clone())],
+                ret_ty: ty.into(),
+            };
+            checker.declare_function(
+                &name.join_ident(path::Ident::from(field_name.as_str())),
+                accessor, span.clone()
+            );
+        }
+        Some(Ty::Void)
+    }
+}
+
 #[derive(Debug, ParseNode, ResolveNode)]
 #[parse(expected = "item declaration")]
 pub enum DeclNode {
+    DestructureDecl(DestructureDecl),
     LetDecl(LetDecl),
+    ConstDecl(ConstDecl),
+    StructDecl(StructDecl),
     FunDecl(FunDecl),
+    IntrinsicDecl(IntrinsicDecl),
+    ExternDecl(ExternDecl),
+    TypeAliasDecl(TypeAliasDecl),
+    Extends(Extends),
+    StaticAssert(StaticAssert),
 }
 
@@ -6,19 +6,27 @@ use crate::{
     parser::{
         parse::{
             Separated, ParseNode, FatalParseError, ParseNodeFn,
-            RefToNode, NodePool, Node, ParseRef, NodeID
+            RefToNode, NodePool, Node, ParseRef, NodeID, Ref
         },
-        tokenizer::TokenIterator
+        tokenizer::{TokenIterator, TokenKind}
     },
-    shared::src::Src,
-    checker::{resolve::{ResolveNode, ResolveRef}, coherency::{Checker, ScopeID}, ty::Ty, path}, try_resolve_list
+    shared::{src::Src, similar::closest_match},
+    checker::{
+        resolve::{ResolveNode, ResolveRef}, coherency::{Checker, ScopeID}, ty::Ty, path,
+        consteval::{EvalConst, ConstValue, ConstError}
+    },
+    try_resolve_list
 };
 use super::{
     decl::Decl,
-    token::{Ident, punct::{self, TerminatingSemicolon}, op::{Prec, self}, delim},
-    atom::Atom,
+    token::{Ident, lit, punct::{self, TerminatingSemicolon}, op::{Prec, self}, delim},
+    atom::{Atom, AtomNode},
+    attr::Attributed,
     flow::Flow,
-    ops::{BinOp, UnOp, Call, Index, CallNode, IndexNode, UnOpNode, BinOpNode}
+    ops::{
+        BinOp, UnOp, Call, MethodCall, Index, Pipe,
+        CallNode, MethodCallNode, IndexNode, UnOpNode, BinOpNode, PipeNode
+    }
 };
 
 #[derive(Debug, ParseNode)]
@@ -28,6 +36,15 @@ pub enum IdentComponentNode {
     Ident(Ident),
 }
 
+impl IdentComponentNode {
+    pub(crate) fn to_ident(&self, pool: &NodePool) -> path::Ident {
+        path::Ident::from(match self {
+            Self::Ident(i) => i.get(pool).to_string(),
+            Self::Attribute(_, i) => format!("@{}", i.get(pool)),
+        })
+    }
+}
+
 impl ResolveNode for IdentComponentNode {
     fn try_resolve_node(&mut self, _: &NodePool, _: &mut Checker) -> Option<Ty> {
         Some(Ty::Invalid)
@@ -43,10 +60,7 @@ pub struct IdentPathNode {
 impl IdentPathNode {
     pub(crate) fn to_path(&self, pool: &NodePool) -> path::IdentPath {
         path::IdentPath::new(
-            self.path.iter().map(|i| path::Ident::from(match *i.get(pool) {
-                IdentComponentNode::Ident(i) => i.get(pool).to_string(),
-                IdentComponentNode::Attribute(_, i) => format!("@{}", i.get(pool)),
-            })).collect::<Vec<_>>(),
+            self.path.iter().map(|i| i.get(pool).to_ident(pool)).collect::<Vec<_>>(),
             self.absolute.is_some()
         )
     }
@@ -66,39 +80,88 @@ pub enum ScalarExprNode {
     Atom(Atom),
 }
 
+impl EvalConst for ScalarExprNode {
+    fn eval_const(&self, pool: &NodePool) -> Result<ConstValue, ConstError> {
+        match self {
+            Self::Atom(atom) => atom.eval_const(pool),
+            _ => Err(ConstError::not_const(self, pool)),
+        }
+    }
+}
+
 #[derive(Debug, ResolveNode)]
 pub enum ExprNode {
     BinOp(BinOp),
+    Pipe(Pipe),
     UnOp(UnOp),
     Call(Call),
+    MethodCall(MethodCall),
     Index(Index),
     Scalar(ScalarExpr),
 }
 pub type Expr = RefToNode<ExprNode>;
 
 impl ExprNode {
+    /// Whether this expression is just the literal `true`
+    pub(crate) fn is_true_literal(&self, pool: &NodePool) -> bool {
+        let Self::Scalar(scalar) = self else { return false };
+        let ScalarExprNode::Atom(atom) = *scalar.get(pool) else { return false };
+        let AtomNode::Bool(b) = *atom.get(pool) else { return false };
+        let is_true = matches!(*b.get(pool), lit::BoolNode::True(_));
+        is_true
+    }
+    /// Whether this expression is an int literal without a suffix, like `1` 
+    /// or `-1`. Literals explicitly typed as ints, like `1i`, don't count
+    pub(crate) fn is_int_literal(&self, pool: &NodePool) -> bool {
+        match self {
+            Self::UnOp(unop) => unop.get(pool).signed(pool).is_some_and(|e| e.get(pool).is_int_literal(pool)),
+            Self::Scalar(scalar) => {
+                let ScalarExprNode::Atom(atom) = *scalar.get(pool) else { return false };
+                let AtomNode::Int(int) = *atom.get(pool) else { return false };
+                !int.get(pool).has_suffix()
+            }
+            _ => false,
+        }
+    }
+    /// The type of this expression, which has type `ty`, where a value of 
+    /// type `expected` is expected. Int literals are adapted to floats, so 
+    /// `let x: float = 1` is fine
+    pub(crate) fn adapted_ty(&self, ty: Ty, expected: &Ty, pool: &NodePool) -> Ty {
+        if ty == Ty::Int && *expected.reduce() == Ty::Float && self.is_int_literal(pool) {
+            Ty::Float
+        }
+        else {
+            ty
+        }
+    }
     fn parse_postfix(
         pool: &mut NodePool,
         src: Arc<Src>,
         tokenizer: &mut TokenIterator
     ) -> Result<NodeID, FatalParseError> {
         let mut expr = Self::Scalar(ParseRef::parse_ref(pool, src.clone(), tokenizer)?);
-        loop {
-            if delim::Parenthesized::<delim::P>::peek(0, tokenizer) {
-                expr = Self::Call(RefToNode::new_raw(
-                    CallNode::parse_with(RefToNode::new_raw(pool.add(expr)), pool, src.clone(), tokenizer)?
-                ));
+        let mut links = 0;
+        let res: Result<NodeID, FatalParseError> = (|| loop {
+            let call = delim::Parenthesized::<delim::P>::peek(0, tokenizer);
+            let method = punct::Dot::peek(0, tokenizer) || punct::SafeDot::peek(0, tokenizer);
+            if !call && !method && !delim::Bracketed::<delim::P>::peek(0, tokenizer) {
+                return Ok(pool.add(expr));
             }
-            else if delim::Bracketed::<delim::P>::peek(0, tokenizer) {
-                expr = Self::Index(RefToNode::new_raw(
-                    IndexNode::parse_with(RefToNode::new_raw(pool.add(expr)), pool, src.clone(), tokenizer)?
-                ));
+            tokenizer.enter_chain_link()?;
+            links += 1;
+            let target = RefToNode::new_raw(pool.add(expr));
+            expr = if call {
+                Self::Call(RefToNode::new_raw(CallNode::parse_with(target, pool, src.clone(), tokenizer)?))
             }
-            else {
-                break;
+            else if method {
+                Self::MethodCall(RefToNode::new_raw(MethodCallNode::parse_with(target, pool, src.clone(), tokenizer)?))
             }
-        }
-        Ok(pool.add(expr))
+            else {
+                Self::Index(RefToNode::new_raw(IndexNode::parse_with(target, pool, src.clone(), tokenizer)?))
+            };
+        })();
+        tokenizer.exit_chain(links);
+        res
     }
     fn parse_unop(
         pool: &mut NodePool,
@@ -107,14 +170,35 @@ impl ExprNode {
     ) -> Result<NodeID, FatalParseError> {
         if op::Unary::peek(0, tokenizer) {
             let res = Self::UnOp(RefToNode::new_raw(
-                UnOpNode::parse_with(Self::parse_postfix, pool, src, tokenizer)?
+                UnOpNode::parse_with(Self::parse_pow, pool, src, tokenizer)?
             ));
             Ok(pool.add(res))
         }
         else {
-            Self::parse_postfix(pool, src, tokenizer)
+            Self::parse_pow(pool, src, tokenizer)
         }
     }
+    /// Exponentiation binds tighter than unary operators, so `-2 ** 2` is 
+    /// `-(2 ** 2)`, and is right-associative, so `2 ** 3 ** 2` is 
+    /// `2 ** (3 ** 2)`. The exponent may have a unary operator, like 
+    /// `2 ** -1`
+    fn parse_pow(
+        pool: &mut NodePool,
+        src: Arc<Src>,
+        tokenizer: &mut TokenIterator
+    ) -> Result<NodeID, FatalParseError> {
+        let base = Self::parse_postfix(pool, src.clone(), tokenizer)?;
+        if !op::Pow::peek(0, tokenizer) {
+            return Ok(base);
+        }
+        // The exponent is parsed recursively, so it counts toward the nesting 
+        // depth like a link of a chain
+        tokenizer.enter_chain_link()?;
+        let res = BinOpNode::parse_with(RefToNode::new_raw(base), Self::parse_unop, pool, src, tokenizer);
+        tokenizer.exit_chain(1);
+        let res = Self::BinOp(RefToNode::new_raw(res?));
+        Ok(pool.add(res))
+    }
     fn parse_binop_prec<F>(
         prec: Prec, sides: &mut F,
         pool: &mut NodePool, src: Arc<Src>, tokenizer: &mut TokenIterator
@@ -122,16 +206,33 @@ impl ExprNode {
         where F: ParseNodeFn
     {
         let mut lhs = sides(pool, src.clone(), tokenizer)?;
-        while prec.peek(tokenizer) {
-            let bop = Self::BinOp(RefToNode::new_raw(
-                BinOpNode::parse_with(
-                    RefToNode::new_raw(lhs),
-                    &mut *sides, pool, src.clone(), tokenizer
-                )?
-            ));
-            lhs = pool.add(bop);
-        }
-        Ok(lhs)
+        let mut links = 0;
+        let res: Result<NodeID, FatalParseError> = (|| {
+            while prec.peek(tokenizer) {
+                tokenizer.enter_chain_link()?;
+                links += 1;
+                if prec == Prec::Pipe {
+                    let pipe = Self::Pipe(RefToNode::new_raw(
+                        PipeNode::parse_with(
+                            RefToNode::new_raw(lhs),
+                            &mut *sides, pool, src.clone(), tokenizer
+                        )?
+                    ));
+                    lhs = pool.add(pipe);
+                    continue;
+                }
+                let bop = Self::BinOp(RefToNode::new_raw(
+                    BinOpNode::parse_with(
+                        RefToNode::new_raw(lhs),
+                        &mut *sides, pool, src.clone(), tokenizer
+                    )?
+                ));
+                lhs = pool.add(bop);
+            }
+            Ok(lhs)
+        })();
+        tokenizer.exit_chain(links);
+        res
     }
 }
 
@@ -139,14 +240,28 @@ impl Node for ExprNode {
     fn children(&self) -> Vec<&dyn ResolveRef> {
         match self {
             Self::BinOp(binop) => vec![binop],
+            Self::Pipe(pipe) => vec![pipe],
             Self::UnOp(unop) => vec![unop],
             Self::Call(call) => vec![call],
+            Self::MethodCall(call) => vec![call],
             Self::Index(index) => vec![index],
             Self::Scalar(scalar) => vec![scalar],
         }
     }
 }
 
+impl EvalConst for ExprNode {
+    fn eval_const(&self, pool: &NodePool) -> Result<ConstValue, ConstError> {
+        match self {
+            Self::BinOp(binop) => binop.eval_const(pool),
+            Self::UnOp(unop) => unop.eval_const(pool),
+            Self::MethodCall(call) => call.eval_const(pool),
+            Self::Scalar(scalar) => scalar.eval_const(pool),
+            _ => Err(ConstError::not_const(self, pool)),
+        }
+    }
+}
+
 impl ParseNode for ExprNode {
     fn parse_node(
         pool: &mut NodePool,
@@ -167,17 +282,91 @@ impl ParseNode for ExprNode {
     }
 }
 
+/// An expression in a list of statements. A statement that is only an 
+/// identifier but is followed by more code, like `whille x > 0 { ... }`, 
+/// likely starts with a misspelled keyword, which the error about the 
+/// missing semicolon then points out
+#[derive(Debug)]
+pub struct Statement(Attributed<Expr>);
+
+impl std::ops::Deref for Statement {
+    type Target = Attributed<Expr>;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Ref for Statement {
+    fn ids(&self) -> Vec<NodeID> {
+        self.0.ids()
+    }
+}
+
+impl ParseRef for Statement {
+    fn parse_ref(pool: &mut NodePool, src: Arc<Src>, tokenizer: &mut TokenIterator) -> Result<Self, FatalParseError> {
+        let first = tokenizer.peek(0)
+            .filter(|t| matches!(t.kind, TokenKind::Ident))
+            .map(|t| (t.raw.to_string(), t.span.1.clone()));
+        let expr = Attributed::parse_ref(pool, src, tokenizer)?;
+        if let Some((word, range)) = first {
+            let continues = tokenizer.peek(0).is_some() && !punct::Semicolon::peek(0, tokenizer);
+            if continues && tokenizer.last_token_end() == range.end {
+                if let Some(keyword) = closest_match(&word, tokenizer.config().statement_keywords()) {
+                    let keyword = keyword.to_string();
+                    tokenizer.set_keyword_typo(keyword, range);
+                }
+            }
+        }
+        Ok(Self(expr))
+    }
+    fn peek(pos: usize, tokenizer: &TokenIterator) -> bool {
+        Attributed::<Expr>::peek(pos, tokenizer)
+    }
+}
+
+impl ResolveRef for Statement {
+    fn try_resolve_ref(&self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        self.0.try_resolve_ref(pool, checker)
+    }
+}
+
 #[derive(Debug, ParseNode)]
 pub struct ExprListNode {
-    exprs: Vec<(Expr, TerminatingSemicolon)>,
+    exprs: Vec<(Statement, TerminatingSemicolon)>,
     #[parse(skip)]
     scope: Option<ScopeID>,
 }
 
+impl ExprListNode {
+    /// The scope of this list's contents, if it has been checked
+    pub(crate) fn scope(&self) -> Option<ScopeID> {
+        self.scope
+    }
+    /// The last expression, if it gives the value of the list because it 
+    /// isn't followed by a semicolon
+    pub(crate) fn tail(&self, pool: &NodePool) -> Option<Expr> {
+        self.exprs.last()
+            .filter(|(_, semi)| !semi.get(pool).has_semicolon())
+            .map(|(e, _)| e.item)
+    }
+}
+
 impl ResolveNode for ExprListNode {
     fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
         let _handle = checker.enter_scope(&mut self.scope);
         let tys = try_resolve_list!(&self.exprs, (pool, checker), (e, c) => e => (e, c));
+        checker.lint_unused_variables();
+        // Everything after an expression that never finishes is unreachable, 
+        // and so is the end of the list
+        if let Some(i) = tys.iter().position(|(e, _)| e.is_never()) {
+            if let Some((next, _)) = self.exprs.get(i + 1) {
+                checker.lint_unreachable(
+                    &next.item.get(pool).span_or_builtin(pool),
+                    &self.exprs[i].0.item.get(pool).span_or_builtin(pool)
+                );
+            }
+            return Some(Ty::Never);
+        }
         if let Some((e, c)) = tys.into_iter().last() {
             if !c.get(pool).has_semicolon() {
                 return Some(e);
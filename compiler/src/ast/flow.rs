@@ -1,26 +1,127 @@
 
 use dash_macros::{ParseNode, ResolveNode};
 use crate::{
-    parser::parse::{Separated, SeparatedWithTrailing, Node, NodePool},
-    checker::{resolve::{ResolveNode, ResolveRef}, ty::Ty, coherency::Checker}, try_resolve_ref
+    parser::parse::{Separated, SeparatedWithTrailing, BeforeBlock, WithoutBitOr, Node, NodePool},
+    checker::{
+        resolve::{ResolveNode, ResolveRef}, ty::{Ty, TyDisplay}, coherency::{Checker, ScopeID, EnclosingLoop},
+        entity::Entity, path,
+        consteval::{EvalConst, ConstValue, ConstError}
+    },
+    shared::{src::ArcSpan, logger::{Message, Level, Note, LoggerRef}, similar::closest_match, ice::Ice},
+    try_resolve_ref
 };
-use super::{token::{kw, delim, punct}, expr::{Expr, ExprList, IdentComponent}};
+use super::{
+    token::{kw, delim, punct, op, Ident, Label}, expr::{Expr, ExprNode, ExprList, IdentComponent},
+    pattern::{Pattern, PatternVar, declare_pattern_vars}
+};
+
+/// Condition like `let x = maybe()` that only holds if the optional value 
+/// on the right is not `none`, binding its contents to the pattern
+#[derive(Debug, ParseNode)]
+pub struct IfLetNode {
+    let_kw: kw::Let,
+    pattern: Pattern,
+    #[parse(expected = "'='")]
+    value: (op::Seq, BeforeBlock<Expr>),
+    #[parse(skip)]
+    vars: Vec<PatternVar>,
+}
+
+impl ResolveNode for IfLetNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        let value = self.value.1.try_resolve_ref(pool, checker)?;
+        let ty = match value.reduce() {
+            Ty::Option { ty } => ty.as_ref().clone(),
+            other if other.is_unreal() => Ty::Invalid,
+            _ => {
+                checker.logger().lock().unwrap().log(Message::new(
+                    Level::Error,
+                    format!("Expected an optional value in 'if let', got {value}"),
+                    self.value.1.get(pool).span_or_builtin(pool).as_ref()
+                ));
+                Ty::Invalid
+            }
+        };
+        self.pattern.get(pool).bind(&ty, pool, checker, &mut self.vars);
+        declare_pattern_vars(&self.vars, checker);
+        Some(if value.is_never() { Ty::Never } else { Ty::Bool })
+    }
+}
+
+#[derive(Debug, ParseNode)]
+#[parse(expected = "condition")]
+pub enum IfCondNode {
+    Let(IfLet),
+    Expr(BeforeBlock<Expr>),
+}
+
+/// Resolve the condition of an `if` or `while`. An assignment like `x = 5` 
+/// there is most likely a typo of `x == 5`, and is checked as one
+fn resolve_cond(cond: &BeforeBlock<Expr>, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+    if let ExprNode::BinOp(binop) = *cond.get(pool) {
+        if binop.get(pool).is_assignment(pool) {
+            checker.set_assignment_in_condition();
+        }
+    }
+    let ty = cond.try_resolve_ref(pool, checker);
+    checker.take_assignment_in_condition();
+    ty
+}
+
+impl ResolveNode for IfCondNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        match self {
+            Self::Let(l) => l.try_resolve_ref(pool, checker),
+            Self::Expr(e) => {
+                let cond = resolve_cond(e, pool, checker)?;
+                let never = cond.is_never();
+                checker.expect_ty_eq(Ty::Bool, cond, e.get(pool).span(pool));
+                Some(if never { Ty::Never } else { Ty::Bool })
+            }
+        }
+    }
+}
 
+/// A conditional like `if cond { ... } else { ... }`. Its value is that of 
+/// the branch taken, so the type is the merged type of both branches, or 
+/// `never` if the condition never finishes. With an `if let` condition, the 
+/// variables it binds are only visible in the first branch
 #[derive(Debug, ParseNode)]
 pub struct IfNode {
     if_kw: kw::If,
-    cond: Expr,
+    cond: IfCond,
     truthy: delim::Braced<ExprList>,
     falsy: Option<(kw::Else, Else)>,
+    #[parse(skip)]
+    scope: Option<ScopeID>,
 }
 
 impl ResolveNode for IfNode {
     fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
-        let cond = self.cond.try_resolve_ref(pool, checker)?;
-        let truthy = self.truthy.try_resolve_ref(pool, checker)?;
+        let (cond, truthy) = {
+            let _scope = checker.enter_scope(&mut self.scope);
+            let cond = self.cond.try_resolve_ref(pool, checker)?;
+            // Variables are dropped when leaving the scope, so the ones bound 
+            // by the condition have to be declared again on every pass
+            if let IfCondNode::Let(l) = &*self.cond.get(pool) {
+                for (name, ty, span) in &l.get(pool).vars {
+                    let _ = checker.scope().entities_mut().try_push(
+                        &path::IdentPath::new([path::Ident::from(name.as_str())], false),
+                        Entity::new(ty.clone(), span.clone(), true)
+                    );
+                }
+            }
+            (cond, self.truthy.try_resolve_ref(pool, checker)?)
+        };
         let falsy = try_resolve_ref!(self.falsy, (pool, checker), Some((_, e)) => e);
-        checker.expect_ty_eq(cond, Ty::Bool, self.cond.get(pool).span(pool));
-        checker.expect_ty_eq(truthy, falsy, self.span(pool)).into()
+        let falsy_span = self.falsy.map(|(_, e)| e.get(pool).span_or_builtin(pool));
+        let ty = checker.merge_branch_tys(
+            "if",
+            (truthy, self.truthy.get(pool).span(pool)),
+            (falsy, falsy_span),
+            self.span(pool)
+        );
+        Some(if cond.is_never() { Ty::Never } else { ty })
     }
 }
 
@@ -40,10 +141,456 @@ pub struct ReturnNode {
 impl ResolveNode for ReturnNode {
     fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
         let expr = try_resolve_ref!(self.expr, (pool, checker), Some(e) => e);
+        check_in_defer("return", false, &self.span_or_builtin(pool), checker);
+        Some(Ty::Never)
+    }
+}
+
+/// The values an arm of a `switch` matches, like `1 | 2`, or `else` for 
+/// everything not matched by the other arms
+#[derive(Debug, ParseNode)]
+#[parse(expected = "switch case")]
+pub enum SwitchCaseNode {
+    Else(kw::Else),
+    Values(Separated<WithoutBitOr<Expr>, punct::Pipe>),
+}
+
+impl ResolveNode for SwitchCaseNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        if let Self::Values(values) = self {
+            values.try_resolve_ref(pool, checker)?;
+        }
+        Some(Ty::Invalid)
+    }
+}
+
+#[derive(Debug, ParseNode)]
+pub struct SwitchArmNode {
+    case: SwitchCase,
+    #[parse(expected = "'=>'")]
+    arrow: punct::FatArrow,
+    #[parse(expected = "expression")]
+    value: Expr,
+}
+
+impl ResolveNode for SwitchArmNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        self.case.try_resolve_ref(pool, checker)?;
+        self.value.try_resolve_ref(pool, checker)
+    }
+}
+
+/// Picks an arm by comparing an int or string against constant cases, like 
+/// `switch n { 1 | 2 => "few", else => "many" }`. The value of the switch is 
+/// that of the arm picked, so without an `else` arm it has no value
+#[derive(Debug, ParseNode)]
+pub struct SwitchNode {
+    switch_kw: kw::Switch,
+    value: BeforeBlock<Expr>,
+    #[parse(expected = "switch arms")]
+    arms: delim::Braced<SeparatedWithTrailing<SwitchArm, punct::Comma>>,
+}
+
+impl SwitchNode {
+    /// Check that the values of a case are distinct constants of type `ty`, 
+    /// adding them to `seen`
+    fn check_case(
+        values: &Separated<WithoutBitOr<Expr>, punct::Pipe>,
+        ty: &Ty,
+        seen: &mut Vec<(ConstValue, ArcSpan)>,
+        pool: &NodePool,
+        checker: &Checker
+    ) {
+        for value in values.iter() {
+            let span = value.get(pool).span_or_builtin(pool);
+            let Some(value_ty) = value.resolved_ty(pool) else { continue };
+            checker.expect_ty_eq(ty.clone(), value_ty.clone(), Some(span.clone()));
+            // Don't try to evaluate cases that failed to typecheck
+            if ty.is_unreal() || value_ty.is_unreal() || !value_ty.convertible(ty) {
+                continue;
+            }
+            match value.eval_const(pool) {
+                Ok(value) => match seen.iter().find(|(v, _)| *v == value) {
+                    Some((_, prev)) => {
+                        checker.logger().lock().unwrap().log(Message::new(
+                            Level::Error,
+                            format!("Duplicate switch case {value}"),
+                            span.as_ref()
+                        ).note(Note::new_at("Previous case here", prev.as_ref())));
+                    }
+                    None => seen.push((value, span)),
+                }
+                Err(e) => {
+                    checker.logger().lock().unwrap().log(Message::new(
+                        Level::Error,
+                        "Switch cases must be constant expressions",
+                        span.as_ref()
+                    ).note(Note::new_at(e.info, e.span.as_ref())));
+                }
+            }
+        }
+    }
+}
+
+impl ResolveNode for SwitchNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        let value = self.value.try_resolve_ref(pool, checker)?;
+        self.arms.try_resolve_ref(pool, checker)?;
+        let ty = match value.reduce() {
+            Ty::Int | Ty::String => value.clone(),
+            other => {
+                if !other.is_unreal() {
+                    checker.logger().lock().unwrap().log(Message::new(
+                        Level::Error,
+                        format!("Cannot switch on type {value}"),
+                        self.value.get(pool).span_or_builtin(pool).as_ref()
+                    ).note(Note::new("Only int and string values can be switched on", true)));
+                }
+                Ty::Invalid
+            }
+        };
+        let mut seen = Vec::new();
+        let mut else_span: Option<ArcSpan> = None;
+        let mut arm_tys = Vec::new();
+        for arm in self.arms.get(pool).value.iter() {
+            let arm = arm.get(pool);
+            match &*arm.case.get(pool) {
+                SwitchCaseNode::Else(kw) => {
+                    let span = kw.get(pool).span_or_builtin(pool);
+                    match &else_span {
+                        Some(prev) => {
+                            checker.logger().lock().unwrap().log(Message::new(
+                                Level::Error,
+                                "Switch has multiple else arms",
+                                span.as_ref()
+                            ).note(Note::new_at("Previous else arm here", prev.as_ref())));
+                        }
+                        None => else_span = Some(span),
+                    }
+                }
+                SwitchCaseNode::Values(values) => Self::check_case(values, &ty, &mut seen, pool, checker),
+            }
+            arm_tys.push((arm.value.resolved_ty(pool)?, arm.value.get(pool).span(pool)));
+        }
+        if value.is_never() {
+            return Some(Ty::Never);
+        }
+        if else_span.is_none() {
+            return Some(Ty::Void);
+        }
+        let mut arm_tys = arm_tys.into_iter();
+        let (mut res, mut res_span) = arm_tys.next()?;
+        for (ty, span) in arm_tys {
+            let picked_new = !ty.convertible(&res);
+            res = checker.merge_branch_tys("switch", (res, res_span.clone()), (ty, span.clone()), self.span(pool));
+            if picked_new {
+                res_span = span;
+            }
+        }
+        Some(res)
+    }
+}
+
+/// A loop like `while cond { ... }`. A `while true` loop can only be exited 
+/// through `break`, so it gets the type of the values given to its `break`s, 
+/// or `never` if it has none
+#[derive(Debug, ParseNode)]
+pub struct WhileNode {
+    while_kw: kw::While,
+    cond: BeforeBlock<Expr>,
+    #[parse(expected = "loop body")]
+    body: delim::Braced<ExprList>,
+    #[parse(skip)]
+    scope: Option<ScopeID>,
+}
+
+impl ResolveNode for WhileNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        let label = checker.take_next_loop_label();
+        let cond = resolve_cond(&self.cond, pool, checker)?;
+        let cond_never = cond.is_never();
+        checker.expect_ty_eq(Ty::Bool, cond, self.cond.get(pool).span(pool));
+        let infinite = self.cond.get(pool).is_true_literal(pool);
+        let span = self.while_kw.get(pool).span_or_builtin(pool);
+        let _scope = checker.enter_scope(&mut self.scope);
+        checker.scope().set_loop(span, infinite, label);
+        self.body.try_resolve_ref(pool, checker)?;
+        checker.lint_unused_label();
+        if cond_never {
+            return Some(Ty::Never);
+        }
+        if !infinite {
+            return Some(Ty::Void);
+        }
+        let breaks = checker.scope().loop_breaks().to_vec();
+        let mut breaks = breaks.into_iter();
+        let Some((ty, _)) = breaks.next() else {
+            return Some(Ty::Never);
+        };
+        Some(breaks.fold(ty, |ty, (b, span)| checker.expect_ty_eq(ty, b, Some(span))))
+    }
+}
+
+/// A loop over the items of a value, like `for c in "abc" { ... }`
+#[derive(Debug, ParseNode)]
+pub struct ForNode {
+    for_kw: kw::For,
+    #[parse(expected = "variable name")]
+    name: Ident,
+    #[parse(expected = "'in'")]
+    in_kw: kw::In,
+    iter: BeforeBlock<Expr>,
+    #[parse(expected = "loop body")]
+    body: delim::Braced<ExprList>,
+    #[parse(skip)]
+    scope: Option<ScopeID>,
+}
+
+impl ResolveNode for ForNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        let label = checker.take_next_loop_label();
+        let iter = self.iter.try_resolve_ref(pool, checker)?;
+        let item = match checker.iter_item(&iter) {
+            Some(item) => item,
+            None => {
+                if !iter.is_unreal() {
+                    checker.logger().lock().unwrap().log(Message::new(
+                        Level::Error,
+                        format!("Cannot iterate over type {iter}"),
+                        self.iter.get(pool).span_or_builtin(pool).as_ref()
+                    ).note(Note::new(
+                        format!(
+                            "Declare a function {}::next(value: {iter}) -> Item? to make it iterable",
+                            iter.display(TyDisplay::qualified())
+                        ),
+                        true
+                    )));
+                }
+                Ty::Invalid
+            }
+        };
+        let span = self.for_kw.get(pool).span_or_builtin(pool);
+        let name = self.name.get(pool).to_string();
+        let _scope = checker.enter_scope(&mut self.scope);
+        checker.scope().set_loop(span, false, label);
+        // The scope is new on every pass since variables are dropped when 
+        // leaving it, so this can't clash with anything
+        let _ = checker.scope().entities_mut().try_push(
+            &path::IdentPath::new([path::Ident::from(name)], false),
+            Entity::new(item, self.name.get(pool).span_or_builtin(pool), true)
+        );
+        self.body.try_resolve_ref(pool, checker)?;
+        checker.lint_unused_label();
+        Some(if iter.is_never() { Ty::Never } else { Ty::Void })
+    }
+}
+
+#[derive(Debug, ParseNode, ResolveNode)]
+#[parse(expected = "loop")]
+pub enum LoopNode {
+    While(While),
+    For(For),
+}
+
+/// A loop with a name, like `'outer: for x in xs { ... }`, so `break` and 
+/// `continue` in nested loops can refer to it
+#[derive(Debug, ParseNode)]
+pub struct LabeledLoopNode {
+    label: Label,
+    colon: punct::Colon,
+    #[parse(expected = "loop")]
+    body: Loop,
+}
+
+impl ResolveNode for LabeledLoopNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        let label = self.label.get(pool);
+        checker.set_next_loop_label(label.to_string(), label.span_or_builtin(pool));
+        self.body.try_resolve_ref(pool, checker)
+    }
+}
+
+/// Find the loop that a `break` or `continue` refers to, reporting an error 
+/// if there is none
+fn find_loop(what: &str, label: Option<Label>, span: ArcSpan, pool: &NodePool, checker: &mut Checker) -> Option<EnclosingLoop> {
+    if check_in_defer(what, true, &span, checker) {
+        return None;
+    }
+    let Some(label) = label else {
+        let lp = checker.enclosing_loop(None);
+        if lp.is_none() {
+            log_outside_loop(what, span, checker);
+        }
+        return lp;
+    };
+    let name = label.get(pool).to_string();
+    if let Some(lp) = checker.enclosing_loop(Some(&name)) {
+        checker.use_loop_label(lp.scope);
+        return Some(lp);
+    }
+    if checker.enclosing_loop(None).is_none() {
+        log_outside_loop(what, span, checker);
+        return None;
+    }
+    let label_span = label.get(pool).span_or_builtin(pool);
+    let mut msg = Message::new(Level::Error, format!("Unknown label {name}"), label_span.as_ref());
+    let labels = checker.loop_labels();
+    if let Some(similar) = closest_match(&name, labels.iter().map(String::as_str)) {
+        msg = msg.note(Note::new(format!("Did you mean {similar}?"), true));
+    }
+    checker.logger().lock().unwrap().log(msg);
+    None
+}
+
+/// Runs an expression when the enclosing scope is exited, like 
+/// `defer close(file);`. Deferred expressions run in the reverse order they 
+/// were declared in, and can't leave the scope on their own
+#[derive(Debug, ParseNode)]
+pub struct DeferNode {
+    defer_kw: kw::Defer,
+    #[parse(expected = "expression")]
+    expr: Expr,
+    #[parse(skip)]
+    scope: Option<ScopeID>,
+}
+
+impl ResolveNode for DeferNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        let span = self.defer_kw.get(pool).span_or_builtin(pool);
+        let _scope = checker.enter_scope(&mut self.scope);
+        checker.scope().set_defer(span);
+        self.expr.try_resolve_ref(pool, checker)?;
+        Some(Ty::Void)
+    }
+}
+
+/// Report an error and return true if `what` is used inside a `defer`, 
+/// which would make it leave the scope the `defer` is running in
+fn check_in_defer(what: &str, inside_loop: bool, span: &ArcSpan, checker: &Checker) -> bool {
+    let Some(defer) = checker.enclosing_defer(inside_loop) else {
+        return false;
+    };
+    checker.logger().lock().unwrap().log(Message::new(
+        Level::Error,
+        format!("Cannot use {what} inside defer"),
+        span.as_ref()
+    ).note(Note::new_at("Deferred here", defer.as_ref())));
+    true
+}
+
+fn log_outside_loop(what: &str, span: ArcSpan, checker: &Checker) {
+    let mut msg = Message::new(
+        Level::Error,
+        format!("Cannot use {what} outside of a loop"),
+        span.as_ref()
+    );
+    if checker.return_ty().is_some() {
+        msg = msg.note(Note::new(
+            "Loops outside the enclosing function can not be exited from inside it; \
+            use return to exit the function instead", true
+        ));
+    }
+    checker.logger().lock().unwrap().log(msg);
+}
+
+/// Exits the innermost loop, or the loop with the given label, optionally 
+/// giving it a value
+#[derive(Debug, ParseNode)]
+pub struct BreakNode {
+    break_kw: kw::Break,
+    label: Option<Label>,
+    value: Option<Expr>,
+}
+
+impl ResolveNode for BreakNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        let value = try_resolve_ref!(self.value, (pool, checker), Some(e) => e else Ty::Void);
+        let span = self.span_or_builtin(pool);
+        match find_loop("break", self.label, span.clone(), pool, checker) {
+            Some(lp) if self.value.is_some() && !lp.allow_break_value => {
+                checker.logger().lock().unwrap().log(Message::new(
+                    Level::Error,
+                    "break can only have a value inside 'while true' loops",
+                    span.as_ref()
+                ).note(Note::new_at("Enclosing loop here", lp.span.as_ref())));
+            }
+            Some(lp) => checker.add_loop_break(lp.scope, value, span),
+            None => {}
+        }
+        Some(Ty::Never)
+    }
+}
+
+/// Skips to the next iteration of the innermost loop, or the loop with the 
+/// given label
+#[derive(Debug, ParseNode)]
+pub struct ContinueNode {
+    continue_kw: kw::Continue,
+    label: Option<Label>,
+}
+
+impl ResolveNode for ContinueNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        find_loop("continue", self.label, self.span_or_builtin(pool), pool, checker);
         Some(Ty::Never)
     }
 }
 
+/// Unwraps a union value, like `try parse(text)`. The first type of the 
+/// union is the value of the expression, and the rest are errors that are 
+/// returned from the enclosing function, so its return type has to be able 
+/// to hold all of them
+#[derive(Debug, ParseNode)]
+pub struct TryNode {
+    try_kw: kw::Try,
+    #[parse(expected = "expression")]
+    expr: Expr,
+}
+
+impl ResolveNode for TryNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        let ty = self.expr.try_resolve_ref(pool, checker)?;
+        if ty.is_unreal() {
+            return Some(Ty::Invalid);
+        }
+        let span = self.span_or_builtin(pool);
+        let Ty::Union(members) = ty.reduce() else {
+            checker.logger().lock().unwrap().log(Message::new(
+                Level::Error,
+                format!("Cannot use try on type {ty}"),
+                span.as_ref()
+            ).note(Note::new(
+                "try expects a union type like 'int | Error', where the first \
+                type is the successful value and the rest are errors", false
+            )));
+            return Some(Ty::Invalid);
+        };
+        let Some(ret_ty) = checker.return_ty() else {
+            checker.logger().lock().unwrap().log(Message::new(
+                Level::Error,
+                "try can only be used inside a function",
+                span.as_ref()
+            ));
+            return Some(members[0].clone());
+        };
+        for err in &members[1..] {
+            if !err.convertible(&ret_ty) {
+                checker.logger().lock().unwrap().log(Message::new(
+                    Level::Error,
+                    format!("Error type {err} is not handled"),
+                    span.as_ref()
+                ).note(Note::new(
+                    format!("The enclosing function returns {ret_ty}, which can not hold {err}"),
+                    false
+                )).note(Note::new(
+                    format!("Add {err} to the function's return type"), true
+                )));
+            }
+        }
+        Some(members[0].clone())
+    }
+}
+
 #[derive(Debug, ParseNode)]
 #[parse(expected = "identifier")]
 enum UsingComponentNode {
@@ -51,6 +598,16 @@ enum UsingComponentNode {
     Single(IdentComponent),
 }
 
+impl UsingComponentNode {
+    /// The paths this component stands for, along with their spans
+    fn expand(&self, pool: &NodePool) -> Vec<(Vec<path::Ident>, ArcSpan)> {
+        match self {
+            Self::Single(i) => vec![(vec![i.get(pool).to_ident(pool)], i.get(pool).span_or_builtin(pool))],
+            Self::Multi(items) => items.get(pool).value.iter().flat_map(|c| c.get(pool).expand(pool)).collect(),
+        }
+    }
+}
+
 impl ResolveNode for UsingComponentNode {
     fn try_resolve_node(&mut self, _: &NodePool, _: &mut Checker) -> Option<Ty> {
         Some(Ty::Invalid)
@@ -63,21 +620,184 @@ struct UsingPathNode {
     path: Separated<UsingComponent, punct::Namespace>,
 }
 
+impl UsingPathNode {
+    /// The paths of the items imported, like `a::b` and `a::c` for 
+    /// `a::{b, c}`, along with the spans of their last components
+    fn targets(&self, pool: &NodePool) -> Vec<(path::IdentPath, ArcSpan)> {
+        let mut targets = vec![(Vec::new(), self.span_or_builtin(pool))];
+        for component in self.path.iter() {
+            let tails = component.get(pool).expand(pool);
+            targets = targets.into_iter()
+                .flat_map(|(head, _)| tails.iter().map(move |(tail, span)| {
+                    (head.iter().chain(tail).cloned().collect::<Vec<_>>(), span.clone())
+                }))
+                .collect();
+        }
+        targets.into_iter()
+            .map(|(p, span)| (path::IdentPath::new(p, self.absolute.is_some()), span))
+            .collect()
+    }
+}
+
 impl ResolveNode for UsingPathNode {
     fn try_resolve_node(&mut self, _: &NodePool, _: &mut Checker) -> Option<Ty> {
         Some(Ty::Invalid)
     }
 }
 
+/// Find the full name of the type or entity `path` refers to
+fn find_import_target(path: &path::IdentPath, checker: &Checker) -> Option<path::FullIdentPath> {
+    checker.scopes().find_map(|s| {
+        s.entities().find_with_path(path).map(|(p, _)| p.clone())
+            .or_else(|| s.types().find_with_path(path).map(|(p, _)| p.clone()))
+    })
+}
+
+/// Brings items into scope under their own name, like `using string::len;`, 
+/// or under another one, like `using string::len as strlen;`. Items 
+/// imported with `public using` are also visible to the files after this one
 #[derive(Debug, ParseNode)]
 pub struct UsingNode {
+    public_kw: Option<kw::Public>,
     using_kw: kw::Using,
+    #[parse(expected = "path")]
     path: UsingPath,
+    alias: Option<(kw::As, Ident)>,
+}
+
+impl UsingNode {
+    /// The paths of the items imported and the names they are imported as, 
+    /// along with the spans of the imports
+    fn imports(&self, pool: &NodePool) -> Vec<(path::IdentPath, path::Ident, ArcSpan)> {
+        let targets = self.path.get(pool).targets(pool);
+        let alias = self.alias.filter(|_| targets.len() == 1).map(|(_, alias)| alias.get(pool).to_string());
+        targets.into_iter()
+            .map(|(target, span)| {
+                let name = match alias {
+                    Some(ref alias) => path::Ident::from(alias.as_str()),
+                    None => target.last().cloned().ice_at("import has an empty path", &span),
+                };
+                (target, name, span)
+            })
+            .collect()
+    }
 }
 
 impl ResolveNode for UsingNode {
     fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
-        todo!()
+        let mut targets = Vec::new();
+        for (target, name, span) in self.imports(pool) {
+            match find_import_target(&target, checker) {
+                Some(full) => {
+                    checker.set_import_resolved(&name.to_string());
+                    targets.push((full, name, span));
+                }
+                None => checker.set_import_pending(name.to_string(), target.to_string(), span),
+            }
+        }
+        if targets.len() != self.imports(pool).len() {
+            return None;
+        }
+        let public = self.public_kw.is_some();
+        if let Some(kw) = self.public_kw.filter(|_| checker.return_ty().is_some()) {
+            checker.logger().lock().unwrap().log(Message::new(
+                Level::Error,
+                "Only imports outside functions can be public",
+                kw.get(pool).span_or_builtin(pool).as_ref()
+            ));
+        }
+        if let Some((_, alias)) = self.alias.filter(|_| targets.len() > 1) {
+            checker.logger().lock().unwrap().log(Message::new(
+                Level::Error,
+                "Cannot import multiple items under one name",
+                alias.get(pool).span_or_builtin(pool).as_ref()
+            ));
+        }
+        for (target, name, span) in targets {
+            checker.import(&target, &path::IdentPath::new([name], false), span, public);
+        }
+        Some(Ty::Void)
+    }
+    fn log_unresolved_reason(&self, pool: &NodePool, checker: &Checker, logger: LoggerRef) {
+        let imports = self.imports(pool);
+        let Some((target, name, span)) = imports.iter()
+            .find(|(target, _, _)| find_import_target(target, checker).is_none())
+            else { return };
+        let Some(cycle) = checker.import_cycle(&name.to_string()) else {
+            logger.lock().unwrap().log(Message::new(
+                Level::Error,
+                format!("Unknown item {target}"),
+                span.as_ref()
+            ));
+            return;
+        };
+        // The cycle is reported by the import in it that was declared first
+        if cycle[0].0 != name.to_string() {
+            return;
+        }
+        let chain = cycle.iter().map(|(n, _)| n.as_str()).chain([cycle[0].0.as_str()]);
+        let mut msg = Message::new(
+            Level::Error,
+            format!("Import of {name} depends on itself"),
+            span.as_ref()
+        ).note(Note::new(chain.collect::<Vec<_>>().join(" -> "), false));
+        for (n, span) in cycle.iter().skip(1) {
+            msg = msg.note(Note::new_at(format!("{n} imported here"), span.as_ref()));
+        }
+        logger.lock().unwrap().log(msg);
+    }
+}
+
+/// The condition and optional message of an `assert` or `static_assert`
+#[derive(Debug, ParseNode)]
+pub struct AssertArgsNode {
+    cond: Expr,
+    message: Option<(punct::Comma, Expr)>,
+}
+
+impl AssertArgsNode {
+    /// Whether the condition is a bool and the message is a string
+    pub(crate) fn is_well_typed(&self, pool: &NodePool) -> bool {
+        self.cond.resolved_ty(pool).is_some_and(|t| *t.reduce() == Ty::Bool) &&
+            self.message.as_ref().is_none_or(|(_, m)| {
+                m.resolved_ty(pool).is_some_and(|t| *t.reduce() == Ty::String)
+            })
+    }
+    /// Evaluate the condition and message at compile time
+    pub(crate) fn eval_const(&self, pool: &NodePool) -> Result<(ConstValue, Option<ConstValue>), ConstError> {
+        let cond = self.cond.eval_const(pool)?;
+        let message = match self.message {
+            Some((_, ref message)) => Some(message.eval_const(pool)?),
+            None => None,
+        };
+        Ok((cond, message))
+    }
+}
+
+impl ResolveNode for AssertArgsNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        let cond = self.cond.try_resolve_ref(pool, checker)?;
+        let message = try_resolve_ref!(self.message, (pool, checker), Some((_, m)) => m else None);
+        checker.expect_ty_eq(Ty::Bool, cond, self.cond.get(pool).span(pool));
+        if let (Some(message), Some((_, expr))) = (message, &self.message) {
+            checker.expect_ty_eq(Ty::String, message, expr.get(pool).span(pool));
+        }
+        Some(Ty::Void)
+    }
+}
+
+/// Assertion checked when the program is run, like `assert(a == b, "oh no")`
+#[derive(Debug, ParseNode)]
+pub struct AssertNode {
+    assert_kw: kw::Assert,
+    #[parse(expected = "assertion condition")]
+    args: delim::Parenthesized<AssertArgs>,
+}
+
+impl ResolveNode for AssertNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        self.args.try_resolve_ref(pool, checker)?;
+        Some(Ty::Void)
     }
 }
 
@@ -85,6 +805,15 @@ impl ResolveNode for UsingNode {
 #[parse(expected = "control flow expression")]
 pub enum FlowNode {
     If(If),
+    Switch(Switch),
+    While(While),
+    For(For),
+    LabeledLoop(LabeledLoop),
+    Defer(Defer),
+    Break(Break),
+    Continue(Continue),
     Return(Return),
+    Try(Try),
+    Assert(Assert),
     Using(Using),
 }
@@ -6,6 +6,8 @@ pub mod expr;
 pub mod ops;
 pub mod atom;
 pub mod flow;
+pub mod attr;
+pub mod pattern;
 
 #[macro_export]
 macro_rules! try_resolve_ref {
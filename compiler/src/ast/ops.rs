@@ -1,16 +1,22 @@
 
-use std::{sync::Arc, collections::HashMap};
+use std::sync::Arc;
 use dash_macros::ParseNode;
 use crate::{
     parser::{parse::{FatalParseError, ParseNodeFn, SeparatedWithTrailing, NodePool, RefToNode, Node, ParseRef, NodeID}, tokenizer::TokenIterator},
-    shared::{src::{Src, ArcSpan}, logger::{Message, Level, Note, LoggerRef}},
-    checker::{resolve::{ResolveNode, ResolveRef}, coherency::Checker, ty::Ty, path}, ice
+    shared::{src::{Src, ArcSpan}, logger::{Message, Level, Note, LoggerRef}, similar::closest_match, ice::Ice},
+    checker::{
+        resolve::{ResolveNode, ResolveRef}, coherency::{Checker, ScopeID}, ty::{Ty, ParamTy}, path,
+        entity::Entity, lint::{UNNECESSARY_SAFE_CALL, ASSIGNMENT_IN_CONDITION},
+        consteval::{EvalConst, ConstValue, ConstError, eval_unary, eval_binary}
+    },
+    ice
 };
-use super::{expr::Expr, token::{op, delim, Ident, punct}};
+use super::{expr::{Expr, ExprNode, ExprList}, token::{op, delim, Ident, punct}};
 
 #[derive(Debug, ParseNode)]
 #[parse(expected = "expression or named argument")]
 pub enum ArgNode {
+    Spread(punct::Spread, Expr),
     Named(Ident, #[parse(peek_point)] punct::Colon, Expr),
     Unnamed(Expr),
 }
@@ -21,10 +27,98 @@ impl ResolveNode for ArgNode {
     }
 }
 
+/// A block passed as the last argument of a call, like the `{ it * 2 }` in 
+/// `items.map { it * 2 }`. The block is a function of the type the last 
+/// parameter expects, and if that function takes a parameter, it is 
+/// called `it` in the block
+#[derive(Debug, ParseNode)]
+pub struct TrailingBlockNode {
+    body: delim::Braced<ExprList>,
+    #[parse(skip)]
+    scope: Option<ScopeID>,
+}
+
+impl ResolveNode for TrailingBlockNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        let expected = checker.take_trailing_block_ty();
+        let span = self.span_or_builtin(pool);
+        // If the block can't be passed to the parameter, its body is still 
+        // checked, with `it` given an invalid type
+        let mut valid = true;
+        let (params, ret_ty) = match expected.as_ref().map(Ty::reduce) {
+            Some(Ty::Function { params, ret_ty }) => (params.as_slice(), Some(ret_ty.as_ref().clone())),
+            Some(other) => {
+                if !other.is_unreal() {
+                    checker.logger().lock().unwrap().log(Message::new(
+                        Level::Error,
+                        format!("Cannot pass a trailing block as a value of type {other}"),
+                        span.as_ref()
+                    ));
+                }
+                valid = false;
+                (&[] as &[ParamTy], None)
+            }
+            None => (&[] as &[ParamTy], None),
+        };
+        if valid && params.len() > 1 {
+            checker.logger().lock().unwrap().log(Message::new(
+                Level::Error,
+                format!("Trailing block can't take {} parameters", params.len()),
+                span.as_ref()
+            ).note(Note::new(
+                format!(
+                    "Expected a function of type {}",
+                    expected.as_ref().ice_at("trailing block has parameters but no expected type", &span)
+                ),
+                false
+            )).note(Note::new("Trailing blocks can only take one parameter, called 'it'", true)));
+            valid = false;
+        }
+        let _scope = checker.enter_scope(&mut self.scope);
+        if let Some(ret_ty) = ret_ty.as_ref().filter(|_| valid) {
+            checker.scope().set_return_ty(ret_ty.clone());
+        }
+        let it_ty = if valid { params.first().map(|p| p.ty.clone()) } else { Some(Ty::Invalid) };
+        if let Some(ty) = it_ty {
+            let _ = checker.scope().entities_mut().try_push(
+                &path::IdentPath::new([path::Ident::from("it")], false),
+                Entity::new(ty, span.clone(), true)
+            );
+        }
+        let body = self.body.try_resolve_ref(pool, checker)?;
+        if !valid {
+            return Some(Ty::Invalid);
+        }
+        match (ret_ty, expected) {
+            (Some(ret_ty), Some(expected)) => {
+                checker.expect_ty_eq(ret_ty, body, self.body.get(pool).value.get(pool).span(pool));
+                Some(expected)
+            }
+            _ => Some(Ty::Function { params: Vec::new(), ret_ty: body.into() }),
+        }
+    }
+}
+
+/// Parse the trailing block of a call, unless the call is followed by a 
+/// block of its own, see `BeforeBlock`
+fn parse_trailing_block(
+    pool: &mut NodePool,
+    src: Arc<Src>,
+    tokenizer: &mut TokenIterator
+) -> Result<Option<TrailingBlock>, FatalParseError> {
+    if tokenizer.allow_trailing_blocks() {
+        ParseRef::parse_ref(pool, src, tokenizer)
+    }
+    else {
+        Ok(None)
+    }
+}
+
 #[derive(Debug)]
 pub struct CallNode {
     target: Expr,
     args: delim::Parenthesized<SeparatedWithTrailing<Arg, punct::Comma>>,
+    trailing: Option<TrailingBlock>,
 }
 pub type Call = RefToNode<CallNode>;
 
@@ -37,7 +131,8 @@ impl CallNode {
     ) -> Result<NodeID, FatalParseError> {
         let res = Self {
             target,
-            args: ParseRef::parse_ref(pool, src, tokenizer)?,
+            args: ParseRef::parse_ref(pool, src.clone(), tokenizer)?,
+            trailing: parse_trailing_block(pool, src, tokenizer)?,
         };
         Ok(pool.add(res))
     }
@@ -45,118 +140,608 @@ impl CallNode {
 
 impl Node for CallNode {
     fn children(&self) -> Vec<&dyn ResolveRef> {
-        vec![&self.target, &self.args]
+        vec![&self.target, &self.args, &self.trailing]
     }
 }
 
-impl ResolveNode for CallNode {
-    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
-        let target = self.target.try_resolve_ref(pool, checker)?;
-        let args = self.args.get(pool).value.iter()
-            .map(|arg| match *arg.get(pool) {
-                ArgNode::Unnamed(value) => {
-                    (None, value.try_resolve_ref(pool, checker), value.get(pool).span(pool))
-                }
-                ArgNode::Named(name, _, value) => {
-                    (Some(name.get(pool).to_string()), value.try_resolve_ref(pool, checker), value.get(pool).span(pool))
-                }
+/// An argument passed to a function, with its value resolved
+struct ResolvedArg {
+    name: Option<String>,
+    ty: Ty,
+    span: Option<ArcSpan>,
+    /// Whether the argument is an array spread over the remaining parameters, 
+    /// like `...args`
+    spread: bool,
+    /// Whether the argument is an int literal, which can be passed as a float
+    int_literal: bool,
+}
+
+impl ResolvedArg {
+    /// The type of the argument when passed to a parameter of type `param`
+    fn ty_for(&self, param: &Ty) -> Ty {
+        if self.int_literal && self.ty == Ty::Int && *param.reduce() == Ty::Float {
+            Ty::Float
+        }
+        else {
+            self.ty.clone()
+        }
+    }
+}
+
+/// Resolve the arguments of a call
+fn resolve_args(
+    args: &delim::Parenthesized<SeparatedWithTrailing<Arg, punct::Comma>>,
+    pool: &NodePool,
+    checker: &mut Checker
+) -> Option<Vec<ResolvedArg>> {
+    args.get(pool).value.iter()
+        .map(|arg| {
+            let (name, value, spread) = match *arg.get(pool) {
+                ArgNode::Spread(_, value) => (None, value, true),
+                ArgNode::Unnamed(value) => (None, value, false),
+                ArgNode::Named(name, _, value) => (Some(name.get(pool).to_string()), value, false),
+            };
+            Some(ResolvedArg {
+                name,
+                ty: value.try_resolve_ref(pool, checker)?,
+                span: if spread { arg.get(pool).span(pool) } else { value.get(pool).span(pool) },
+                spread,
+                int_literal: value.get(pool).is_int_literal(pool),
             })
-            .map(|(a, e, s)| e.map(|e| (a, e, s)))
-            .collect::<Option<Vec<_>>>()?;
-        match target {
-            Ty::Function { params, ret_ty } => {
-                let mut arg_ix = 0usize;
-                let mut encountered_named = None;
-                let mut passed: HashMap<String, ArcSpan> = HashMap::new();
-                for (name, ty, span) in &args {
-                    if let Some(name) = name {
-                        encountered_named = Some(span.clone());
-                        match passed.get(name) {
-                            Some(old) => {
-                                checker.logger().lock().unwrap().log(Message::new(
-                                    Level::Error,
-                                    format!("Parameter '{name}' has already been passed"),
-                                    span.clone().unwrap_or(ArcSpan::builtin()).as_ref()
-                                ).note(Note::new_at(
-                                    "Previous passing here",
-                                    old.as_ref()
-                                )));
-                            }
-                            None => {
-                                match params.iter().find(|p| p.0.as_ref() == Some(name)) {
-                                    Some((_, pty)) => {
-                                        checker.expect_ty_eq(ty.clone(), pty.clone(), span.clone());
-                                    }
-                                    None => {
-                                        checker.logger().lock().unwrap().log(Message::new(
-                                            Level::Error,
-                                            format!("Unknown parameter '{name}'"),
-                                            span.clone().unwrap_or(ArcSpan::builtin()).as_ref()
-                                        ));
-                                    }
-                                }
-                                passed.insert(name.clone(), span.clone().unwrap_or(ArcSpan::builtin()));
-                            }
-                        }
-                    }
-                    else {
-                        match encountered_named.clone() {
-                            Some(e_span) => {
-                                checker.logger().lock().unwrap().log(Message::new(
-                                    Level::Error,
-                                    "Cannot pass positional arguments after named arguments \
-                                    have been passed",
-                                    span.clone().unwrap_or(ArcSpan::builtin()).as_ref()
-                                ).note(Note::hint(
-                                    "Move this named argument to the end of the arguments pool",
-                                    e_span.unwrap_or(ArcSpan::builtin()).as_ref()
-                                )));
-                            }
-                            None => {
-                                match params.get(arg_ix) {
-                                    Some((name, pty)) => {
-                                        if let Some(name) = name {
-                                            passed.insert(name.clone(), span.clone().unwrap_or(ArcSpan::builtin()));
-                                        }
-                                        checker.expect_ty_eq(ty.clone(), pty.clone(), span.clone());
-                                    }
-                                    None => {
-                                        checker.logger().lock().unwrap().log(Message::new(
-                                            Level::Error,
-                                            "Too many positional arguments",
-                                            span.clone().unwrap_or(ArcSpan::builtin()).as_ref()
-                                        ).note(Note::new(format!(
-                                            "Function has only {} parameters, but {} were passed",
-                                            params.len(), args.len()
-                                        ), false)));
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    arg_ix += 1;
+        })
+        .collect::<Option<Vec<_>>>()
+}
+
+/// Check resolved arguments against the parameters of the function being 
+/// called. `call_span` is the span of the whole call
+fn check_args(
+    params: &[ParamTy],
+    args: &[ResolvedArg],
+    call_span: Option<ArcSpan>,
+    checker: &mut Checker
+) {
+    let call_span = call_span.unwrap_or(ArcSpan::builtin());
+    // The span of the argument passed to each parameter
+    let mut passed: Vec<Option<ArcSpan>> = vec![None; params.len()];
+    let mut positional_ix = 0usize;
+    let mut encountered_named: Option<ArcSpan> = None;
+    let mut encountered_spread = None;
+    for arg @ ResolvedArg { name, ty, span, spread, int_literal: _ } in args {
+        let span = span.clone().unwrap_or(ArcSpan::builtin());
+        if *spread {
+            if let Some(ref e_span) = encountered_named {
+                checker.logger().lock().unwrap().log(Message::new(
+                    Level::Error,
+                    "Cannot spread arguments after named arguments have been passed",
+                    span.as_ref()
+                ).note(Note::hint(
+                    "Move this named argument to the end of the arguments pool",
+                    e_span.as_ref()
+                )));
+                continue;
+            }
+            let item = match ty.reduce() {
+                Ty::Array(item) => item.as_ref().clone(),
+                other if other.is_unreal() => Ty::Invalid,
+                other => {
+                    checker.logger().lock().unwrap().log(Message::new(
+                        Level::Error,
+                        format!("Cannot spread value of type {other}"),
+                        span.as_ref()
+                    ).note(Note::new("Only arrays can be spread", false)));
+                    Ty::Invalid
+                }
+            };
+            // The spread value fills all the positional parameters left. Only 
+            // the first mismatch is reported, as the rest would be duplicates
+            let mut mismatched = false;
+            for (param, passed) in params.iter().zip(passed.iter_mut()).skip(positional_ix) {
+                if !mismatched && !item.convertible(&param.ty) {
+                    checker.expect_ty_eq(param.ty.clone(), item.clone(), Some(span.clone()));
+                    mismatched = true;
                 }
-                if arg_ix < params.len() {
+                *passed = Some(span.clone());
+            }
+            positional_ix = params.len();
+            encountered_spread = Some(span);
+            continue;
+        }
+        let param_ix = if let Some(name) = name {
+            encountered_named = Some(span.clone());
+            match params.iter().position(|p| p.name.as_ref() == Some(name)) {
+                Some(ix) => ix,
+                None => {
                     checker.logger().lock().unwrap().log(Message::new(
                         Level::Error,
-                        "Missing arguments",
-                        self.span(pool).unwrap_or(ArcSpan::builtin()).as_ref()
-                    ).note(Note::new(format!(
-                        "Function has {} parameters, but only {} were passed",
-                        params.len(), args.len()
-                    ), false)));
+                        format!("Unknown parameter '{name}'"),
+                        span.as_ref()
+                    ));
+                    continue;
                 }
-                Some(ret_ty.as_ref().clone())
             }
-            other => {
+        }
+        else {
+            if let Some(ref e_span) = encountered_named {
                 checker.logger().lock().unwrap().log(Message::new(
                     Level::Error,
-                    format!("Cannot call an expression of type {other}"),
-                    self.span(pool).unwrap_or(ArcSpan::builtin()).as_ref()
-                ));
-                Some(Ty::Invalid)
+                    "Cannot pass positional arguments after named arguments \
+                    have been passed",
+                    span.as_ref()
+                ).note(Note::hint(
+                    "Move this named argument to the end of the arguments pool",
+                    e_span.as_ref()
+                )));
+                continue;
+            }
+            if let Some(ref s_span) = encountered_spread {
+                checker.logger().lock().unwrap().log(Message::new(
+                    Level::Error,
+                    "Cannot pass positional arguments after a spread argument",
+                    span.as_ref()
+                ).note(Note::new_at(
+                    "Spread argument here",
+                    s_span.as_ref()
+                )));
+                continue;
+            }
+            positional_ix += 1;
+            if positional_ix > params.len() {
+                checker.logger().lock().unwrap().log(Message::new(
+                    Level::Error,
+                    "Too many positional arguments",
+                    span.as_ref()
+                ).note(Note::new(format!(
+                    "Function has only {} parameters, but {} were passed",
+                    params.len(), args.len()
+                ), false)));
+                continue;
             }
+            positional_ix - 1
+        };
+        if let Some(ref old) = passed[param_ix] {
+            checker.logger().lock().unwrap().log(Message::new(
+                Level::Error,
+                format!("Parameter '{}' has already been passed", params[param_ix].name.as_deref().unwrap_or_default()),
+                span.as_ref()
+            ).note(Note::new_at(
+                "Previous passing here",
+                old.as_ref()
+            )));
+            continue;
+        }
+        checker.expect_ty_eq(params[param_ix].ty.clone(), arg.ty_for(&params[param_ix].ty), Some(span.clone()));
+        passed[param_ix] = Some(span);
+    }
+    for (param, passed) in params.iter().zip(passed) {
+        if passed.is_some() || param.has_default {
+            continue;
+        }
+        let msg = match param.name {
+            // Parameters of intrinsics aren't declared anywhere in source
+            Some(ref name) if param.decl_span.is_builtin() => Message::new(
+                Level::Error,
+                format!("Missing argument for parameter '{name}'"),
+                call_span.as_ref()
+            ),
+            Some(ref name) => Message::new(
+                Level::Error,
+                format!("Missing argument for parameter '{name}'"),
+                call_span.as_ref()
+            ).note(Note::new_at("Parameter declared here", param.decl_span.as_ref())),
+            None => Message::new(
+                Level::Error,
+                "Missing arguments",
+                call_span.as_ref()
+            ).note(Note::new(format!(
+                "Function has {} parameters, but only {} were passed",
+                params.len(), args.len()
+            ), false)),
+        };
+        checker.logger().lock().unwrap().log(msg);
+    }
+}
+
+/// Whether the arguments can be passed to the parameters. With `exact`, the 
+/// arguments must have exactly the types of the parameters instead of just 
+/// being convertible to them. Mistakes in the order of the arguments are 
+/// left for `check_args` to report
+fn args_fit(params: &[ParamTy], args: &[ResolvedArg], exact: bool) -> bool {
+    let fits = |ty: &Ty, param: &ParamTy| if exact {
+        ty.reduce() == param.ty.reduce()
+    }
+    else {
+        ty.convertible(&param.ty)
+    };
+    let mut passed = vec![false; params.len()];
+    let mut positional_ix = 0usize;
+    for arg in args {
+        if arg.spread {
+            let Ty::Array(item) = arg.ty.reduce() else {
+                return false;
+            };
+            for (param, passed) in params.iter().zip(passed.iter_mut()).skip(positional_ix) {
+                if !fits(item, param) {
+                    return false;
+                }
+                *passed = true;
+            }
+            positional_ix = params.len();
+            continue;
         }
+        let param_ix = match arg.name {
+            Some(ref name) => match params.iter().position(|p| p.name.as_ref() == Some(name)) {
+                Some(ix) => ix,
+                None => return false,
+            },
+            None => {
+                positional_ix += 1;
+                positional_ix - 1
+            }
+        };
+        if param_ix >= params.len() || passed[param_ix] {
+            return false;
+        }
+        // Int literals only fit float parameters when the types don't have 
+        // to be exact, so that an overload taking an int is picked over one 
+        // taking a float
+        let ty = if exact { arg.ty.clone() } else { arg.ty_for(&params[param_ix].ty) };
+        if !fits(&ty, &params[param_ix]) {
+            return false;
+        }
+        passed[param_ix] = true;
+    }
+    params.iter().zip(passed).all(|(param, passed)| passed || param.has_default)
+}
+
+/// Pick the function of an overload set that the arguments fit. If they fit 
+/// many, the one whose parameters have exactly the types of the arguments 
+/// is picked. With `trailing`, the call has a trailing block, which fits a 
+/// last parameter that takes a function. Returns the parameters and return 
+/// type of the function
+fn pick_overload(
+    name: &str,
+    overloads: &[(Ty, ArcSpan)],
+    args: &[ResolvedArg],
+    trailing: bool,
+    call_span: Option<ArcSpan>,
+    checker: &mut Checker
+) -> Option<(Vec<ParamTy>, Ty)> {
+    // Any function would fit an argument that failed to resolve
+    if args.iter().any(|a| a.ty.is_unreal()) {
+        return None;
+    }
+    let fits = |params: &[ParamTy], exact| match params.split_last() {
+        Some((last, params)) if trailing => {
+            matches!(last.ty.reduce(), Ty::Function { .. }) && args_fit(params, args, exact)
+        }
+        _ => !trailing && args_fit(params, args, exact),
+    };
+    let fitting = |exact| overloads.iter()
+        .filter(|(ty, _)| matches!(ty, Ty::Function { params, .. } if fits(params, exact)))
+        .collect::<Vec<_>>();
+    let (fitting, exact) = (fitting(false), fitting(true));
+    if let ([(Ty::Function { params, ret_ty }, _)], _) | (_, [(Ty::Function { params, ret_ty }, _)]) =
+        (fitting.as_slice(), exact.as_slice())
+    {
+        return Some((params.clone(), ret_ty.as_ref().clone()));
+    }
+    let arg_tys = args.iter()
+        .map(|a| a.ty.to_string())
+        .chain(trailing.then(|| String::from("trailing block")))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let call_span = call_span.unwrap_or_default();
+    let (msg, candidates) = if fitting.is_empty() {
+        (format!("No overload of {name} takes arguments ({arg_tys})"), overloads.iter().collect())
+    }
+    else {
+        (format!("Ambiguous call to {name} with arguments ({arg_tys})"), fitting)
+    };
+    let mut msg = Message::new(Level::Error, msg, call_span.as_ref());
+    for (ty, span) in candidates {
+        msg = msg.note(Note::new_at(format!("Candidate {ty}"), span.as_ref()));
+    }
+    checker.logger().lock().unwrap().log(msg);
+    None
+}
+
+/// Check the arguments and trailing block of a call against the parameters 
+/// of the function being called. The trailing block is passed to the last 
+/// parameter. Returns `None` if the trailing block could not be resolved yet
+fn check_call(
+    params: &[ParamTy],
+    args: &[ResolvedArg],
+    trailing: Option<&TrailingBlock>,
+    call_span: Option<ArcSpan>,
+    pool: &NodePool,
+    checker: &mut Checker
+) -> Option<()> {
+    let Some(trailing) = trailing else {
+        check_args(params, args, call_span, checker);
+        return Some(());
+    };
+    let Some((last, params)) = params.split_last() else {
+        checker.logger().lock().unwrap().log(Message::new(
+            Level::Error,
+            "Function takes no parameters to pass the trailing block to",
+            trailing.get(pool).span_or_builtin(pool).as_ref()
+        ));
+        return Some(());
+    };
+    checker.set_trailing_block_ty(last.ty.clone());
+    let block = trailing.try_resolve_ref(pool, checker);
+    checker.take_trailing_block_ty();
+    let block = block?;
+    check_args(params, args, call_span, checker);
+    checker.expect_ty_eq(last.ty.clone(), block, trailing.get(pool).span(pool));
+    Some(())
+}
+
+/// Call a value of type `target` with the arguments and trailing block. 
+/// Functions may be called through variables, which can have an alias of a 
+/// function type
+fn call_target(
+    target: &Ty,
+    args: &[ResolvedArg],
+    trailing: Option<&TrailingBlock>,
+    call_span: Option<ArcSpan>,
+    pool: &NodePool,
+    checker: &mut Checker
+) -> Option<Ty> {
+    match target.reduce() {
+        Ty::Function { params, ret_ty } => {
+            check_call(params, args, trailing, call_span, pool, checker)?;
+            Some(ret_ty.as_ref().clone())
+        }
+        Ty::Overloaded { name, overloads } => {
+            let Some((params, ret_ty)) = pick_overload(
+                name, overloads, args, trailing.is_some(), call_span.clone(), checker
+            ) else {
+                return Some(Ty::Invalid);
+            };
+            check_call(&params, args, trailing, call_span, pool, checker)?;
+            Some(ret_ty)
+        }
+        _ => {
+            checker.logger().lock().unwrap().log(Message::new(
+                Level::Error,
+                format!("Cannot call an expression of type {target}"),
+                call_span.unwrap_or(ArcSpan::builtin()).as_ref()
+            ));
+            Some(Ty::Invalid)
+        }
+    }
+}
+
+impl ResolveNode for CallNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        // The left operand of a pipeline is passed as the first argument
+        let piped = checker.take_piped_arg();
+        let target = self.target.try_resolve_ref(pool, checker)?;
+        let mut args = resolve_args(&self.args, pool, checker)?;
+        if let Some((ty, span)) = piped {
+            args.insert(0, ResolvedArg { name: None, ty, span, spread: false, int_literal: false });
+        }
+        if Ty::any_never([&target].into_iter().chain(args.iter().map(|a| &a.ty))) {
+            return Some(Ty::Never);
+        }
+        call_target(&target, &args, self.trailing.as_ref(), self.span(pool), pool, checker)
+    }
+}
+
+/// A pipeline, like `a |> f` or `a |> g(b)`, which calls the function on the 
+/// right with the left operand as the first argument, so that `a |> g(b)` 
+/// is the same as `g(a, b)`
+#[derive(Debug)]
+pub struct PipeNode {
+    lhs: Expr,
+    op: op::Pipeline,
+    rhs: Expr,
+}
+pub type Pipe = RefToNode<PipeNode>;
+
+impl PipeNode {
+    pub(crate) fn parse_with<F>(
+        lhs: Expr,
+        mut rhs: F,
+        pool: &mut NodePool,
+        src: Arc<Src>,
+        tokenizer: &mut TokenIterator
+    ) -> Result<NodeID, FatalParseError>
+        where F: ParseNodeFn
+    {
+        let res = Self {
+            lhs,
+            op: ParseRef::parse_ref(pool, src.clone(), tokenizer)?,
+            rhs: RefToNode::new_raw(rhs(pool, src, tokenizer)?),
+        };
+        Ok(pool.add(res))
+    }
+}
+
+impl Node for PipeNode {
+    fn children(&self) -> Vec<&dyn ResolveRef> {
+        vec![&self.lhs, &self.op, &self.rhs]
+    }
+}
+
+impl ResolveNode for PipeNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        let arg = self.lhs.try_resolve_ref(pool, checker)?;
+        let span = self.lhs.get(pool).span(pool);
+        // A call on the right gets the value prepended to its arguments
+        if matches!(*self.rhs.get(pool), ExprNode::Call(_)) {
+            checker.set_piped_arg(arg, span);
+            let ty = self.rhs.try_resolve_ref(pool, checker);
+            checker.take_piped_arg();
+            return ty;
+        }
+        // Anything else is called with the value as its only argument
+        let target = self.rhs.try_resolve_ref(pool, checker)?;
+        if Ty::any_never([&arg, &target]) {
+            return Some(Ty::Never);
+        }
+        let args = [ResolvedArg { name: None, ty: arg, span, spread: false, int_literal: false }];
+        call_target(&target, &args, None, self.span(pool), pool, checker)
+    }
+}
+
+/// The dot between the value and name of a method call. A null-safe dot 
+/// `?.` calls the method on the contents of an optional value, and gives 
+/// `none` if there are none
+#[derive(Debug, ParseNode)]
+#[parse(expected = "'.'")]
+pub enum MethodDotNode {
+    Dot(punct::Dot),
+    Safe(punct::SafeDot),
+}
+
+impl ResolveNode for MethodDotNode {
+    fn try_resolve_node(&mut self, _: &NodePool, _: &mut Checker) -> Option<Ty> {
+        Some(Ty::Invalid)
+    }
+}
+
+/// A call of a method on a value, like `value.method(args)` or 
+/// `maybe?.method(args)`
+#[derive(Debug)]
+pub struct MethodCallNode {
+    target: Expr,
+    dot: MethodDot,
+    name: Ident,
+    /// The parentheses may be left out if the method is only given a 
+    /// trailing block, like `items.each { ... }`
+    args: Option<delim::Parenthesized<SeparatedWithTrailing<Arg, punct::Comma>>>,
+    trailing: Option<TrailingBlock>,
+    /// Existing method with a name similar to this one, found when the 
+    /// method could not be found. Saved here since the scopes it was 
+    /// searched in are no longer current when the error is reported
+    similar: Option<String>,
+    /// Whether the method could not be found because it was called with `.` 
+    /// on an optional value whose contents have the method
+    needs_safe_dot: bool,
+}
+pub type MethodCall = RefToNode<MethodCallNode>;
+
+impl MethodCallNode {
+    pub(crate) fn parse_with(
+        target: Expr,
+        pool: &mut NodePool,
+        src: Arc<Src>,
+        tokenizer: &mut TokenIterator
+    ) -> Result<NodeID, FatalParseError> {
+        let dot = ParseRef::parse_ref(pool, src.clone(), tokenizer)?;
+        let name = ParseRef::parse_ref(pool, src.clone(), tokenizer)?;
+        let args: Option<delim::Parenthesized<_>> = ParseRef::parse_ref(pool, src.clone(), tokenizer)?;
+        let trailing = parse_trailing_block(pool, src, tokenizer)?;
+        if args.is_none() && trailing.is_none() {
+            tokenizer.expected("arguments");
+            return Err(FatalParseError);
+        }
+        Ok(pool.add(Self { target, dot, name, args, trailing, similar: None, needs_safe_dot: false }))
+    }
+}
+
+impl Node for MethodCallNode {
+    fn children(&self) -> Vec<&dyn ResolveRef> {
+        vec![&self.target, &self.dot, &self.name, &self.args, &self.trailing]
+    }
+}
+
+impl ResolveNode for MethodCallNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        let receiver = self.target.try_resolve_ref(pool, checker)?;
+        let args = match &self.args {
+            Some(args) => resolve_args(args, pool, checker)?,
+            None => Vec::new(),
+        };
+        if Ty::any_never([&receiver].into_iter().chain(args.iter().map(|a| &a.ty))) {
+            return Some(Ty::Never);
+        }
+        if receiver.is_unreal() {
+            return Some(Ty::Invalid);
+        }
+        // With `?.` the method is called on the contents of the optional
+        let safe = matches!(*self.dot.get(pool), MethodDotNode::Safe(_));
+        let target = match receiver.reduce() {
+            Ty::Option { ty } if safe => ty.as_ref().clone(),
+            _ => receiver.clone(),
+        };
+        // The method may be declared later, in which case this is resolved 
+        // on a later pass
+        let name = self.name.get(pool).to_string();
+        let Some((method, decl_span)) = checker.find_method(&target, &name) else {
+            let methods = checker.method_names(&target);
+            self.similar = closest_match(&name, methods.iter().map(|m| m.as_str())).map(String::from);
+            self.needs_safe_dot = matches!(
+                target.reduce(), Ty::Option { ty } if checker.find_method(ty, &name).is_some()
+            );
+            return None;
+        };
+        checker.lint_deprecated_use(&name, &decl_span, &self.name.get(pool).span_or_builtin(pool));
+        match method {
+            Ty::Function { params, ret_ty } => {
+                check_call(&params[1..], &args, self.trailing.as_ref(), self.span(pool), pool, checker)?;
+                checker.expect_ty_eq(params[0].ty.clone(), target, self.target.get(pool).span(pool));
+                if !safe {
+                    return Some(ret_ty.as_ref().clone());
+                }
+                if !matches!(receiver.reduce(), Ty::Option { .. }) {
+                    checker.lint(&UNNECESSARY_SAFE_CALL, Message::new(
+                        Level::Warning,
+                        format!("Value of type {receiver} is never none"),
+                        self.dot.get(pool).span_or_builtin(pool).as_ref()
+                    ).note(Note::new("Use '.' instead", true)));
+                    return Some(ret_ty.as_ref().clone());
+                }
+                // The result is optional, as the call is skipped if the 
+                // value is none
+                Some(match ret_ty.reduce() {
+                    Ty::Option { .. } => ret_ty.as_ref().clone(),
+                    _ => Ty::Option { ty: ret_ty },
+                })
+            }
+            other => ice!("method had non-function type {other}"),
+        }
+    }
+    fn log_unresolved_reason(&self, pool: &NodePool, _checker: &Checker, logger: LoggerRef) {
+        let Some(target) = self.target.resolved_ty(pool) else {
+            return;
+        };
+        let target = match target.reduce() {
+            Ty::Option { ty } if matches!(*self.dot.get(pool), MethodDotNode::Safe(_)) => ty.as_ref().clone(),
+            _ => target,
+        };
+        let name = self.name.get(pool).to_string();
+        let span = self.name.get(pool).span_or_builtin(pool);
+        let mut msg = Message::new(
+            Level::Error,
+            format!("No method named {name} on type {target}"),
+            span.as_ref()
+        );
+        if let Some(ref similar) = self.similar {
+            msg = msg.note(Note::new(format!("Did you mean {similar}?"), true));
+        }
+        if self.needs_safe_dot {
+            msg = msg.note(Note::new(
+                "Use '?.' to call the method if the value is not none", true
+            ));
+        }
+        logger.lock().unwrap().log(msg);
+    }
+}
+
+impl EvalConst for MethodCallNode {
+    /// Only the length of byte strings is known at compile time
+    fn eval_const(&self, pool: &NodePool) -> Result<ConstValue, ConstError> {
+        let no_args = self.trailing.is_none() &&
+            self.args.as_ref().is_some_and(|args| args.get(pool).value.iter().len() == 0);
+        if no_args && self.name.get(pool).to_string() == "len" {
+            if let ConstValue::Bytes(bytes) = self.target.eval_const(pool)? {
+                return Ok(ConstValue::Int(bytes.len() as i64));
+            }
+        }
+        Err(ConstError::not_const(self, pool))
     }
 }
 
@@ -192,7 +777,37 @@ impl Node for IndexNode {
 
 impl ResolveNode for IndexNode {
     fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
-        todo!()
+        let target = self.target.try_resolve_ref(pool, checker)?;
+        let index = self.index.try_resolve_ref(pool, checker)?;
+        if Ty::any_never([&target, &index]) {
+            return Some(Ty::Never);
+        }
+        if target.is_unreal() || index.is_unreal() {
+            return Some(Ty::Invalid);
+        }
+        // Indexing with a range gives a slice of the same type
+        match (target.reduce(), index.reduce()) {
+            (Ty::String, Ty::Int) => Some(Ty::Char),
+            (Ty::String, Ty::Range) => Some(Ty::String),
+            (Ty::Array(item), Ty::Int) => Some(item.as_ref().clone()),
+            (Ty::Array(item), Ty::Range) => Some(Ty::Array(item.clone())),
+            (Ty::String | Ty::Array(_), index) => {
+                checker.logger().lock().unwrap().log(Message::new(
+                    Level::Error,
+                    format!("Cannot index value of type {target} with type {index}"),
+                    self.index.get(pool).span_or_builtin(pool).as_ref()
+                ).note(Note::new("Index must be an int or a range", false)));
+                Some(Ty::Invalid)
+            }
+            _ => {
+                checker.logger().lock().unwrap().log(Message::new(
+                    Level::Error,
+                    format!("Cannot index value of type {target}"),
+                    self.target.get(pool).span_or_builtin(pool).as_ref()
+                ));
+                Some(Ty::Invalid)
+            }
+        }
     }
 }
 
@@ -218,6 +833,10 @@ impl UnOpNode {
         };
         Ok(pool.add(res))
     }
+    /// The operand, if this only gives a sign to it, like `-1` or `+1`
+    pub(crate) fn signed(&self, pool: &NodePool) -> Option<Expr> {
+        matches!(self.op.get(pool).op(), op::UnaryOp::Neg | op::UnaryOp::Plus).then_some(self.target)
+    }
 }
 
 impl Node for UnOpNode {
@@ -230,6 +849,9 @@ impl ResolveNode for UnOpNode {
     fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
         let target = self.target.try_resolve_ref(pool, checker)?;
         let op = self.op.get(pool);
+        if target.is_never() {
+            return Some(Ty::Never);
+        }
         if target.is_unreal() {
             return Some(Ty::Invalid);
         }
@@ -262,11 +884,22 @@ impl ResolveNode for UnOpNode {
     }
 }
 
+impl EvalConst for UnOpNode {
+    fn eval_const(&self, pool: &NodePool) -> Result<ConstValue, ConstError> {
+        let value = self.target.eval_const(pool)?;
+        eval_unary(self.op.get(pool).op(), value)
+            .map_err(|e| ConstError::new(e, self.span_or_builtin(pool)))
+    }
+}
+
 #[derive(Debug)]
 pub struct BinOpNode {
     lhs: Expr,
     op: op::Binary,
     rhs: Expr,
+    /// Whether this is an assignment in a condition, like `if x = 5`, which 
+    /// is checked as the comparison `x == 5` that was most likely meant
+    as_comparison: bool,
 }
 pub type BinOp = RefToNode<BinOpNode>;
 
@@ -284,9 +917,86 @@ impl BinOpNode {
             lhs,
             op: ParseRef::parse_ref(pool, src.clone(), tokenizer)?,
             rhs: RefToNode::new_raw(rhs(pool, src, tokenizer)?),
+            as_comparison: false,
         };
         Ok(pool.add(res))
     }
+    /// Whether this is an assignment, like `x = 5`
+    pub(crate) fn is_assignment(&self, pool: &NodePool) -> bool {
+        self.op.get(pool).op() == op::BinaryOp::Seq
+    }
+    /// The operator this is checked as
+    fn op(&self, pool: &NodePool) -> op::BinaryOp {
+        if self.as_comparison {
+            op::BinaryOp::Eq
+        }
+        else {
+            self.op.get(pool).op()
+        }
+    }
+}
+
+impl BinOpNode {
+    fn is_comparison(op: op::BinaryOp) -> bool {
+        use op::BinaryOp as B;
+        matches!(op, B::Eq | B::Neq | B::Less | B::Leq | B::Grt | B::Geq)
+    }
+    /// Report a chain of comparisons like `a < b < c`, which would compare 
+    /// the bool result of `a < b` with `c`. Returns whether one was found
+    fn check_chained_comparison(&self, pool: &NodePool, checker: &Checker) -> bool {
+        let op = self.op(pool);
+        if !Self::is_comparison(op) {
+            return false;
+        }
+        // A parenthesized comparison isn't a binary operation here
+        let ExprNode::BinOp(inner) = *self.lhs.get(pool) else {
+            return false;
+        };
+        let inner = inner.get(pool);
+        let inner_op = inner.op(pool);
+        if !Self::is_comparison(inner_op) {
+            return false;
+        }
+        // Longer chains are only reported once, by their first comparison
+        let continues_chain = matches!(
+            *inner.lhs.get(pool),
+            ExprNode::BinOp(first) if Self::is_comparison(first.get(pool).op(pool))
+        );
+        if continues_chain {
+            return true;
+        }
+        let text = |expr: &Expr| {
+            let span = expr.get(pool).span_or_builtin(pool);
+            span.0.data()[span.1].to_string()
+        };
+        let (a, b, c) = (text(&inner.lhs), text(&inner.rhs), text(&self.rhs));
+        checker.logger().lock().unwrap().log(Message::new(
+            Level::Error,
+            "Comparison operators can't be chained",
+            self.span_or_builtin(pool).as_ref()
+        ).note(Note::new(
+            format!("Did you mean '{a} {inner_op} {b} && {b} {op} {c}'?"), true
+        )));
+        true
+    }
+    /// Warn if an int is shifted by a constant amount of bits that is 
+    /// negative or not less than the width of an int
+    fn check_shift_amount(&self, pool: &NodePool, checker: &Checker) {
+        if !matches!(self.op(pool), op::BinaryOp::Shl | op::BinaryOp::Shr) {
+            return;
+        }
+        let Ok(ConstValue::Int(amount)) = self.rhs.eval_const(pool) else {
+            return;
+        };
+        if (0..64).contains(&amount) {
+            return;
+        }
+        checker.logger().lock().unwrap().log(Message::new(
+            Level::Warning,
+            format!("Shifting by {amount} bits is out of range"),
+            self.rhs.get(pool).span_or_builtin(pool).as_ref()
+        ).note(Note::new("An int can only be shifted by 0 to 63 bits", false)));
+    }
 }
 
 impl Node for BinOpNode {
@@ -297,21 +1007,40 @@ impl Node for BinOpNode {
 
 impl ResolveNode for BinOpNode {
     fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        if checker.take_assignment_in_condition() && !self.as_comparison {
+            self.as_comparison = true;
+            let span = self.op.get(pool).span_or_builtin(pool);
+            checker.lint(&ASSIGNMENT_IN_CONDITION, Message::new(
+                Level::Warning,
+                "Assignment in condition",
+                span.as_ref()
+            ).note(Note::new("Use '==' to compare the values", true)));
+        }
         let a = self.lhs.try_resolve_ref(pool, checker)?;
         let b = self.rhs.try_resolve_ref(pool, checker)?;
-        let op = self.op.get(pool);
+        let op = self.op(pool);
+        if Ty::any_never([&a, &b]) {
+            return Some(Ty::Never);
+        }
         if a.is_unreal() || b.is_unreal() {
             return Some(Ty::Invalid);
         }
+        if self.check_chained_comparison(pool, checker) {
+            return Some(Ty::Bool);
+        }
         for scope in checker.scopes() {
             // todo: handle symmetrive ops, like a + b <=> b + a
             // todo: synthesize ops, like a == b <=> a != b
             let name = path::IdentPath::new([
-                path::Ident::BinOp(a.clone(), op.op(), b.clone())
+                path::Ident::BinOp(a.clone(), op, b.clone())
             ], false);
             if let Some(fun) = scope.entities().find(&name) {
                 match fun.ty() {
-                    Ty::Function { params: _, ret_ty } => return Some(ret_ty.as_ref().clone()),
+                    Ty::Function { params: _, ret_ty } => {
+                        let ret_ty = ret_ty.as_ref().clone();
+                        self.check_shift_amount(pool, checker);
+                        return Some(ret_ty);
+                    }
                     _ => ice!(
                         "encountered entity with binop name '{name}' \
                         that wasn't a function type, but {}",
@@ -328,10 +1057,19 @@ impl ResolveNode for BinOpNode {
                 Level::Error,
                 format!(
                     "Cannot use operator '{}' on types {lhs} and {rhs}",
-                    self.op.get(pool).op(),
+                    self.op(pool),
                 ),
                 self.span_or_builtin(pool).as_ref()
             ))
         }
     }
 }
+
+impl EvalConst for BinOpNode {
+    fn eval_const(&self, pool: &NodePool) -> Result<ConstValue, ConstError> {
+        let a = self.lhs.eval_const(pool)?;
+        let b = self.rhs.eval_const(pool)?;
+        eval_binary(a, self.op.get(pool).op(), b)
+            .map_err(|e| ConstError::new(e, self.span_or_builtin(pool)))
+    }
+}
@@ -0,0 +1,126 @@
+
+use dash_macros::ParseNode;
+use super::token::{punct, delim, Ident};
+use crate::{
+    checker::{resolve::ResolveNode, coherency::Checker, ty::Ty, entity::Entity, path},
+    parser::parse::{NodePool, Node, SeparatedWithTrailing},
+    shared::{src::ArcSpan, logger::{Message, Level, Note}}
+};
+
+/// A variable declared by a pattern, along with its type and where it was
+/// declared
+pub(crate) type PatternVar = (String, Ty, ArcSpan);
+
+/// Declare the variables bound by a pattern in the current scope. A pattern 
+/// can't bind the same name twice, but may shadow earlier variables
+pub(crate) fn declare_pattern_vars(vars: &[PatternVar], checker: &mut Checker) {
+    for (i, (name, ty, span)) in vars.iter().enumerate() {
+        if let Some((_, _, first)) = vars[..i].iter().find(|(n, _, _)| n == name) {
+            checker.logger().lock().unwrap().log(Message::new(
+                Level::Error,
+                format!("Variable {name} is bound more than once in this pattern"),
+                span.as_ref()
+            ).note(Note::new_at("First bound here", first.as_ref())));
+            continue;
+        }
+        let name = path::IdentPath::new([path::Ident::from(name.as_str())], false);
+        checker.declare_variable(&name, Entity::new(ty.clone(), span.clone(), true));
+    }
+}
+
+/// A field in a struct pattern, like `x` or `x: (a, b)`
+#[derive(Debug, ParseNode)]
+pub struct FieldPatternNode {
+    #[parse(expected = "field name")]
+    name: Ident,
+    pattern: Option<(punct::Colon, Pattern)>,
+}
+
+impl ResolveNode for FieldPatternNode {
+    fn try_resolve_node(&mut self, _: &NodePool, _: &mut Checker) -> Option<Ty> {
+        Some(Ty::Invalid)
+    }
+}
+
+/// Pattern for taking apart a value, like `(a, b)` or `{ x, y }`
+#[derive(Debug, ParseNode)]
+#[parse(expected = "pattern")]
+pub enum PatternNode {
+    Tuple(delim::Parenthesized<SeparatedWithTrailing<Pattern, punct::Comma>>),
+    Struct(delim::Braced<SeparatedWithTrailing<FieldPattern, punct::Comma>>),
+    Name(Ident),
+}
+
+impl ResolveNode for PatternNode {
+    /// Patterns have no type of their own, see `PatternNode::bind`
+    fn try_resolve_node(&mut self, _: &NodePool, _: &mut Checker) -> Option<Ty> {
+        Some(Ty::Invalid)
+    }
+}
+
+impl PatternNode {
+    /// Match this pattern against a value of type `ty`, collecting the
+    /// variables it declares. Variables in parts of the pattern that don't
+    /// fit `ty` are still declared, with an invalid type
+    pub(crate) fn bind(&self, ty: &Ty, pool: &NodePool, checker: &Checker, vars: &mut Vec<PatternVar>) {
+        let error = |msg: String, span: ArcSpan| {
+            checker.logger().lock().unwrap().log(Message::new(Level::Error, msg, span.as_ref()));
+        };
+        match self {
+            Self::Name(name) => {
+                let name = name.get(pool);
+                vars.push((name.to_string(), ty.clone(), name.span_or_builtin(pool)));
+            }
+            Self::Tuple(items) => {
+                let items = &items.get(pool).value;
+                let count = items.iter().len();
+                let tys = match ty.reduce() {
+                    Ty::Tuple(tys) if tys.len() == count => Some(tys.as_slice()),
+                    Ty::Tuple(tys) => {
+                        error(
+                            format!("Pattern has {count} items, but type {ty} has {}", tys.len()),
+                            self.span_or_builtin(pool)
+                        );
+                        None
+                    }
+                    other if other.is_unreal() => None,
+                    _ => {
+                        error(format!("Cannot destructure value of type {ty} as a tuple"), self.span_or_builtin(pool));
+                        None
+                    }
+                };
+                for (i, item) in items.iter().enumerate() {
+                    let ty = tys.map(|tys| tys[i].clone()).unwrap_or(Ty::Invalid);
+                    item.get(pool).bind(&ty, pool, checker, vars);
+                }
+            }
+            Self::Struct(fields) => {
+                let fields_ty = match ty.reduce() {
+                    Ty::Struct { name: _, fields, decl_span: _ } => Some(fields),
+                    other if other.is_unreal() => None,
+                    _ => {
+                        error(format!("Cannot destructure value of type {ty} as a struct"), self.span_or_builtin(pool));
+                        None
+                    }
+                };
+                for field in fields.get(pool).value.iter() {
+                    let field = field.get(pool);
+                    let name = field.name.get(pool).to_string();
+                    let span = field.name.get(pool).span_or_builtin(pool);
+                    let field_ty = match fields_ty.map(|f| f.iter().find(|(n, _)| *n == name)) {
+                        Some(Some((_, ty))) => ty.clone(),
+                        Some(None) => {
+                            error(format!("Struct {ty} has no field {name}"), span.clone());
+                            Ty::Invalid
+                        }
+                        None => Ty::Invalid,
+                    };
+                    match field.pattern {
+                        Some((_, pattern)) => pattern.get(pool).bind(&field_ty, pool, checker, vars),
+                        None => vars.push((name, field_ty, span)),
+                    }
+                }
+            }
+        }
+    }
+}
@@ -12,11 +12,23 @@ impl Display for IdentNode {
     }
 }
 
+/// Name of a loop, like `'outer`
+#[token(kind = "Label", include_raw)]
+pub struct Label {}
+
+impl Display for LabelNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.raw)
+    }
+}
+
 pub(crate) mod kw {
     use dash_macros::token;
 
     #[token(kind = "Keyword", raw = "let")]
     pub struct Let {}
+    #[token(kind = "Keyword", raw = "const")]
+    pub struct Const {}
     #[token(kind = "Keyword", raw = "fun")]
     pub struct Fun {}
     #[token(kind = "Keyword", raw = "if")]
@@ -29,11 +41,58 @@ pub(crate) mod kw {
     pub struct Return {}
     #[token(kind = "Keyword", raw = "using")]
     pub struct Using {}
+    #[token(kind = "Keyword", raw = "struct")]
+    pub struct Struct {}
+    #[token(kind = "Keyword", raw = "compiler_intrinsic")]
+    pub struct CompilerIntrinsic {}
+    #[token(kind = "Keyword", raw = "extern")]
+    pub struct Extern {}
+    #[token(kind = "Keyword", raw = "type")]
+    pub struct Type {}
+    #[token(kind = "Keyword", raw = "new")]
+    pub struct New {}
+    #[token(kind = "Keyword", raw = "try")]
+    pub struct Try {}
+    #[token(kind = "Keyword", raw = "while")]
+    pub struct While {}
+    #[token(kind = "Keyword", raw = "for")]
+    pub struct For {}
+    #[token(kind = "Keyword", raw = "in")]
+    pub struct In {}
+    #[token(kind = "Keyword", raw = "break")]
+    pub struct Break {}
+    #[token(kind = "Keyword", raw = "continue")]
+    pub struct Continue {}
+    #[token(kind = "Keyword", raw = "extends")]
+    pub struct Extends {}
+    #[token(kind = "Keyword", raw = "defer")]
+    pub struct Defer {}
+    #[token(kind = "Keyword", raw = "switch")]
+    pub struct Switch {}
+    #[token(kind = "Keyword", raw = "as")]
+    pub struct As {}
+    #[token(kind = "Keyword", raw = "public")]
+    pub struct Public {}
 
     #[token(kind = "Ident", raw = "get")]
     pub struct Get {}
     #[token(kind = "Ident", raw = "set")]
     pub struct Set {}
+    #[token(kind = "Ident", raw = "assert")]
+    pub struct Assert {}
+    #[token(kind = "Ident", raw = "static_assert")]
+    pub struct StaticAssert {}
+}
+
+pub(crate) mod attr {
+    use dash_macros::token;
+
+    #[token(kind = "Ident", raw = "allow")]
+    pub struct Allow {}
+    #[token(kind = "Ident", raw = "deprecated")]
+    pub struct Deprecated {}
+    #[token(kind = "Ident", raw = "cfg")]
+    pub struct Cfg {}
 }
 
 pub(crate) mod lit {
@@ -63,17 +122,34 @@ pub(crate) mod lit {
         False(False),
     }
 
+    impl BoolNode {
+        pub fn value(&self) -> bool {
+            matches!(self, Self::True(_))
+        }
+    }
+
     impl ResolveNode for BoolNode {
         fn try_resolve_node(&mut self, _: &NodePool, _: &mut Checker) -> Option<Ty> {
             Some(Ty::Bool)
         }
     }
 
-    #[token(kind = "Int(_)", no_default_resolve)]
+    #[token(kind = "Int(_)", no_default_resolve, include_raw)]
     pub struct Int {
         value: i64,
     }
 
+    impl IntNode {
+        pub fn value(&self) -> i64 {
+            self.value
+        }
+        /// Whether the literal is explicitly typed with the `i` suffix, 
+        /// like `1i`
+        pub fn has_suffix(&self) -> bool {
+            self.raw.ends_with('i')
+        }
+    }
+
     impl ResolveNode for IntNode {
         fn try_resolve_node(&mut self, _: &NodePool, _: &mut Checker) -> Option<Ty> {
             Some(Ty::Int)
@@ -85,6 +161,12 @@ pub(crate) mod lit {
         value: f64,
     }
 
+    impl FloatNode {
+        pub fn value(&self) -> f64 {
+            self.value
+        }
+    }
+
     impl ResolveNode for FloatNode {
         fn try_resolve_node(&mut self, _: &NodePool, _: &mut Checker) -> Option<Ty> {
             Some(Ty::Float)
@@ -96,11 +178,52 @@ pub(crate) mod lit {
         value: std::string::String,
     }
 
+    impl StringNode {
+        pub fn value(&self) -> &str {
+            &self.value
+        }
+    }
+
     impl ResolveNode for StringNode {
         fn try_resolve_node(&mut self, _: &NodePool, _: &mut Checker) -> Option<Ty> {
             Some(Ty::String)
         }
     }
+
+    /// Byte string like `b"GIF89a"`, which is an array of the bytes as ints
+    #[token(kind = "Bytes(_)", no_default_resolve)]
+    pub struct Bytes {
+        value: Vec<u8>,
+    }
+
+    impl BytesNode {
+        pub fn value(&self) -> &[u8] {
+            &self.value
+        }
+    }
+
+    impl ResolveNode for BytesNode {
+        fn try_resolve_node(&mut self, _: &NodePool, _: &mut Checker) -> Option<Ty> {
+            Some(Ty::Array(Box::from(Ty::Int)))
+        }
+    }
+
+    #[token(kind = "Char(_)", no_default_resolve)]
+    pub struct Char {
+        value: char,
+    }
+
+    impl CharNode {
+        pub fn value(&self) -> char {
+            self.value
+        }
+    }
+
+    impl ResolveNode for CharNode {
+        fn try_resolve_node(&mut self, _: &NodePool, _: &mut Checker) -> Option<Ty> {
+            Some(Ty::Char)
+        }
+    }
 }
 
 pub(crate) mod punct {
@@ -109,7 +232,7 @@ pub(crate) mod punct {
     use dash_macros::token;
 
     use crate::{
-        shared::{src::Src, logger::{Message, Level}},
+        shared::{src::Src, logger::{Message, Level, Note}, ice::Ice},
         parser::{
             parse::{ParseNode, FatalParseError, calculate_span, NodePool, Node, NodeID, ParseRef, RefToNode},
             tokenizer::TokenIterator
@@ -143,6 +266,7 @@ pub(crate) mod punct {
     impl ParseNode for TerminatingSemicolonNode {
         fn parse_node(pool: &mut NodePool, src: Arc<Src>, tokenizer: &mut TokenIterator) -> Result<NodeID, FatalParseError> {
             let last_was_braced = tokenizer.last_was_braced();
+            let keyword_typo = tokenizer.take_keyword_typo();
             let mut found = vec![];
             while let Some(s) = Semicolon::peek_and_parse(pool, src.clone(), tokenizer)? {
                 found.push(s);
@@ -150,7 +274,10 @@ pub(crate) mod punct {
             // If the last token was a Braced or we're at EOF of this tree 
             // then allow omitting semicolon
             if found.is_empty() && !last_was_braced && tokenizer.peek(0).is_some() {
-                tokenizer.expected("semicolon");
+                let notes = keyword_typo.map(|(keyword, span)| {
+                    Note::hint(format!("Did you mean the keyword '{keyword}'?"), span)
+                });
+                tokenizer.expected_with_notes("semicolon", notes.into_iter().collect());
             }
             // Warn if there were multiple semicolons
             if !found.is_empty() && last_was_braced {
@@ -162,14 +289,14 @@ pub(crate) mod punct {
                     else {
                         "Unnecessary semicolon"
                     },
-                    calculate_span(found.iter().map(|s| s.get(pool).span(pool))).unwrap().as_ref()
+                    calculate_span(found.iter().map(|s| s.get(pool).span(pool))).ice("semicolons have no span").as_ref()
                 ));
             }
             else if found.len() > 1 {
                 tokenizer.logger().lock().unwrap().log(Message::new(
                     Level::Warning,
                     "Unnecessary semicolons",
-                    calculate_span(found.iter().skip(1).map(|s| s.get(pool).span(pool))).unwrap().as_ref()
+                    calculate_span(found.iter().skip(1).map(|s| s.get(pool).span(pool))).ice("semicolons have no span").as_ref()
                 ));
             }
             // Missing semicolon is not a fatal parsing error
@@ -200,6 +327,19 @@ pub(crate) mod punct {
 
     #[token(kind = "Punct", raw = "@")]
     pub struct At {}
+
+    #[token(kind = "Punct", raw = "|")]
+    pub struct Pipe {}
+
+    #[token(kind = "Punct", raw = ".")]
+    pub struct Dot {}
+
+    /// Null-safe member access, like `value?.method()`
+    #[token(kind = "Punct", raw = "?.")]
+    pub struct SafeDot {}
+
+    #[token(kind = "Punct", raw = "...")]
+    pub struct Spread {}
 }
 
 pub(crate) mod op {
@@ -238,6 +378,10 @@ pub(crate) mod op {
                     }
                 }
 
+                impl op_name {
+                    pub const ALL: &'static [op_name] = &[$(Self::$name),*];
+                }
+
                 $(
                     impl $name {
                         pub fn op(&self) -> op_name {
@@ -291,24 +435,42 @@ pub(crate) mod op {
             Seq = "=",
             Add = "+", Sub = "-",
             Mul = "*", Div = "/", Mod = "%",
+            Pow = "**",
+            BitAnd = "&", BitOr = "|", BitXor = "^",
+            Shl = "<<", Shr = ">>",
             Grt = ">", Geq = ">=", Less = "<", Leq = "<=",
+            Range = "..", RangeInclusive = "..=",
         }
     }
 
+    /// The pipeline operator, which passes its left operand as the first 
+    /// argument of the function on its right, like `a |> f`
+    #[token(kind = "Punct", raw = "|>")]
+    pub struct Pipeline {}
+
     #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
     pub enum Prec {
         Mul,
         Add,
+        Shift,
+        BitAnd,
+        BitXor,
+        BitOr,
         Ord,
         Eq,
         And,
         Or,
+        Range,
+        Pipe,
         Seq,
     }
 
     impl Prec {
-        pub(crate) const fn order() -> [Prec; 7] {
-            [Prec::Mul, Prec::Add, Prec::Ord, Prec::Eq, Prec::And, Prec::Or, Prec::Seq]
+        pub(crate) const fn order() -> [Prec; 13] {
+            [
+                Prec::Mul, Prec::Add, Prec::Shift, Prec::BitAnd, Prec::BitXor, Prec::BitOr,
+                Prec::Ord, Prec::Eq, Prec::And, Prec::Or, Prec::Range, Prec::Pipe, Prec::Seq
+            ]
         }
         pub fn peek(&self, tokenizer: &TokenIterator) -> bool {
             match self {
@@ -317,9 +479,16 @@ pub(crate) mod op {
                 Prec::Ord => Grt::peek(0, tokenizer) || Less::peek(0, tokenizer) ||
                              Geq::peek(0, tokenizer) || Leq::peek(0, tokenizer),
                 Prec::Add => Add::peek(0, tokenizer) || Sub::peek(0, tokenizer),
+                Prec::Shift => Shl::peek(0, tokenizer) || Shr::peek(0, tokenizer),
+                Prec::BitAnd => BitAnd::peek(0, tokenizer),
+                Prec::BitXor => BitXor::peek(0, tokenizer),
+                // `|` separates the values of a switch case instead
+                Prec::BitOr => BitOr::peek(0, tokenizer) && tokenizer.allow_bit_or(),
                 Prec::Eq  => Eq::peek(0, tokenizer) || Neq::peek(0, tokenizer),
                 Prec::And => And::peek(0, tokenizer),
                 Prec::Or  => Or::peek(0, tokenizer),
+                Prec::Range => Range::peek(0, tokenizer) || RangeInclusive::peek(0, tokenizer),
+                Prec::Pipe => Pipeline::peek(0, tokenizer),
                 Prec::Seq => Seq::peek(0, tokenizer),
             }
         }
@@ -1,16 +1,18 @@
 
 use std::sync::Arc;
 
-use dash_macros::{ParseNode, ResolveNode};
+use dash_macros::ParseNode;
 use crate::{
-    parser::{parse::{ParseNode, FatalParseError, RefToNode, NodePool, Node, NodeID, ParseRef}, tokenizer::TokenIterator},
+    parser::{parse::{ParseNode, FatalParseError, RefToNode, NodePool, Node, NodeID, ParseRef, SeparatedWithTrailing}, tokenizer::TokenIterator},
     shared::{src::Src, logger::{Message, Level, LoggerRef}},
-    checker::{resolve::{ResolveNode, ResolveRef}, coherency::Checker, ty::Ty}
+    checker::{resolve::{ResolveNode, ResolveRef}, coherency::Checker, ty::{Ty, ParamTy}},
+    try_resolve_ref,
 };
-use super::{expr::IdentPath, token::op};
+use super::{expr::IdentPath, token::{kw, op, punct, delim}};
 
 #[derive(Debug)]
 pub enum TypeExprNode {
+    Union(TypeExpr, punct::Pipe, TypeExpr),
     Optional(TypeExpr, op::Question),
     Atom(TypeAtom),
 }
@@ -19,28 +21,75 @@ pub type TypeExpr = RefToNode<TypeExprNode>;
 impl Node for TypeExprNode {
     fn children(&self) -> Vec<&dyn ResolveRef> {
         match self {
+            Self::Union(a, p, b) => vec![a, p, b],
             Self::Optional(ty, q) => vec![ty, q],
             Self::Atom(atom) => vec![atom],
         }
     }
 }
 
+impl TypeExprNode {
+    fn parse_optional(pool: &mut NodePool, src: Arc<Src>, tokenizer: &mut TokenIterator) -> Result<Self, FatalParseError> {
+        let mut res = Self::Atom(ParseRef::parse_ref(pool, src.clone(), tokenizer)?);
+        let mut links = 0;
+        let opt: Result<Self, FatalParseError> = (|| {
+            while op::Question::peek(0, tokenizer) {
+                tokenizer.enter_chain_link()?;
+                links += 1;
+                let q = ParseRef::parse_ref(pool, src.clone(), tokenizer)?;
+                res = Self::Optional(RefToNode::new(pool, res), q);
+            }
+            Ok(res)
+        })();
+        tokenizer.exit_chain(links);
+        opt
+    }
+}
+
 impl ParseNode for TypeExprNode {
     fn parse_node(pool: &mut NodePool, src: Arc<Src>, tokenizer: &mut TokenIterator) -> Result<NodeID, FatalParseError> {
-        let mut res = Self::Atom(ParseRef::parse_ref(pool, src.clone(), tokenizer)?);
-        while let Some(q) = op::Question::peek_and_parse(pool, src.clone(), tokenizer)? {
-            res = Self::Optional(RefToNode::new(pool, res), q);
-        }
-        Ok(pool.add(res))
+        let mut res = Self::parse_optional(pool, src.clone(), tokenizer)?;
+        let mut links = 0;
+        let union: Result<NodeID, FatalParseError> = (|| {
+            while punct::Pipe::peek(0, tokenizer) {
+                tokenizer.enter_chain_link()?;
+                links += 1;
+                let pipe = ParseRef::parse_ref(pool, src.clone(), tokenizer)?;
+                let rhs = Self::parse_optional(pool, src.clone(), tokenizer)?;
+                res = Self::Union(RefToNode::new(pool, res), pipe, RefToNode::new(pool, rhs));
+            }
+            Ok(pool.add(res))
+        })();
+        tokenizer.exit_chain(links);
+        union
     }
     fn peek(pos: usize, tokenizer: &TokenIterator) -> bool {
         TypeAtom::peek(pos, tokenizer)
     }
 }
 
+impl TypeExprNode {
+    /// The name of the type this type expression refers to, if it is just a 
+    /// name (or an optional of one)
+    pub(crate) fn referenced_name(&self, pool: &NodePool) -> Option<String> {
+        match self {
+            Self::Union(..) => None,
+            Self::Optional(opt, _) => opt.get(pool).referenced_name(pool),
+            Self::Atom(atom) => match *atom.get(pool) {
+                TypeAtomNode::Array(_) | TypeAtomNode::Tuple(_) | TypeAtomNode::Function(..) => None,
+                TypeAtomNode::TypeIdent(ident) => Some(ident.get(pool).name.get(pool).to_path(pool).to_string()),
+            },
+        }
+    }
+}
+
 impl ResolveNode for TypeExprNode {
     fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
         match self {
+            Self::Union(a, _, b) => {
+                let a = a.try_resolve_ref(pool, checker)?;
+                Some(Ty::union([a, b.try_resolve_ref(pool, checker)?]))
+            }
             Self::Optional(opt, _) => Some(Ty::Option {
                 ty: Box::new(opt.try_resolve_ref(pool, checker)?)
             }),
@@ -49,12 +98,48 @@ impl ResolveNode for TypeExprNode {
     }
 }
 
-#[derive(Debug, ParseNode, ResolveNode)]
+#[derive(Debug, ParseNode)]
 #[parse(expected = "type")]
 pub enum TypeAtomNode {
+    Array(delim::Bracketed<TypeExpr>),
+    /// A tuple type like `(int, string)`, or a single type in parentheses
+    Tuple(delim::Parenthesized<SeparatedWithTrailing<TypeExpr, punct::Comma>>),
+    /// A function type like `fun(int, int) -> int`
+    Function(
+        kw::Fun,
+        delim::Parenthesized<SeparatedWithTrailing<TypeExpr, punct::Comma>>,
+        Option<(punct::Arrow, TypeExpr)>,
+    ),
     TypeIdent(TypeIdent),
 }
 
+impl ResolveNode for TypeAtomNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        match self {
+            Self::Array(item) => Some(Ty::Array(item.try_resolve_ref(pool, checker)?.into())),
+            Self::Tuple(items) => {
+                let items = &items.get(pool).value;
+                let tys = items.iter()
+                    .map(|item| item.try_resolve_ref(pool, checker))
+                    .collect::<Option<Vec<_>>>()?;
+                match <[Ty; 1]>::try_from(tys) {
+                    Ok([ty]) if !items.has_trailing() => Some(ty),
+                    Ok(tys) => Some(Ty::Tuple(tys.into())),
+                    Err(tys) => Some(Ty::Tuple(tys)),
+                }
+            }
+            Self::Function(_, params, ret_ty) => {
+                let params = params.get(pool).value.iter()
+                    .map(|param| Some(ParamTy::new(None::<String>, param.try_resolve_ref(pool, checker)?)))
+                    .collect::<Option<Vec<_>>>()?;
+                let ret_ty = try_resolve_ref!(ret_ty, (pool, checker), Some((_, ty)) => ty);
+                Some(Ty::Function { params, ret_ty: ret_ty.into() })
+            }
+            Self::TypeIdent(ident) => ident.try_resolve_ref(pool, checker),
+        }
+    }
+}
+
 #[derive(Debug, ParseNode)]
 pub struct TypeIdentNode {
     name: IdentPath,
@@ -69,10 +154,16 @@ impl ResolveNode for TypeIdentNode {
         }
         None
     }
-    fn log_unresolved_reason(&self, pool: &NodePool, _checker: &Checker, logger: LoggerRef) {
+    fn log_unresolved_reason(&self, pool: &NodePool, checker: &Checker, logger: LoggerRef) {
+        let name = self.name.get(pool).to_path(pool);
+        // The type exists, it's just defined in terms of itself, which is 
+        // reported by the alias declaration
+        if checker.type_alias_cycle(&name.to_string()).is_some() {
+            return;
+        }
         logger.lock().unwrap().log(Message::new(
             Level::Error,
-            format!("Unknown type {}", self.name.get(pool).to_path(pool)),
+            format!("Unknown type {name}"),
             self.name.get(pool).span_or_builtin(pool).as_ref()
         ))
     }
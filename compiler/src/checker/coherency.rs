@@ -1,12 +1,20 @@
 
-use std::collections::HashMap;
+use std::{collections::{HashMap, hash_map::Entry}, sync::Arc};
 use crate::{
-    shared::{logger::{LoggerRef, Message, Level, Note}, src::{ArcSpan, Span}},
+    shared::{logger::{Logger, LoggerRef, Message, Level, Note}, src::{ArcSpan, Span}, cancel::CancellationToken, ice::Ice},
     ast::token::op,
-    parser::parse::NodePool,
-    checker::resolve::ResolveRef
+    parser::{parse::NodePool, config::{LanguageConfig, Edition}, tokenizer::{Tokenizer, TokenKind}},
+    checker::resolve::ResolveRef,
+    ice,
+};
+use super::lint::{
+    Lint, LintLevel, UNUSED_VARIABLE, UNUSED_LABEL, SHADOWING, DEPRECATED, UNREACHABLE_CODE, MIXED_LINE_ENDINGS,
+    FUTURE_KEYWORD,
+};
+use super::{
+    ty::{Ty, ParamTy, TyDisplay}, path::{FullIdentPath, IdentPath, Ident}, entity::Entity,
+    pool::AST, intrinsic::intrinsics
 };
-use super::{ty::Ty, path::{FullIdentPath, IdentPath, Ident}, entity::Entity, pool::AST};
 
 #[derive(Debug)]
 struct ItemSpace<T> {
@@ -23,38 +31,51 @@ impl<T> ItemSpace<T> {
     }
     /// Try to find an item in this scope with an unresolved name
     fn find(&self, name: &IdentPath, stack: &FullIdentPath) -> Option<&T> {
+        self.find_with_path(name, stack).map(|(_, item)| item)
+    }
+    /// Try to find an item in this scope with an unresolved name, along 
+    /// with its fully resolved name
+    fn find_with_path(&self, name: &IdentPath, stack: &FullIdentPath) -> Option<(&FullIdentPath, &T)> {
         // This is an optimization; the else branch would also do this since 
         // FullIdentPath::join would just return `name` every time
         if name.is_absolute() {
-            self.get(&name.to_full())
+            self.items.get_key_value(&name.to_full())
         }
         else {
             // Try joining the path to the namespace stack. If not found, check 
             // that namespace's parent namespace, all the way down to root
             let mut temp = stack.clone();
             while !temp.is_empty() {
-                if let Some(found) = self.get(&temp.join(name)) {
+                if let Some(found) = self.items.get_key_value(&temp.join(name)) {
                     return Some(found);
                 }
                 temp.pop();
             }
             // Check root namespace
-            self.get(&name.to_full())
+            self.items.get_key_value(&name.to_full())
         }
     }
+    /// Find an item declared in this scope with exactly the name `name` 
+    /// would get if it was pushed
+    fn get_declared(&self, name: &IdentPath, stack: &FullIdentPath) -> Option<&T> {
+        self.get(&stack.join(name))
+    }
+    /// Remove the item with the fully resolved name `full_name`
+    fn remove(&mut self, full_name: &FullIdentPath) -> Option<T> {
+        self.items.remove(full_name)
+    }
+    /// Add an item, replacing any item with the same name. Returns the item 
+    /// that was replaced
+    fn replace(&mut self, name: &IdentPath, item: T, stack: &FullIdentPath) -> Option<T> {
+        self.items.insert(stack.join(name), item)
+    }
     fn try_push(&mut self, name: &IdentPath, item: T, stack: &FullIdentPath) -> Result<&T, &T> {
         // The full name for this item is the current topmost namespace name 
         // joined with the name of the item
-        let full_name = stack.join(name);
-        // Check if this name already exists in this scope
-        // Can't just do `if let Some` because the borrow checker then complains 
-        // that you can't mutate self.items in the `else` branch afterwards
-        if self.items.contains_key(&full_name) {
-            Err(self.items.get(&full_name).unwrap())
-        }
-        else {
-            self.items.insert(full_name.clone(), item);
-            Ok(self.items.get(&full_name).unwrap())
+        match self.items.entry(stack.join(name)) {
+            // This name already exists in this scope
+            Entry::Occupied(existing) => Err(existing.into_mut()),
+            Entry::Vacant(entry) => Ok(entry.insert(item)),
         }
     }
 }
@@ -78,6 +99,19 @@ impl<'s, T> ItemSpaceWithStack<'s, T> {
     pub fn find(self, name: &IdentPath) -> Option<&'s T> {
         self.space.find(name, self.stack)
     }
+    /// Try to find an item in this scope with an unresolved name, along 
+    /// with its fully resolved name
+    pub fn find_with_path(self, name: &IdentPath) -> Option<(&'s FullIdentPath, &'s T)> {
+        self.space.find_with_path(name, self.stack)
+    }
+    /// Try to find an item in this scope with a fully resolved name
+    pub fn get(self, full_name: &FullIdentPath) -> Option<&'s T> {
+        self.space.get(full_name)
+    }
+    /// Iterate the full names and values of all items in this scope
+    pub fn iter(self) -> impl Iterator<Item = (&'s FullIdentPath, &'s T)> {
+        self.space.items.iter()
+    }
 }
 
 #[derive(Debug)]
@@ -95,6 +129,50 @@ impl<'s, T> ItemSpaceWithStackMut<'s, T> {
     pub fn try_push(self, name: &IdentPath, item: T) -> Result<&'s T, &'s T> {
         self.space.try_push(name, item, self.stack)
     }
+    /// Add an item, replacing any item with the same name. Returns the item 
+    /// that was replaced
+    pub fn replace(self, name: &IdentPath, item: T) -> Option<T> {
+        self.space.replace(name, item, self.stack)
+    }
+}
+
+/// What a scope is the body of
+#[derive(Debug)]
+enum ScopeKind {
+    Block,
+    Function {
+        return_ty: Ty,
+    },
+    Loop {
+        /// Span of the loop's keyword
+        span: ArcSpan,
+        /// Whether `break` may give the loop a value, which is only possible 
+        /// for loops that can't finish without breaking
+        allow_break_value: bool,
+        /// Types of the values of the `break`s inside this loop
+        breaks: Vec<(Ty, ArcSpan)>,
+        /// The loop's label and where it was declared, if it has one
+        label: Option<(String, ArcSpan)>,
+        /// Whether a `break` or `continue` has referred to the label
+        label_used: bool,
+    },
+    Defer {
+        /// Span of the `defer` keyword
+        span: ArcSpan,
+    },
+}
+
+/// An item brought into a scope with `using`
+#[derive(Debug)]
+struct Import {
+    /// Full name of the imported item
+    target: FullIdentPath,
+    /// Full name the item is visible as in the scope
+    name: FullIdentPath,
+    span: ArcSpan,
+    /// Whether this is a `public using`, which stays visible to the files 
+    /// checked after the file it is in
+    public: bool,
 }
 
 #[derive(Debug)]
@@ -102,6 +180,8 @@ struct Scope {
     parent: Option<ScopeID>,
     types: ItemSpace<Ty>,
     entities: ItemSpace<Entity>,
+    imports: Vec<Import>,
+    kind: ScopeKind,
 }
 
 impl Scope {
@@ -110,6 +190,8 @@ impl Scope {
             parent: Some(parent),
             types: Default::default(),
             entities: Default::default(),
+            imports: Vec::new(),
+            kind: ScopeKind::Block,
         }
     }
     fn root() -> Self {
@@ -118,11 +200,16 @@ impl Scope {
                 (Ty::$a, op::BinaryOp::$op, Ty::$b, Ty::$r)
             };
         }
+        macro_rules! decl_unop {
+            ($op: ident $a: ident => $r: ident) => {
+                (op::UnaryOp::$op, Ty::$a, Ty::$r)
+            };
+        }
 
         Self {
             parent: None,
             types: ItemSpace::new(
-                [Ty::Never, Ty::Void, Ty::Bool, Ty::Int, Ty::Float, Ty::String]
+                [Ty::Never, Ty::Void, Ty::Bool, Ty::Int, Ty::Float, Ty::String, Ty::Char, Ty::Range]
                     .map(|t| (FullIdentPath::new([t.to_string().into()]), t))
             ),
             entities: ItemSpace::new(
@@ -138,6 +225,12 @@ impl Scope {
                     decl_binop!(Int Mul Int => Int),
                     decl_binop!(Int Div Int => Int),
                     decl_binop!(Int Mod Int => Int),
+                    decl_binop!(Int Pow Int => Int),
+                    decl_binop!(Int BitAnd Int => Int),
+                    decl_binop!(Int BitOr Int => Int),
+                    decl_binop!(Int BitXor Int => Int),
+                    decl_binop!(Int Shl Int => Int),
+                    decl_binop!(Int Shr Int => Int),
                     
                     decl_binop!(Float Eq  Float => Bool),
                     decl_binop!(Float Neq Float => Bool),
@@ -150,6 +243,7 @@ impl Scope {
                     decl_binop!(Float Mul Float => Float),
                     decl_binop!(Float Div Float => Float),
                     decl_binop!(Float Mod Float => Float),
+                    decl_binop!(Float Pow Float => Float),
 
                     decl_binop!(Int Add Float => Float),
                     decl_binop!(Int Sub Float => Float),
@@ -163,6 +257,18 @@ impl Scope {
                     decl_binop!(String Add String => String),
                     decl_binop!(String Mul Int => String),
 
+                    decl_binop!(Char Eq  Char => Bool),
+                    decl_binop!(Char Neq Char => Bool),
+                    decl_binop!(Char Less Char => Bool),
+                    decl_binop!(Char Leq Char => Bool),
+                    decl_binop!(Char Grt Char => Bool),
+                    decl_binop!(Char Geq Char => Bool),
+
+                    decl_binop!(Int Range Int => Range),
+                    decl_binop!(Int RangeInclusive Int => Range),
+
+                    decl_binop!(Bool Eq Bool => Bool),
+                    decl_binop!(Bool Neq Bool => Bool),
                     decl_binop!(Bool And Bool => Bool),
                     decl_binop!(Bool Or Bool => Bool),
                 ]
@@ -170,19 +276,52 @@ impl Scope {
                     FullIdentPath::new([Ident::BinOp(a.clone(), op, b.clone())]),
                     Entity::new(
                         Ty::Function {
-                            params: vec![(None, a), (None, b)],
+                            params: vec![ParamTy::new(None::<String>, a), ParamTy::new(None::<String>, b)],
                             ret_ty: Box::from(ret)
                         },
                         ArcSpan::builtin(),
                         false
                     )
                 ))
+                .into_iter()
+                .chain([
+                    decl_unop!(Neg Int => Int),
+                    decl_unop!(Plus Int => Int),
+                    decl_unop!(Neg Float => Float),
+                    decl_unop!(Plus Float => Float),
+                    decl_unop!(Not Bool => Bool),
+                ]
+                .map(|(op, a, ret)| (
+                    FullIdentPath::new([Ident::UnOp(op, a.clone())]),
+                    Entity::new(
+                        Ty::Function {
+                            params: vec![ParamTy::new(None::<String>, a)],
+                            ret_ty: Box::from(ret)
+                        },
+                        ArcSpan::builtin(),
+                        false
+                    )
+                )))
+                .chain(intrinsics().into_iter().map(|i| (
+                    i.name,
+                    Entity::new(i.ty, ArcSpan::builtin(), false)
+                )))
+                .collect::<HashMap<_, _>>()
             ),
+            imports: Vec::new(),
+            kind: ScopeKind::Block,
         }
     }
     fn drop_ephemeral(&mut self) {
         self.entities.items.retain(|_, v| !v.ephemeral());
     }
+    /// Remove the items imported without `public using`
+    fn drop_private_imports(&mut self) {
+        for import in self.imports.extract_if(.., |i| !i.public) {
+            self.types.remove(&import.name);
+            self.entities.remove(&import.name);
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -198,6 +337,13 @@ impl<'s> ScopeWithStack<'s> {
     pub fn entities(&self) -> ItemSpaceWithStack<'s, Entity> {
         ItemSpaceWithStack { space: &self.scope.entities, stack: self.stack }
     }
+    /// If this is the scope of a function body, the function's return type
+    pub fn return_ty(&self) -> Option<&'s Ty> {
+        match &self.scope.kind {
+            ScopeKind::Function { return_ty } => Some(return_ty),
+            _ => None,
+        }
+    }
 }
 
 /// Used to pass the namespace stack from the checker to 
@@ -223,6 +369,30 @@ impl<'s> ScopeWithStackMut<'s> {
     pub fn entities_mut(self) -> ItemSpaceWithStackMut<'s, Entity> {
         ItemSpaceWithStackMut { space: &mut self.scope.entities, stack: self.stack }
     }
+    /// Mark this scope as the body of a function returning `ty`
+    pub fn set_return_ty(self, ty: Ty) {
+        self.scope.kind = ScopeKind::Function { return_ty: ty };
+    }
+    /// Mark this scope as a deferred expression
+    pub fn set_defer(self, span: ArcSpan) {
+        self.scope.kind = ScopeKind::Defer { span };
+    }
+    /// Mark this scope as the body of a loop. Does nothing if it already is 
+    /// one, so the `break`s found on earlier checking passes are kept
+    pub fn set_loop(self, span: ArcSpan, allow_break_value: bool, label: Option<(String, ArcSpan)>) {
+        if !matches!(self.scope.kind, ScopeKind::Loop { .. }) {
+            self.scope.kind = ScopeKind::Loop {
+                span, allow_break_value, breaks: Vec::new(), label, label_used: false
+            };
+        }
+    }
+    /// The values of the `break`s found in this loop so far
+    pub fn loop_breaks(self) -> &'s [(Ty, ArcSpan)] {
+        match &self.scope.kind {
+            ScopeKind::Loop { breaks, .. } => breaks,
+            _ => &[],
+        }
+    }
 }
 
 pub struct LeaveScope {
@@ -231,13 +401,65 @@ pub struct LeaveScope {
 
 impl Drop for LeaveScope {
     fn drop(&mut self) {
-        unsafe { self.checker.as_mut() }.unwrap().leave_scope();
+        // Panicking in a destructor while unwinding from an internal 
+        // compiler error would abort the process
+        if let Some(checker) = unsafe { self.checker.as_mut() } {
+            checker.leave_scope();
+        }
     }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct ScopeID(usize);
 
+/// Path of the item `member` declared for the type `ty`, like `Type::member`
+fn member_path(ty: &Ty, member: &str) -> IdentPath {
+    IdentPath::new(
+        ty.display(TyDisplay::qualified()).to_string().split("::").map(Ident::from).chain([Ident::from(member)]).collect::<Vec<_>>(),
+        false
+    )
+}
+
+/// Rotate a cycle of declarations so it starts from the one declared first, 
+/// so every member of the cycle finds the same one. Only that member reports 
+/// the cycle, so it isn't reported once for every declaration in it
+fn rotate_to_first_declared(mut cycle: Vec<(String, ArcSpan)>) -> Vec<(String, ArcSpan)> {
+    let first = cycle.iter()
+        .enumerate()
+        .min_by_key(|(_, (_, ArcSpan(src, range)))| (src.name(), range.start))
+        .map(|(i, _)| i)
+        .unwrap_or(0);
+    cycle.rotate_left(first);
+    cycle
+}
+
+/// Whether `inner` is completely inside `outer`
+fn span_contains(outer: Span, inner: &Span) -> bool {
+    *outer.0 == *inner.0 && outer.1.start <= inner.1.start && inner.1.end <= outer.1.end
+}
+
+/// Whether two function types have the same parameter types, so they can't 
+/// be told apart by the arguments of a call
+fn same_params(a: &Ty, b: &Ty) -> bool {
+    match (a, b) {
+        (Ty::Function { params: a, .. }, Ty::Function { params: b, .. }) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.ty.reduce() == b.ty.reduce())
+        }
+        _ => false,
+    }
+}
+
+fn is_method(ty: &Ty) -> bool {
+    matches!(ty, Ty::Function { params, .. } if params.first().is_some_and(|p| p.name.as_deref() == Some("this")))
+}
+
+/// The loop a `break` or `continue` refers to
+pub(crate) struct EnclosingLoop {
+    pub scope: ScopeID,
+    pub span: ArcSpan,
+    pub allow_break_value: bool,
+}
+
 pub struct ScopeIter<'s> {
     current: Option<ScopeID>,
     scopes: &'s Vec<Scope>,
@@ -253,36 +475,127 @@ impl<'s> ScopeIter<'s> {
 impl<'s> Iterator for ScopeIter<'s> {
     type Item = ScopeWithStack<'s>;
     fn next(&mut self) -> Option<Self::Item> {
-        let ret = self.scopes.get(self.current?.0).unwrap();
+        let ret = self.scopes.get(self.current?.0).ice("scope ID is not in this checker");
         self.current = ret.parent;
         Some(ScopeWithStack { scope: ret, stack: self.stack })
     }
 }
 
 pub struct Checker {
+    config: Arc<LanguageConfig>,
     logger: LoggerRef,
     current_scope: ScopeID,
     scopes: Vec<Scope>,
     namespace_stack: FullIdentPath,
     some_nodes_resolve_state_changed: bool,
+    /// Lints allowed by `@allow` attributes, and the spans of the items 
+    /// they were allowed on
+    allowed_lints: Vec<(ArcSpan, String)>,
+    /// Spans of items marked `@deprecated`, and the reason given if any
+    deprecations: Vec<(ArcSpan, Option<String>)>,
+    /// Type aliases that could not be resolved yet, and the name of the type 
+    /// they are defined as if it is a plain name
+    pending_type_aliases: HashMap<String, Option<String>>,
+    /// Imports that could not be resolved yet, by the name they import 
+    /// the item as, and the path of the item along with the import's span
+    pending_imports: HashMap<String, (String, ArcSpan)>,
+    /// Constants that could not be resolved yet, with the names their 
+    /// values refer to that could not be found and where they are declared
+    pending_consts: HashMap<String, (Vec<String>, ArcSpan)>,
+    /// The constant whose value is being checked, see `start_const`
+    current_const: Option<String>,
+    /// The type whose methods are being declared, if inside an `extends` 
+    /// block
+    extends_ty: Option<Ty>,
+    /// Label for the loop about to be checked, see `set_next_loop_label`
+    next_loop_label: Option<(String, ArcSpan)>,
+    /// Type expected of the trailing block about to be checked, see 
+    /// `set_trailing_block_ty`
+    trailing_block_ty: Option<Ty>,
+    /// Type and span of the left operand of a pipeline for the call about to 
+    /// be checked, see `set_piped_arg`
+    piped_arg: Option<(Ty, Option<ArcSpan>)>,
+    /// Whether the condition about to be checked is an assignment, see 
+    /// `set_assignment_in_condition`
+    assignment_in_condition: bool,
+    cancel: CancellationToken,
 }
 
 impl Checker {
-    fn new(logger: LoggerRef) -> Self {
+    pub(crate) fn new(config: Arc<LanguageConfig>, logger: LoggerRef) -> Self {
         Self {
+            config,
             logger: logger.clone(),
             current_scope: ScopeID(0),
             scopes: Vec::from([Scope::root()]),
             namespace_stack: FullIdentPath::default(),
             some_nodes_resolve_state_changed: false,
+            allowed_lints: Vec::new(),
+            deprecations: Vec::new(),
+            pending_type_aliases: HashMap::new(),
+            pending_imports: HashMap::new(),
+            pending_consts: HashMap::new(),
+            current_const: None,
+            extends_ty: None,
+            next_loop_label: None,
+            trailing_block_ty: None,
+            piped_arg: None,
+            assignment_in_condition: false,
+            cancel: CancellationToken::default(),
         }
     }
-    pub fn try_resolve(ast: &mut AST, pool: &mut NodePool, logger: LoggerRef) -> Ty {
-        let mut checker = Checker::new(logger.clone());
+    pub fn try_resolve(
+        ast: &mut AST, pool: &mut NodePool, config: Arc<LanguageConfig>, logger: LoggerRef
+    ) -> Ty {
+        Checker::new(config, logger).resolve_to_completion(ast, pool)
+    }
+    /// Check a list of ASTs that share a prelude. The ASTs in `prelude` are 
+    /// checked first, in order, and everything they declare at the top level 
+    /// is visible to the prelude ASTs after them and to every AST in `asts`
+    pub fn try_resolve_with_prelude(
+        prelude: &mut [AST], asts: &mut [AST],
+        pool: &mut NodePool, config: Arc<LanguageConfig>, logger: LoggerRef
+    ) -> Vec<Ty> {
+        let mut checker = Checker::new(config, logger);
+        for ast in prelude {
+            checker.resolve_prelude(ast, pool);
+        }
+        asts.iter_mut().map(|ast| checker.resolve_to_completion(ast, pool)).collect()
+    }
+    /// Stop checking as soon as `cancel` is cancelled
+    pub(crate) fn set_cancellation_token(&mut self, cancel: CancellationToken) {
+        self.cancel = cancel;
+    }
+    /// Check an AST and make everything it declares at the top level visible 
+    /// to the ASTs checked after it
+    pub(crate) fn resolve_prelude(&mut self, ast: &mut AST, pool: &mut NodePool) -> Ty {
+        let ty = self.resolve_to_completion(ast, pool);
+        // Check the following ASTs inside the scope of this one. Only the 
+        // `public using`s of this AST are visible to them
+        if let Some(scope) = ast.get(pool).scope() {
+            self.scopes[scope.0].drop_private_imports();
+            self.current_scope = scope;
+        }
+        ty
+    }
+    pub(crate) fn resolve_to_completion(&mut self, ast: &mut AST, pool: &mut NodePool) -> Ty {
+        let ty = self.resolve_until_done(ast, pool);
+        if !self.cancel.is_cancelled() {
+            self.lint_line_endings(ast, pool);
+            self.lint_future_keywords(ast, pool);
+        }
+        ty
+    }
+    fn resolve_until_done(&mut self, ast: &mut AST, pool: &mut NodePool) -> Ty {
         for i in 0.. {
+            // Unresolved nodes are not reported when cancelled, since the 
+            // result is going to be discarded anyway
+            if self.cancel.is_cancelled() {
+                return Ty::Invalid;
+            }
             // todo: allow customizing max loop count via a compiler option
             if i > 1000 {
-                checker.logger.lock().unwrap().log(Message::new(
+                self.logger.lock().unwrap().log(Message::new(
                     Level::Error,
                     "Internal error: maximum check loop count reached (1000)",
                     Span::builtin()
@@ -293,36 +606,283 @@ impl Checker {
                 return Ty::Invalid;
             }
             // Reset node state marker
-            checker.some_nodes_resolve_state_changed = false;
-            if let Some(r) = ast.try_resolve_ref(pool, &mut checker) {
+            self.some_nodes_resolve_state_changed = false;
+            if let Some(r) = ast.try_resolve_ref(pool, self) {
                 return r;
             }
             // If no nodes' states changed, then we have ended up in an 
             // infinite unresolvable loop
-            if !checker.some_nodes_resolve_state_changed {
-                pool.release_unresolved(&checker, logger);
+            if !self.some_nodes_resolve_state_changed {
+                pool.release_unresolved(self, self.logger.clone());
                 return Ty::Invalid;
             }
         }
-        unreachable!()
+        ice!("check loop ended without a result")
     }
 
     pub fn scopes(&self) -> ScopeIter {
         ScopeIter::new(self.current_scope, &self.scopes, &self.namespace_stack)
     }
+    /// Iterate `scope` and the scopes it is inside of, innermost first
+    pub(crate) fn scopes_from(&self, scope: ScopeID) -> ScopeIter<'_> {
+        ScopeIter::new(scope, &self.scopes, &self.namespace_stack)
+    }
     pub fn scope(&mut self) -> ScopeWithStackMut {
         ScopeWithStackMut {
-            scope: self.scopes.get_mut(self.current_scope.0).unwrap(),
+            scope: self.scopes.get_mut(self.current_scope.0).ice("current scope is not in this checker"),
             stack: &self.namespace_stack
         }
     }
+    /// The return type of the innermost function being checked, or `None` 
+    /// if not inside a function
+    pub fn return_ty(&self) -> Option<Ty> {
+        self.scopes().find_map(|s| s.return_ty().cloned())
+    }
+    /// The type of the items yielded when iterating over a value of type 
+    /// `ty` with a `for` loop, if it can be iterated over
+    /// 
+    /// Besides the builtin iterable types, a type `X` is iterable if there 
+    /// is a function `X::next(value: X) -> Item?`, which gives the next item 
+    /// or `none` when there are no items left
+    pub fn iter_item(&self, ty: &Ty) -> Option<Ty> {
+        if let Some(item) = ty.iter_item() {
+            return Some(item);
+        }
+        let next = self.scopes().find_map(|s| s.entities().find(&member_path(ty, "next")))?;
+        match next.ty() {
+            Ty::Function { params, ret_ty } if params.len() == 1 && ty.convertible(&params[0].ty) => {
+                match ret_ty.reduce() {
+                    Ty::Option { ty } => Some(ty.as_ref().clone()),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+    /// Set the type whose methods are being declared, returning the 
+    /// previous one
+    pub(crate) fn set_extends_ty(&mut self, ty: Option<Ty>) -> Option<Ty> {
+        std::mem::replace(&mut self.extends_ty, ty)
+    }
+    pub(crate) fn extends_ty(&self) -> Option<&Ty> {
+        self.extends_ty.as_ref()
+    }
+    /// Find the method `name` of `ty`, returning its function type and 
+    /// where it was declared. Methods are functions named `Type::name` whose 
+    /// first parameter is `this`
+    pub(crate) fn find_method(&self, ty: &Ty, name: &str) -> Option<(Ty, ArcSpan)> {
+        let path = member_path(ty.reduce(), name);
+        self.scopes()
+            .filter_map(|s| s.entities().find(&path))
+            .map(|e| (e.ty(), e.span()))
+            .find(|(ty, _)| is_method(ty))
+    }
+    /// Names of all the methods of `ty` visible in the current scope, sorted 
+    /// so the result doesn't depend on hash map iteration order
+    pub(crate) fn method_names(&self, ty: &Ty) -> Vec<String> {
+        let parent = member_path(ty.reduce(), "_").to_full().parent();
+        let mut names = self.scopes()
+            .flat_map(|s| s.entities().iter())
+            .filter(|(path, e)| path.parent() == parent && is_method(&e.ty()))
+            .filter_map(|(path, _)| path.last().map(|i| i.to_string()))
+            .collect::<Vec<_>>();
+        names.sort_unstable();
+        names.dedup();
+        names
+    }
+    /// Find the loop that `break` and `continue` refer to, which is the 
+    /// innermost one or the one named `label` if given. Loops outside the 
+    /// current function are not considered
+    pub(crate) fn enclosing_loop(&self, label: Option<&str>) -> Option<EnclosingLoop> {
+        let mut current = Some(self.current_scope);
+        while let Some(id) = current {
+            let scope = &self.scopes[id.0];
+            match &scope.kind {
+                ScopeKind::Loop { span, allow_break_value, label: loop_label, .. } if
+                    label.is_none_or(|l| loop_label.as_ref().is_some_and(|(name, _)| name == l)) =>
+                {
+                    return Some(EnclosingLoop {
+                        scope: id,
+                        span: span.clone(),
+                        allow_break_value: *allow_break_value,
+                    });
+                }
+                ScopeKind::Function { .. } | ScopeKind::Defer { .. } => return None,
+                ScopeKind::Loop { .. } | ScopeKind::Block => current = scope.parent,
+            }
+        }
+        None
+    }
+    /// Find the `defer` that the current scope is inside of, if any. With 
+    /// `inside_loop`, a `defer` outside the innermost loop doesn't count, 
+    /// as a `break` or `continue` there only leaves the loop
+    pub(crate) fn enclosing_defer(&self, inside_loop: bool) -> Option<ArcSpan> {
+        let mut current = Some(self.current_scope);
+        while let Some(id) = current {
+            let scope = &self.scopes[id.0];
+            match &scope.kind {
+                ScopeKind::Defer { span } => return Some(span.clone()),
+                ScopeKind::Function { .. } => return None,
+                ScopeKind::Loop { .. } if inside_loop => return None,
+                ScopeKind::Loop { .. } | ScopeKind::Block => current = scope.parent,
+            }
+        }
+        None
+    }
+    /// Labels of the loops that `break` and `continue` can refer to
+    pub(crate) fn loop_labels(&self) -> Vec<String> {
+        let mut labels = Vec::new();
+        let mut current = Some(self.current_scope);
+        while let Some(id) = current {
+            let scope = &self.scopes[id.0];
+            match &scope.kind {
+                ScopeKind::Function { .. } | ScopeKind::Defer { .. } => break,
+                ScopeKind::Loop { label: Some((name, _)), .. } => labels.push(name.clone()),
+                _ => {}
+            }
+            current = scope.parent;
+        }
+        labels
+    }
+    /// Record that the label of the loop `scope` has been referred to
+    pub(crate) fn use_loop_label(&mut self, scope: ScopeID) {
+        if let ScopeKind::Loop { label_used, .. } = &mut self.scopes[scope.0].kind {
+            *label_used = true;
+        }
+    }
+    /// Give the next loop that is checked the label `label`
+    pub(crate) fn set_next_loop_label(&mut self, label: String, span: ArcSpan) {
+        self.next_loop_label = Some((label, span));
+    }
+    /// Take the label given by `set_next_loop_label`, if any
+    pub(crate) fn take_next_loop_label(&mut self) -> Option<(String, ArcSpan)> {
+        self.next_loop_label.take()
+    }
+    /// Give the next trailing block that is checked the type `ty`, which is 
+    /// the type of the parameter it is passed to
+    pub(crate) fn set_trailing_block_ty(&mut self, ty: Ty) {
+        self.trailing_block_ty = Some(ty);
+    }
+    /// Take the type given by `set_trailing_block_ty`, if any
+    pub(crate) fn take_trailing_block_ty(&mut self) -> Option<Ty> {
+        self.trailing_block_ty.take()
+    }
+    /// Pass a value of type `ty` as the first argument of the next call that 
+    /// is checked, like the `a` in `a |> f(b)`
+    pub(crate) fn set_piped_arg(&mut self, ty: Ty, span: Option<ArcSpan>) {
+        self.piped_arg = Some((ty, span));
+    }
+    /// Take the argument given by `set_piped_arg`, if any
+    pub(crate) fn take_piped_arg(&mut self) -> Option<(Ty, Option<ArcSpan>)> {
+        self.piped_arg.take()
+    }
+    /// Mark the condition about to be checked as an assignment like 
+    /// `if x = 5`, which is checked as a comparison instead
+    pub(crate) fn set_assignment_in_condition(&mut self) {
+        self.assignment_in_condition = true;
+    }
+    /// Take the mark set by `set_assignment_in_condition`
+    pub(crate) fn take_assignment_in_condition(&mut self) -> bool {
+        std::mem::take(&mut self.assignment_in_condition)
+    }
+    /// Declare the function `name` of type `ty` in the current scope. If a 
+    /// function with the same name already exists, the functions are made 
+    /// into an overload set, as long as their parameter types differ
+    pub(crate) fn declare_function(&mut self, name: &IdentPath, ty: Ty, span: ArcSpan) {
+        let (old_ty, old_span, old_ephemeral) = match self.scope().entities_mut().try_push(
+            name, Entity::new(ty.clone(), span.clone(), false)
+        ) {
+            Ok(_) => return,
+            Err(old) => (old.ty(), old.span(), old.ephemeral()),
+        };
+        let mut overloads = match old_ty {
+            Ty::Function { .. } if !old_ephemeral && matches!(ty, Ty::Function { .. }) => {
+                vec![(old_ty, old_span.clone())]
+            }
+            Ty::Overloaded { overloads, .. } if matches!(ty, Ty::Function { .. }) => overloads,
+            _ => {
+                self.logger.lock().unwrap().log(Message::new(
+                    Level::Error,
+                    format!("Name {} has already been defined", name),
+                    span.as_ref()
+                ).note(Note::new_at("Previous definition here", old_span.as_ref())));
+                return;
+            }
+        };
+        if let Some((_, prev)) = overloads.iter().find(|(o, _)| same_params(o, &ty)) {
+            self.logger.lock().unwrap().log(Message::new(
+                Level::Error,
+                format!("Function {name} has already been defined with the same parameter types"),
+                span.as_ref()
+            ).note(Note::new_at("Previous definition here", prev.as_ref())));
+            return;
+        }
+        overloads.push((ty, span));
+        self.scope().entities_mut().replace(
+            name,
+            Entity::new(Ty::Overloaded { name: name.to_string(), overloads }, old_span, false)
+        );
+    }
+    /// Bring the type and entity named `target` into the current scope as 
+    /// `name`
+    pub(crate) fn import(&mut self, target: &FullIdentPath, name: &IdentPath, span: ArcSpan, public: bool) {
+        let ty = self.scopes().find_map(|s| s.types().get(target).cloned());
+        let entity = self.scopes()
+            .find_map(|s| s.entities().get(target).map(|e| Entity::new(e.ty(), e.span(), false).with_value(e.value())));
+        if ty.is_none() && entity.is_none() {
+            return;
+        }
+        let full_name = self.namespace_stack.join(name);
+        let scope = &self.scopes[self.current_scope.0];
+        if let Some(prev) = scope.imports.iter().find(|i| i.name == full_name) {
+            let msg = if prev.target == *target {
+                Message::new(Level::Warning, format!("{target} is already imported"), span.as_ref())
+                    .note(Note::new_at("First imported here", prev.span.as_ref()))
+            }
+            else {
+                Message::new(
+                    Level::Error,
+                    format!("Ambiguous name {name}, imported from both {} and {target}", prev.target),
+                    span.as_ref()
+                )
+                .note(Note::new_at(format!("{} imported here", prev.target), prev.span.as_ref()))
+                .note(Note::new_at(format!("{target} imported here"), span.as_ref()))
+            };
+            self.logger.lock().unwrap().log(msg);
+            return;
+        }
+        let mut conflict = None;
+        if let Some(ty) = ty {
+            conflict = self.scope().types_mut().try_push(name, ty).err().map(|t| t.span());
+        }
+        if let Some(entity) = entity {
+            conflict = conflict.or(self.scope().entities_mut().try_push(name, entity).err().map(|e| e.span()));
+        }
+        if let Some(prev) = conflict {
+            self.logger.lock().unwrap().log(Message::new(
+                Level::Error,
+                format!("Item {name} has already been defined in this scope"),
+                span.as_ref()
+            ).note(Note::new_at("Previous definition here", prev.as_ref())));
+        }
+        self.scopes[self.current_scope.0].imports.push(Import {
+            target: target.clone(), name: full_name, span, public
+        });
+    }
+    /// Record a `break` out of the loop `scope` with a value of type `ty`
+    pub(crate) fn add_loop_break(&mut self, scope: ScopeID, ty: Ty, span: ArcSpan) {
+        if let ScopeKind::Loop { breaks, .. } = &mut self.scopes[scope.0].kind {
+            breaks.push((ty, span));
+        }
+    }
     pub fn enter_scope(&mut self, scope: &mut Option<ScopeID>) -> LeaveScope {
         match scope {
             Some(scope) => self.current_scope = *scope,
             None => {
-                *scope = Some(ScopeID(self.scopes.len()));
+                let id = ScopeID(self.scopes.len());
                 self.scopes.push(Scope::new(self.current_scope));
-                self.current_scope = scope.unwrap();
+                *scope = Some(id);
+                self.current_scope = id;
             }
         }
         LeaveScope { checker: self }
@@ -341,6 +901,113 @@ impl Checker {
         self.namespace_stack.pop();
     }
 
+    /// Mark a type alias as not resolved yet. `target` is the name of the 
+    /// type it is defined as
+    pub(crate) fn set_type_alias_pending(&mut self, name: String, target: Option<String>) {
+        self.pending_type_aliases.insert(name, target);
+    }
+    pub(crate) fn set_type_alias_resolved(&mut self, name: &str) {
+        self.pending_type_aliases.remove(name);
+    }
+    /// If the type alias `name` is (indirectly) defined in terms of itself, 
+    /// returns the names of the aliases in the cycle starting from `name`
+    pub(crate) fn type_alias_cycle(&self, name: &str) -> Option<Vec<String>> {
+        let mut cycle = vec![name.to_string()];
+        let mut next = self.pending_type_aliases.get(name)?.as_ref()?;
+        while next != name {
+            if cycle.contains(next) {
+                // The cycle does not go through `name`
+                return None;
+            }
+            cycle.push(next.clone());
+            next = self.pending_type_aliases.get(next)?.as_ref()?;
+        }
+        Some(cycle)
+    }
+
+    /// Mark an import of `target` as `name` as not resolved yet
+    pub(crate) fn set_import_pending(&mut self, name: String, target: String, span: ArcSpan) {
+        self.pending_imports.insert(name, (target, span));
+    }
+    pub(crate) fn set_import_resolved(&mut self, name: &str) {
+        self.pending_imports.remove(name);
+    }
+    /// If the import named `name` (indirectly) imports itself, returns the 
+    /// names and spans of the imports in the cycle, starting from the one 
+    /// declared first. See `rotate_to_first_declared`
+    pub(crate) fn import_cycle(&self, name: &str) -> Option<Vec<(String, ArcSpan)>> {
+        let (target, span) = self.pending_imports.get(name)?;
+        let mut cycle = vec![(name.to_string(), span.clone())];
+        let mut next = target;
+        while next != name {
+            if cycle.iter().any(|(n, _)| n == next) {
+                // The cycle does not go through `name`
+                return None;
+            }
+            let (target, span) = self.pending_imports.get(next)?;
+            cycle.push((next.clone(), span.clone()));
+            next = target;
+        }
+        Some(rotate_to_first_declared(cycle))
+    }
+
+    /// Start checking the value of the constant `name`. Until `end_const` 
+    /// is called, the names that can't be found are recorded as 
+    /// dependencies of the constant. Returns the constant that was being 
+    /// checked before, which should be passed to `end_const`
+    pub(crate) fn start_const(&mut self, name: String, span: ArcSpan) -> Option<String> {
+        self.pending_consts.insert(name.clone(), (Vec::new(), span));
+        self.current_const.replace(name)
+    }
+    pub(crate) fn end_const(&mut self, prev: Option<String>) {
+        self.current_const = prev;
+    }
+    pub(crate) fn set_const_resolved(&mut self, name: &str) {
+        self.pending_consts.remove(name);
+    }
+    /// Record that the value of the constant being checked refers to `name`, 
+    /// which could not be found yet
+    pub(crate) fn add_const_dependency(&mut self, name: String) {
+        if let Some(deps) = self.current_const.as_ref().and_then(|c| self.pending_consts.get_mut(c)) {
+            deps.0.push(name);
+        }
+    }
+    /// Whether `name` is a constant that could not be resolved yet
+    pub(crate) fn is_const_pending(&self, name: &str) -> bool {
+        self.pending_consts.contains_key(name)
+    }
+    /// If the value of the constant `name` (indirectly) refers to itself, 
+    /// returns the names and spans of the constants in the cycle, starting 
+    /// from the one declared first. See `rotate_to_first_declared`
+    pub(crate) fn const_cycle(&self, name: &str) -> Option<Vec<(String, ArcSpan)>> {
+        let mut cycle = vec![name.to_string()];
+        self.find_const_cycle(name, &mut cycle).then(|| {
+            rotate_to_first_declared(cycle.into_iter().map(|n| {
+                let span = self.pending_consts[&n].1.clone();
+                (n, span)
+            }).collect())
+        })
+    }
+    fn find_const_cycle(&self, start: &str, cycle: &mut Vec<String>) -> bool {
+        let Some((deps, _)) = cycle.last().and_then(|c| self.pending_consts.get(c)) else {
+            return false;
+        };
+        for dep in deps {
+            if dep == start {
+                return true;
+            }
+            if cycle.contains(dep) || !self.pending_consts.contains_key(dep) {
+                continue;
+            }
+            cycle.push(dep.clone());
+            if self.find_const_cycle(start, cycle) {
+                return true;
+            }
+            cycle.pop();
+        }
+        false
+    }
+
     pub fn mark_some_nodes_resolve_state_changed(&mut self) {
         self.some_nodes_resolve_state_changed = true;
     }
@@ -359,13 +1026,15 @@ impl Checker {
         }
         true
     }
+    /// Check that a value of type `b` can be used where `a` is expected
     pub fn expect_ty_eq(&self, a: Ty, b: Ty, span: Option<ArcSpan>) -> Ty {
         if self.expect_ty_decided(a.clone(), span.clone()) &&
             self.expect_ty_decided(b.clone(), span.clone()) {
             if !b.convertible(&a) {
+                let opts = TyDisplay::distinguishing(&a, &b);
                 self.logger.lock().unwrap().log(Message::new(
                     Level::Error,
-                    format!("Cannot convert from type {b} to {a}"),
+                    format!("Cannot convert from type {} to {}", b.display(opts), a.display(opts)),
                     span.unwrap_or(ArcSpan::builtin()).as_ref()
                 ));
             }
@@ -375,8 +1044,241 @@ impl Checker {
             Ty::Invalid
         }
     }
+    /// Merge the types of two branches that both give the value of a 
+    /// `what` expression, like the branches of an `if`. The result is 
+    /// whichever of the types the other one can be converted to
+    pub fn merge_branch_tys(
+        &self,
+        what: &str,
+        (a, a_span): (Ty, Option<ArcSpan>),
+        (b, b_span): (Ty, Option<ArcSpan>),
+        span: Option<ArcSpan>
+    ) -> Ty {
+        if !self.expect_ty_decided(a.clone(), span.clone()) || !self.expect_ty_decided(b.clone(), span.clone()) {
+            return Ty::Invalid;
+        }
+        if b.convertible(&a) {
+            return a.or(b);
+        }
+        if a.convertible(&b) {
+            return b;
+        }
+        let span = span.unwrap_or_default();
+        let opts = TyDisplay::distinguishing(&a, &b);
+        let mut msg = Message::new(
+            Level::Error,
+            format!("Branches of {what} have incompatible types {} and {}", a.display(opts), b.display(opts)),
+            span.as_ref()
+        );
+        for (ty, span) in [(&a, &a_span), (&b, &b_span)] {
+            if let Some(span) = span {
+                msg = msg.note(Note::new_at(format!("This branch has type {}", ty.display(opts)), span.as_ref()));
+            }
+        }
+        self.logger.lock().unwrap().log(msg);
+        Ty::Invalid
+    }
     
+    /// Silence `lint` for everything inside `span`
+    pub fn allow_lint(&mut self, span: ArcSpan, lint: String) {
+        if !self.allowed_lints.iter().any(|(s, l)| *s == span && *l == lint) {
+            self.allowed_lints.push((span, lint));
+        }
+    }
+    /// Report `msg` as an instance of `lint`, unless the lint has been 
+    /// allowed where the message points to. The level of the message is 
+    /// decided by the lint's configured level
+    pub fn lint(&self, lint: &Lint, mut msg: Message) {
+        let level = self.config.lints.get(lint.name).copied().unwrap_or(lint.default_level);
+        let allowed = self.allowed_lints.iter().any(|(span, name)|
+            name == lint.name && span_contains(span.as_ref(), &msg.span)
+        );
+        msg.level = match level {
+            _ if allowed => return,
+            LintLevel::Allow => return,
+            LintLevel::Warn => Level::Warning,
+            LintLevel::Deny => Level::Error,
+        };
+        self.logger.lock().unwrap().log(msg.code(lint.name));
+    }
+    /// Mark everything declared within `span` as deprecated
+    pub(crate) fn deprecate(&mut self, span: ArcSpan, reason: Option<String>) {
+        if !self.deprecations.iter().any(|(s, _)| *s == span) {
+            self.deprecations.push((span, reason));
+        }
+    }
+    /// Warn about a use of `name` at `use_span` if the item declared at 
+    /// `decl_span` is deprecated. Uses inside the deprecated item itself are 
+    /// fine
+    pub(crate) fn lint_deprecated_use(&self, name: &str, decl_span: &ArcSpan, use_span: &ArcSpan) {
+        let Some((_, reason)) = self.deprecations.iter().find(|(span, _)|
+            span_contains(span.as_ref(), &decl_span.as_ref()) &&
+                !span_contains(span.as_ref(), &use_span.as_ref())
+        ) else {
+            return;
+        };
+        self.lint(&DEPRECATED, Message::new(
+            Level::Warning,
+            match reason {
+                Some(reason) => format!("{name} is deprecated: {reason}"),
+                None => format!("{name} is deprecated"),
+            },
+            use_span.as_ref()
+        ).note(Note::new_at("Declared here", decl_span.as_ref())));
+    }
+    /// Report variables declared in the current scope that were never used. 
+    /// Should be called once everything in the scope has been resolved
+    pub(crate) fn lint_unused_variables(&mut self) {
+        let mut unused = self.scopes[self.current_scope.0].entities.items.iter()
+            .filter_map(|(name, ent)| Some((name.last()?.to_string(), ent)))
+            .filter(|(_, ent)| ent.ephemeral() && !ent.used())
+            .map(|(name, ent)| (name, ent.span()))
+            .collect::<Vec<_>>();
+        unused.sort_by_key(|(_, span)| span.1.start);
+        for (name, span) in unused {
+            self.lint_unused_variable(&name, &span);
+        }
+    }
+    /// Warn once per file about line breaks that differ from the one that 
+    /// ends the first line of the file
+    fn lint_line_endings(&self, ast: &AST, pool: &NodePool) {
+        let Some(ArcSpan(src, _)) = pool.span_of(ast) else { return };
+        let data = src.data();
+        // Spans of every line break
+        let mut breaks = data.match_indices('\n').map(|(i, _)|
+            if data[..i].ends_with('\r') { i - 1..i + 1 } else { i..i + 1 }
+        );
+        let Some(first) = breaks.next() else { return };
+        let name = |crlf: bool| if crlf { "CRLF (\\r\\n)" } else { "LF (\\n)" };
+        let first_crlf = first.len() == 2;
+        let differing = breaks.filter(|b| (b.len() == 2) != first_crlf).collect::<Vec<_>>();
+        let Some(at) = differing.first() else { return };
+        self.lint(&MIXED_LINE_ENDINGS, Message::new(
+            Level::Warning,
+            format!(
+                "{} {} with {} while the first line ends with {}",
+                differing.len(),
+                if differing.len() == 1 { "line ends" } else { "lines end" },
+                name(!first_crlf), name(first_crlf)
+            ),
+            Span(&src, at.clone())
+        ).note(Note::new("Convert the file to use the same line endings throughout", true)));
+    }
+    /// Warn about identifiers that are keywords in a later edition, so code 
+    /// can be renamed before switching editions
+    fn lint_future_keywords(&self, ast: &AST, pool: &NodePool) {
+        if self.config.edition == Edition::LATEST {
+            return;
+        }
+        let Some(ArcSpan(src, _)) = pool.span_of(ast) else { return };
+        // Any errors in the source have already been reported by the parser
+        let tokens = Tokenizer::new(&src, self.config.clone(), Logger::empty())
+            .flat_map(|t| t.flatten());
+        for token in tokens {
+            // Contextual keywords are already keywords in this edition
+            if !matches!(token.kind, TokenKind::Ident) || self.config.keyword_kind(&token.raw).is_some() {
+                continue;
+            }
+            let Some(edition) = self.config.edition.later_keyword_edition(&token.raw) else { continue };
+            self.lint(&FUTURE_KEYWORD, Message::new(
+                Level::Warning,
+                format!("'{}' is a keyword in edition {edition}", token.raw),
+                token.span
+            ).note(Note::new(
+                format!("Rename this to keep the code compiling in edition {edition}"),
+                true
+            )));
+        }
+    }
+    fn lint_unused_variable(&self, name: &str, span: &ArcSpan) {
+        self.lint(&UNUSED_VARIABLE, Message::new(
+            Level::Warning,
+            format!("Unused variable {name}"),
+            span.as_ref()
+        ).note(Note::new("Remove the variable, or add @allow(unused_variable) if it is intentional", true)));
+    }
+    /// Declare a variable in the current scope. Inside functions a variable 
+    /// may shadow an earlier one with the same name, which is reported by 
+    /// the opt-in `shadowing` lint. At the top level, declaring a name twice 
+    /// is an error
+    pub(crate) fn declare_variable(&mut self, name: &IdentPath, entity: Entity) {
+        let span = entity.span();
+        let top_level = self.return_ty().is_none();
+        let existing = self.scopes[self.current_scope.0].entities
+            .get_declared(name, &self.namespace_stack)
+            .map(|e| (e.ephemeral(), e.span()));
+        let shadowed = match existing {
+            Some((ephemeral, old_span)) if top_level || !ephemeral => {
+                let mut msg = Message::new(
+                    Level::Error,
+                    format!("Item {} has already been defined in this scope", name),
+                    span.as_ref()
+                ).note(Note::new_at("Previous definition here", old_span.as_ref()));
+                if ephemeral {
+                    msg = msg.note(Note::new("Variables can only be shadowed inside functions", true));
+                }
+                self.logger.lock().unwrap().log(msg);
+                return;
+            }
+            Some(_) => {
+                let old = self.scope().entities_mut().replace(name, entity)
+                    .ice_at("shadowed variable is not in scope", &span);
+                // Nothing can refer to the old variable anymore
+                if !old.used() {
+                    self.lint_unused_variable(&name.to_string(), &old.span());
+                }
+                Some(old.span())
+            }
+            None => {
+                let outer = self.scopes().skip(1)
+                    .find_map(|s| s.entities().find(name))
+                    .filter(|e| e.ephemeral())
+                    .map(|e| e.span());
+                let _ = self.scope().entities_mut().try_push(name, entity);
+                outer
+            }
+        };
+        if let Some(old_span) = shadowed {
+            self.lint(&SHADOWING, Message::new(
+                Level::Warning,
+                format!("Variable {name} shadows an earlier variable"),
+                span.as_ref()
+            ).note(Note::new_at("Shadowed variable here", old_span.as_ref())));
+        }
+    }
+    /// Report the label of the loop in the current scope if nothing refers 
+    /// to it. Should be called once the loop's body has been resolved
+    pub(crate) fn lint_unused_label(&self) {
+        if let ScopeKind::Loop { label: Some((name, span)), label_used: false, .. } =
+            &self.scopes[self.current_scope.0].kind
+        {
+            self.lint(&UNUSED_LABEL, Message::new(
+                Level::Warning,
+                format!("Unused label {name}"),
+                span.as_ref()
+            ).note(Note::new("Remove the label, or add @allow(unused_label) if it is intentional", true)));
+        }
+    }
+    /// Report the code at `span`, which comes after the expression at 
+    /// `never_span` that never finishes
+    pub(crate) fn lint_unreachable(&self, span: &ArcSpan, never_span: &ArcSpan) {
+        self.lint(&UNREACHABLE_CODE, Message::new(
+            Level::Warning,
+            "Unreachable code",
+            span.as_ref()
+        ).note(Note::new_at("Any code after this expression is unreachable", never_span.as_ref())));
+    }
+
     pub fn logger(&self) -> LoggerRef {
         self.logger.clone()
     }
+    /// The language settings the code is being checked with
+    pub fn config(&self) -> &LanguageConfig {
+        &self.config
+    }
+    /// The edition the code is being checked with, for gating changes in 
+    /// semantics
+    pub fn edition(&self) -> Edition {
+        self.config.edition
+    }
 }
@@ -0,0 +1,165 @@
+
+use std::fmt::Display;
+use crate::{
+    ast::token::op::{BinaryOp, UnaryOp},
+    parser::parse::{NodePool, RefToNode, Node},
+    shared::src::ArcSpan,
+};
+use super::{resolve::ResolveNode, ty::Ty};
+
+/// A value computed at compile time
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConstValue {
+    Void,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    Bytes(Vec<u8>),
+    Char(char),
+}
+
+impl Display for ConstValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Void => f.write_str("void"),
+            Self::Bool(b) => write!(f, "{b}"),
+            Self::Int(i) => write!(f, "{i}"),
+            Self::Float(n) => write!(f, "{n}"),
+            Self::String(s) => write!(f, "{s:?}"),
+            Self::Bytes(b) => write!(f, "b\"{}\"", b.escape_ascii()),
+            Self::Char(c) => write!(f, "{c:?}"),
+        }
+    }
+}
+
+/// Why an expression could not be evaluated at compile time
+#[derive(Debug)]
+pub struct ConstError {
+    pub info: String,
+    pub span: ArcSpan,
+}
+
+impl ConstValue {
+    /// Convert this to a value of type `ty`, for int literals that were 
+    /// adapted to floats
+    pub(crate) fn adapted(self, ty: &Ty) -> Self {
+        match (self, ty.reduce()) {
+            (Self::Int(i), Ty::Float) => Self::Float(i as f64),
+            (value, _) => value,
+        }
+    }
+}
+
+impl ConstError {
+    pub fn new<S: Into<String>>(info: S, span: ArcSpan) -> Self {
+        Self { info: info.into(), span }
+    }
+    /// The expression at `node` isn't one that can be evaluated at compile
+    /// time at all, like a function call
+    pub(crate) fn not_const<N: Node + ?Sized>(node: &N, pool: &NodePool) -> Self {
+        Self::new("This expression can't be evaluated at compile time", node.span_or_builtin(pool))
+    }
+}
+
+pub trait EvalConst {
+    /// Evaluate this expression at compile time. Should only be called on
+    /// nodes that have been resolved without errors, as the evaluator
+    /// relies on the typechecker having rejected invalid operations
+    fn eval_const(&self, pool: &NodePool) -> Result<ConstValue, ConstError>;
+}
+
+impl<T: ResolveNode + EvalConst> EvalConst for RefToNode<T> {
+    fn eval_const(&self, pool: &NodePool) -> Result<ConstValue, ConstError> {
+        self.get(pool).eval_const(pool)
+    }
+}
+
+pub(crate) fn eval_unary(op: UnaryOp, value: ConstValue) -> Result<ConstValue, String> {
+    match (op, value) {
+        (UnaryOp::Not, ConstValue::Bool(b)) => Ok(ConstValue::Bool(!b)),
+        (UnaryOp::Plus, v @ (ConstValue::Int(_) | ConstValue::Float(_))) => Ok(v),
+        (UnaryOp::Neg, ConstValue::Int(i)) => i.checked_neg()
+            .map(ConstValue::Int)
+            .ok_or_else(|| String::from("Integer overflow")),
+        (UnaryOp::Neg, ConstValue::Float(f)) => Ok(ConstValue::Float(-f)),
+        (op, value) => Err(format!("Operator '{op}' can't be applied to {value} at compile time")),
+    }
+}
+
+pub(crate) fn eval_binary(a: ConstValue, op: BinaryOp, b: ConstValue) -> Result<ConstValue, String> {
+    use ConstValue as V;
+
+    fn overflow<T>(value: Option<T>) -> Result<T, String> {
+        value.ok_or_else(|| String::from("Integer overflow"))
+    }
+    fn nonzero(b: i64) -> Result<i64, String> {
+        if b == 0 { Err(String::from("Division by zero")) } else { Ok(b) }
+    }
+
+    Ok(match (a, op, b) {
+        (V::Int(a), BinaryOp::Add, V::Int(b)) => V::Int(overflow(a.checked_add(b))?),
+        (V::Int(a), BinaryOp::Sub, V::Int(b)) => V::Int(overflow(a.checked_sub(b))?),
+        (V::Int(a), BinaryOp::Mul, V::Int(b)) => V::Int(overflow(a.checked_mul(b))?),
+        (V::Int(a), BinaryOp::Div, V::Int(b)) => V::Int(overflow(a.checked_div(nonzero(b)?))?),
+        (V::Int(a), BinaryOp::Mod, V::Int(b)) => V::Int(overflow(a.checked_rem(nonzero(b)?))?),
+        (V::Int(a), BinaryOp::Pow, V::Int(b)) => V::Int(overflow(a.checked_pow(
+            u32::try_from(b).map_err(|_| "Can't raise an integer to a negative power")?
+        ))?),
+
+        (V::Int(a), BinaryOp::BitAnd, V::Int(b)) => V::Int(a & b),
+        (V::Int(a), BinaryOp::BitOr, V::Int(b)) => V::Int(a | b),
+        (V::Int(a), BinaryOp::BitXor, V::Int(b)) => V::Int(a ^ b),
+        (V::Int(a), op @ (BinaryOp::Shl | BinaryOp::Shr), V::Int(b)) => {
+            let shifted = u32::try_from(b).ok().and_then(|b| match op {
+                BinaryOp::Shl => a.checked_shl(b),
+                _ => a.checked_shr(b),
+            });
+            V::Int(shifted.ok_or_else(|| format!("Can't shift an int by {b} bits"))?)
+        }
+
+        (V::Float(a), BinaryOp::Add, V::Float(b)) => V::Float(a + b),
+        (V::Float(a), BinaryOp::Sub, V::Float(b)) => V::Float(a - b),
+        (V::Float(a), BinaryOp::Mul, V::Float(b)) => V::Float(a * b),
+        (V::Float(a), BinaryOp::Div, V::Float(b)) => V::Float(a / b),
+        (V::Float(a), BinaryOp::Mod, V::Float(b)) => V::Float(a % b),
+        (V::Float(a), BinaryOp::Pow, V::Float(b)) => V::Float(a.powf(b)),
+        (V::Int(a), op @ (BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div), V::Float(b)) => {
+            return eval_binary(V::Float(a as f64), op, V::Float(b));
+        }
+        (V::Int(a), BinaryOp::Mod, V::Float(b)) => V::Int((a as f64 % b) as i64),
+        (V::Float(a), BinaryOp::Mod, V::Int(b)) => V::Float(a % b as f64),
+
+        (V::String(a), BinaryOp::Add, V::String(b)) => V::String(a + &b),
+        (V::String(a), BinaryOp::Mul, V::Int(b)) => {
+            V::String(a.repeat(usize::try_from(b).map_err(|_| "Can't repeat a string a negative number of times")?))
+        }
+
+        (V::Bool(a), BinaryOp::And, V::Bool(b)) => V::Bool(a && b),
+        (V::Bool(a), BinaryOp::Or, V::Bool(b)) => V::Bool(a || b),
+
+        (a, BinaryOp::Eq, b) if same_kind(&a, &b) => V::Bool(a == b),
+        (a, BinaryOp::Neq, b) if same_kind(&a, &b) => V::Bool(a != b),
+        (a, op @ (BinaryOp::Less | BinaryOp::Leq | BinaryOp::Grt | BinaryOp::Geq), b) => {
+            let ord = match (&a, &b) {
+                (V::Int(a), V::Int(b)) => a.partial_cmp(b),
+                (V::Float(a), V::Float(b)) => a.partial_cmp(b),
+                (V::Char(a), V::Char(b)) => a.partial_cmp(b),
+                _ => return Err(format!("Operator '{op}' can't be applied to {a} and {b} at compile time")),
+            };
+            // Comparisons with NaN are always false
+            V::Bool(ord.is_some_and(|ord| match op {
+                BinaryOp::Less => ord.is_lt(),
+                BinaryOp::Leq => ord.is_le(),
+                BinaryOp::Grt => ord.is_gt(),
+                _ => ord.is_ge(),
+            }))
+        }
+
+        (a, op, b) => return Err(format!("Operator '{op}' can't be applied to {a} and {b} at compile time")),
+    })
+}
+
+fn same_kind(a: &ConstValue, b: &ConstValue) -> bool {
+    std::mem::discriminant(a) == std::mem::discriminant(b)
+}
@@ -1,7 +1,9 @@
 
+use std::cell::Cell;
+
 use crate::shared::src::ArcSpan;
 
-use super::ty::Ty;
+use super::{ty::Ty, consteval::ConstValue};
 
 #[derive(Debug)]
 pub struct Entity {
@@ -10,11 +12,19 @@ pub struct Entity {
     decl_span: ArcSpan,
     /// Whether this entity only exists after declaration, i.e. variables
     ephemeral: bool,
+    /// Whether this entity has been referred to after being declared
+    used: Cell<bool>,
+    /// The value of the entity if it is a constant
+    value: Option<ConstValue>,
 }
 
 impl Entity {
     pub fn new(ty: Ty, decl_span: ArcSpan, ephemeral: bool) -> Self {
-        Self { ty, decl_span, ephemeral }
+        Self { ty, decl_span, ephemeral, used: Cell::new(false), value: None }
+    }
+    pub fn with_value(mut self, value: Option<ConstValue>) -> Self {
+        self.value = value;
+        self
     }
     pub fn span(&self) -> ArcSpan {
         self.decl_span.clone()
@@ -22,7 +32,16 @@ impl Entity {
     pub fn ty(&self) -> Ty {
         self.ty.clone()
     }
+    pub fn value(&self) -> Option<ConstValue> {
+        self.value.clone()
+    }
     pub fn ephemeral(&self) -> bool {
         self.ephemeral
     }
+    pub fn mark_used(&self) {
+        self.used.set(true);
+    }
+    pub fn used(&self) -> bool {
+        self.used.get()
+    }
 }
@@ -0,0 +1,82 @@
+
+use super::{ty::{Ty, ParamTy}, path::{FullIdentPath, Ident}};
+
+/// A function that is implemented by the compiler itself rather than in 
+/// source code. Intrinsics are available in the prelude, and can also be 
+/// declared explicitly with `compiler_intrinsic fun`, in which case the 
+/// declared signature must match the one here
+#[derive(Debug, Clone)]
+pub struct Intrinsic {
+    pub name: FullIdentPath,
+    pub ty: Ty,
+}
+
+/// All of the intrinsics known to the compiler. Functions that operate on a 
+/// specific type live in that type's namespace, i.e. `string::len`, and take 
+/// the value as `this` so they can be called as methods, i.e. `"abc".len()`
+pub fn intrinsics() -> Vec<Intrinsic> {
+    macro_rules! ty {
+        ([$ty: ident]) => { Ty::Array(Box::from(Ty::$ty)) };
+        ($ty: ident) => { Ty::$ty };
+    }
+    macro_rules! decl_fun {
+        ($($name: ident)::+ ($($param: ident: $ty: tt),*) -> $r: tt) => {
+            Intrinsic {
+                name: FullIdentPath::new([$(Ident::from(stringify!($name))),+]),
+                ty: Ty::Function {
+                    params: vec![$(ParamTy::new(Some(stringify!($param)), ty!($ty))),*],
+                    ret_ty: Box::from(ty!($r)),
+                },
+            }
+        };
+    }
+
+    // Arrays have no name to declare their methods under, so they go under 
+    // the name the type is displayed with, like `[int]::len`
+    let array_len = |item: Ty| Intrinsic {
+        name: FullIdentPath::new([Ident::from(format!("[{item}]").as_str()), Ident::from("len")]),
+        ty: Ty::Function {
+            params: vec![ParamTy::new(Some("this"), Ty::Array(Box::from(item)))],
+            ret_ty: Box::from(Ty::Int),
+        },
+    };
+
+    vec![
+        decl_fun!(print(value: String) -> Void),
+
+        decl_fun!(string::len(this: String) -> Int),
+        decl_fun!(string::slice(this: String, start: Int, end: Int) -> String),
+        decl_fun!(string::contains(this: String, needle: String) -> Bool),
+        decl_fun!(string::split(this: String, separator: String) -> [String]),
+        decl_fun!(int::abs(this: Int) -> Int),
+        decl_fun!(int::to_string(this: Int) -> String),
+        decl_fun!(int::to_char(this: Int) -> Char),
+        decl_fun!(char::to_int(this: Char) -> Int),
+        decl_fun!(char::to_string(this: Char) -> String),
+        decl_fun!(float::abs(this: Float) -> Float),
+        decl_fun!(float::to_string(this: Float) -> String),
+        decl_fun!(bool::to_string(this: Bool) -> String),
+        array_len(Ty::Int),
+    ]
+}
+
+/// Find an intrinsic by its full name
+pub fn find_intrinsic(name: &FullIdentPath) -> Option<Intrinsic> {
+    intrinsics().into_iter().find(|i| i.name == *name)
+}
+
+/// Whether a declared function type matches the type of an intrinsic. 
+/// Parameter names don't matter, only types do
+pub fn signature_matches(declared: &Ty, intrinsic: &Ty) -> bool {
+    match (declared, intrinsic) {
+        (
+            Ty::Function { params: a_params, ret_ty: a_ret },
+            Ty::Function { params: b_params, ret_ty: b_ret }
+        ) => {
+            a_params.len() == b_params.len() &&
+                a_params.iter().zip(b_params).all(|(a, b)| a.ty.reduce() == b.ty.reduce()) &&
+                a_ret.reduce() == b_ret.reduce()
+        }
+        _ => false,
+    }
+}
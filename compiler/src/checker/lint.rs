@@ -0,0 +1,85 @@
+
+use serde::Deserialize;
+
+/// How a lint should be reported
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LintLevel {
+    Allow,
+    Warn,
+    Deny,
+}
+
+/// A check for code that is valid but likely a mistake. Lints are reported 
+/// as warnings by default, and can be silenced with `@allow(name)` or have 
+/// their level changed in the project manifest
+#[derive(Debug)]
+pub struct Lint {
+    pub name: &'static str,
+    pub default_level: LintLevel,
+    pub description: &'static str,
+}
+
+pub const UNUSED_VARIABLE: Lint = Lint {
+    name: "unused_variable",
+    default_level: LintLevel::Warn,
+    description: "a variable is declared but never used",
+};
+
+pub const UNUSED_LABEL: Lint = Lint {
+    name: "unused_label",
+    default_level: LintLevel::Warn,
+    description: "a loop label is declared but never used",
+};
+
+pub const SHADOWING: Lint = Lint {
+    name: "shadowing",
+    default_level: LintLevel::Allow,
+    description: "a variable is declared with the same name as an earlier variable",
+};
+
+pub const DEPRECATED: Lint = Lint {
+    name: "deprecated",
+    default_level: LintLevel::Warn,
+    description: "an item marked @deprecated is used",
+};
+
+pub const UNREACHABLE_CODE: Lint = Lint {
+    name: "unreachable_code",
+    default_level: LintLevel::Warn,
+    description: "code comes after an expression that never finishes, like `return`",
+};
+
+pub const UNNECESSARY_SAFE_CALL: Lint = Lint {
+    name: "unnecessary_safe_call",
+    default_level: LintLevel::Warn,
+    description: "`?.` is used on a value that can't be `none`",
+};
+
+pub const ASSIGNMENT_IN_CONDITION: Lint = Lint {
+    name: "assignment_in_condition",
+    default_level: LintLevel::Warn,
+    description: "the condition of an `if` or `while` is an assignment, like `x = 5`",
+};
+
+pub const MIXED_LINE_ENDINGS: Lint = Lint {
+    name: "mixed_line_endings",
+    default_level: LintLevel::Warn,
+    description: "a file has lines ending with both `\\n` and `\\r\\n`",
+};
+
+pub const FUTURE_KEYWORD: Lint = Lint {
+    name: "future_keyword",
+    default_level: LintLevel::Warn,
+    description: "an identifier is a keyword in a later edition",
+};
+
+/// Every lint the compiler knows about
+pub const LINTS: &[Lint] = &[
+    UNUSED_VARIABLE, UNUSED_LABEL, SHADOWING, DEPRECATED, UNREACHABLE_CODE,
+    UNNECESSARY_SAFE_CALL, ASSIGNMENT_IN_CONDITION, MIXED_LINE_ENDINGS, FUTURE_KEYWORD,
+];
+
+pub fn find_lint(name: &str) -> Option<&'static Lint> {
+    LINTS.iter().find(|l| l.name == name)
+}
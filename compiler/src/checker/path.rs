@@ -71,6 +71,16 @@ impl IdentPath {
     pub fn is_absolute(&self) -> bool {
         self.absolute
     }
+    /// The last component of this path, i.e. the item's own name
+    pub fn last(&self) -> Option<&Ident> {
+        self.components.last()
+    }
+    /// This path with `ident` added to the end
+    pub fn join_ident(&self, ident: Ident) -> IdentPath {
+        let mut components = self.components.clone();
+        components.push(ident);
+        Self { components, absolute: self.absolute }
+    }
 }
 
 impl Display for IdentPath {
@@ -116,6 +126,17 @@ impl FullIdentPath {
     pub fn is_empty(&self) -> bool {
         self.components.is_empty()
     }
+    /// The last component of this path, i.e. the item's own name
+    pub fn last(&self) -> Option<&Ident> {
+        self.components.last()
+    }
+    /// This path without its last component, i.e. the namespace the item 
+    /// is in
+    pub fn parent(&self) -> FullIdentPath {
+        let mut parent = self.clone();
+        parent.pop();
+        parent
+    }
 }
 
 impl Display for FullIdentPath {
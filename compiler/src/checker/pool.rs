@@ -1,9 +1,5 @@
 
 use crate::ast::expr::ExprList;
-use crate::parser::tokenizer::Tokenizer;
-use crate::shared::src::SrcPool;
-use crate::shared::logger::LoggerRef;
-use crate::parser::parse::{ParseRef, NodePool};
 
 pub type AST = ExprList;
 
@@ -11,21 +7,16 @@ pub struct ASTPool {
     asts: Vec<AST>,
 }
 
-impl<'s: 'g, 'g> ASTPool {
-    pub fn parse_src_pool(list: &mut NodePool, pool: &SrcPool, logger: LoggerRef) -> Self {
-        Self {
-            asts: pool.iter()
-                .filter_map(|src| ExprList::parse_complete(
-                    list,
-                    src.clone(),
-                    Tokenizer::new(&src, logger.clone())
-                ).ok())
-                .collect(),
-        }
+impl ASTPool {
+    pub(crate) fn new(asts: Vec<AST>) -> Self {
+        Self { asts }
     }
     pub fn iter(&self) -> <&Vec<AST> as IntoIterator>::IntoIter {
         self.into_iter()
     }
+    pub fn as_mut_slice(&mut self) -> &mut [AST] {
+        &mut self.asts
+    }
 }
 
 impl<'a> IntoIterator for &'a ASTPool {
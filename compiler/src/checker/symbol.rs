@@ -0,0 +1,90 @@
+use serde::{Deserialize, Serialize};
+use crate::shared::src::ArcSpan;
+use super::{entity::Entity, path::FullIdentPath, ty::Ty};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SymbolKind {
+    Function,
+    Constant,
+}
+
+/// What tooling needs to know about a declared item to show it on hover, 
+/// in completion details or in generated documentation
+#[derive(Debug, Clone, PartialEq)]
+pub struct SymbolInfo {
+    pub path: FullIdentPath,
+    pub kind: SymbolKind,
+    /// Where the item is declared. For overloaded functions this is the 
+    /// first overload
+    pub span: ArcSpan,
+    /// The declaration as it would be written in source, without its body. 
+    /// Overloaded functions have one line per overload
+    pub signature: String,
+    /// The `///` comment lines right above the declaration, with the 
+    /// slashes and one space after them removed
+    pub doc: Option<String>,
+}
+
+impl SymbolInfo {
+    pub(crate) fn new(path: &FullIdentPath, entity: &Entity) -> Self {
+        let name = path.last().map(|n| n.to_string()).unwrap_or_default();
+        let kind = match entity.ty() {
+            Ty::Function { .. } | Ty::Overloaded { .. } => SymbolKind::Function,
+            _ => SymbolKind::Constant,
+        };
+        Self {
+            path: path.clone(),
+            kind,
+            span: entity.span(),
+            signature: signature(&name, entity),
+            doc: doc_comment(&entity.span()),
+        }
+    }
+}
+
+fn function_signature(name: &str, ty: &Ty) -> String {
+    let Ty::Function { params, ret_ty } = ty else {
+        return format!("{name}: {ty}");
+    };
+    let params = params.iter()
+        .map(|p| format!(
+            "{}: {}{}",
+            p.name.as_deref().unwrap_or("_"), p.ty,
+            if p.has_default { " = ..." } else { "" }
+        ))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!("fun {name}({params}) -> {ret_ty}")
+}
+
+fn signature(name: &str, entity: &Entity) -> String {
+    match (entity.ty(), entity.value()) {
+        (ty @ Ty::Function { .. }, _) => function_signature(name, &ty),
+        (Ty::Overloaded { overloads, .. }, _) => overloads.iter()
+            .map(|(ty, _)| function_signature(name, ty))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        (ty, Some(value)) => format!("const {name}: {ty} = {value}"),
+        // Constants whose value couldn't be computed
+        (ty, None) => format!("const {name}: {ty}"),
+    }
+}
+
+/// Get the doc comment of the item declared at `span`. Attributes between 
+/// the comment and the declaration are skipped
+pub(crate) fn doc_comment(span: &ArcSpan) -> Option<String> {
+    let data = span.0.data();
+    let line_start = data.get(..span.1.start)?.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let mut lines = data[..line_start].lines().rev()
+        .map(str::trim)
+        .skip_while(|l| l.starts_with('@'))
+        .map_while(|l| l.strip_prefix("///"))
+        .map(|l| l.strip_prefix(' ').unwrap_or(l))
+        .collect::<Vec<_>>();
+    if lines.is_empty() {
+        return None;
+    }
+    lines.reverse();
+    Some(lines.join("\n"))
+}
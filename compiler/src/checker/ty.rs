@@ -1,8 +1,42 @@
 
-use std::fmt::Display;
+use std::{fmt::Display, hash::{Hash, Hasher}};
 use crate::ice;
 use crate::shared::src::ArcSpan;
 
+/// A parameter of a function type. Parameters are compared by their type 
+/// only, so function types from different declarations are the same type 
+/// as long as their parameter and return types are
+#[derive(Debug, Clone)]
+pub struct ParamTy {
+    pub name: Option<String>,
+    pub ty: Ty,
+    /// Whether the parameter has a default value, so it can be left out in 
+    /// calls
+    pub has_default: bool,
+    pub decl_span: ArcSpan,
+}
+
+impl ParamTy {
+    /// A parameter without a default value that wasn't declared in source
+    pub fn new<S: Into<String>>(name: Option<S>, ty: Ty) -> Self {
+        Self { name: name.map(Into::into), ty, has_default: false, decl_span: ArcSpan::builtin() }
+    }
+}
+
+impl PartialEq for ParamTy {
+    fn eq(&self, other: &Self) -> bool {
+        self.ty == other.ty
+    }
+}
+
+impl Eq for ParamTy {}
+
+impl Hash for ParamTy {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.ty.hash(state);
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Ty {
     /// The type of a variable whose real type has not yet been inferred
@@ -22,15 +56,27 @@ pub enum Ty {
     Float,
     /// UTF-8 string type
     String,
+    /// Unicode scalar value, like `'a'`
+    Char,
+    /// Range of integers, like `0..10`
+    Range,
     /// Function type
     Function {
-        params: Vec<(Option<String>, Ty)>,
+        params: Vec<ParamTy>,
         ret_ty: Box<Ty>,
     },
+    /// Array of values of one type
+    Array(Box<Ty>),
+    /// Fixed-size list of values of different types, like `(int, string)`
+    Tuple(Vec<Ty>),
     /// Optional type
     Option {
         ty: Box<Ty>,
     },
+    /// A value of any one of the listed types. When used with `try`, the 
+    /// first type is the successful value and the rest are errors. Always 
+    /// has at least two types; construct using `Ty::union`
+    Union(Vec<Ty>),
     /// Alias for another type. Can be implicitly converted to the other type
     Alias {
         name: String,
@@ -44,6 +90,19 @@ pub enum Ty {
         ty: Box<Ty>,
         decl_span: ArcSpan,
     },
+    /// A type with named fields, declared with `struct`
+    Struct {
+        name: String,
+        fields: Vec<(String, Ty)>,
+        decl_span: ArcSpan,
+    },
+    /// Functions with the same name but different parameters, along with 
+    /// where each was declared. Calls pick the function whose parameters fit 
+    /// the arguments
+    Overloaded {
+        name: String,
+        overloads: Vec<(Ty, ArcSpan)>,
+    },
 }
 
 impl Ty {
@@ -55,14 +114,43 @@ impl Ty {
             "int" => Self::Int,
             "float" => Self::Float,
             "string" => Self::String,
+            "char" => Self::Char,
+            "range" => Self::Range,
             _ => ice!("invalid builtin type '{name}'")
         }
     }
 
+    /// Create a union of types. Nested unions are flattened and duplicate 
+    /// types removed; if only one type remains, that type is returned as-is
+    pub fn union<I: IntoIterator<Item = Ty>>(tys: I) -> Self {
+        let mut res: Vec<Ty> = Vec::new();
+        for ty in tys {
+            let members = match ty {
+                Ty::Union(members) => members,
+                other => vec![other],
+            };
+            for ty in members {
+                if !res.contains(&ty) {
+                    res.push(ty);
+                }
+            }
+        }
+        match res.len() {
+            1 => res.remove(0),
+            _ => Self::Union(res),
+        }
+    }
+
     pub fn is_never(&self) -> bool {
         matches!(self, Ty::Never)
     }
 
+    /// Whether any of `tys` is `never`. An expression with an operand that 
+    /// never finishes doesn't finish either, so its type is `never` too
+    pub fn any_never<'a, I: IntoIterator<Item = &'a Ty>>(tys: I) -> bool {
+        tys.into_iter().any(Ty::is_never)
+    }
+
     pub fn is_undecided(&self) -> bool {
         matches!(self, Ty::Undecided(_, _))
     }
@@ -75,7 +163,7 @@ impl Ty {
     /// Reduce type into its canonical representation, for example remove aliases
     pub fn reduce(&self) -> &Ty {
         match self {
-            Self::Alias { name: _, ty, decl_span: _ } => ty,
+            Self::Alias { name: _, ty, decl_span: _ } => ty.reduce(),
             other => other,
         }
     }
@@ -84,8 +172,52 @@ impl Ty {
     /// not
     /// 
     /// In most cases this means equality
+    /// 
+    /// A type is convertible to a union if it is convertible to one of its 
+    /// members, and a union is convertible to a type if all of its members 
+    /// are. Arrays are convertible if their items are, and tuples if they 
+    /// have the same length and each of their items are. Functions are 
+    /// convertible if they take the same number of parameters, the 
+    /// parameters of `other` are convertible to the ones of this function, 
+    /// and the return type of this function is convertible to the one of 
+    /// `other`. An overload set is convertible to a function type if one of 
+    /// its functions is
     pub fn convertible(&self, other: &Ty) -> bool {
-        self.is_unreal() || other.is_unreal() || *self.reduce() == *other.reduce()
+        if self.is_unreal() || other.is_unreal() {
+            return true;
+        }
+        match (self.reduce(), other.reduce()) {
+            (Ty::Union(members), other) => members.iter().all(|m| m.convertible(other)),
+            (this, Ty::Union(members)) => members.iter().any(|m| this.convertible(m)),
+            (Ty::Array(a), Ty::Array(b)) => a.convertible(b),
+            (Ty::Tuple(a), Ty::Tuple(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.convertible(b))
+            }
+            (
+                Ty::Function { params: a_params, ret_ty: a_ret },
+                Ty::Function { params: b_params, ret_ty: b_ret },
+            ) => {
+                a_params.len() == b_params.len() &&
+                    a_params.iter().zip(b_params).all(|(a, b)| b.ty.convertible(&a.ty)) &&
+                    a_ret.convertible(b_ret)
+            }
+            (Ty::Overloaded { name: _, overloads }, fun @ Ty::Function { .. }) => {
+                overloads.iter().any(|(ty, _)| ty.convertible(fun))
+            }
+            (this, other) => *this == *other,
+        }
+    }
+
+    /// The type of the items yielded when iterating over a value of this 
+    /// builtin type with a `for` loop, if it can be iterated over. See 
+    /// `Checker::iter_item` for iterating user types
+    pub fn iter_item(&self) -> Option<Ty> {
+        match self.reduce() {
+            Ty::String => Some(Ty::Char),
+            Ty::Range => Some(Ty::Int),
+            Ty::Array(item) => Some(item.as_ref().clone()),
+            _ => None,
+        }
     }
 
     pub fn span(&self) -> ArcSpan {
@@ -98,10 +230,30 @@ impl Ty {
             Ty::Int => ArcSpan::builtin(),
             Ty::Float => ArcSpan::builtin(),
             Ty::String => ArcSpan::builtin(),
+            Ty::Char => ArcSpan::builtin(),
+            Ty::Range => ArcSpan::builtin(),
             Ty::Function { params: _, ret_ty: _ } => ArcSpan::builtin(),
+            Ty::Array(_) => ArcSpan::builtin(),
+            Ty::Tuple(_) => ArcSpan::builtin(),
             Ty::Option { ty: _ } => ArcSpan::builtin(),
+            Ty::Union(_) => ArcSpan::builtin(),
+            Ty::Overloaded { name: _, overloads: _ } => ArcSpan::builtin(),
             Ty::Alias { name: _, ty: _, decl_span } |
-            Ty::Named { name: _, ty: _, decl_span } => decl_span.clone(),
+            Ty::Named { name: _, ty: _, decl_span } |
+            Ty::Struct { name: _, fields: _, decl_span } => decl_span.clone(),
+        }
+    }
+
+    /// Whether values of this type can be passed to and from foreign 
+    /// functions
+    pub fn is_ffi_safe(&self) -> bool {
+        match self {
+            Self::Bool | Self::Int | Self::Float | Self::String | Self::Char => true,
+            // Don't report errors about types that already failed to resolve
+            Self::Invalid => true,
+            Self::Alias { name: _, ty, decl_span: _ } |
+            Self::Named { name: _, ty, decl_span: _ } => ty.is_ffi_safe(),
+            _ => false,
         }
     }
 
@@ -111,32 +263,113 @@ impl Ty {
     }
 }
 
-impl Display for Ty {
+/// Options for how types are shown in diagnostics. By default types with 
+/// names are shown by the last component of their name only, like `Point` 
+/// for `shapes::Point`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TyDisplay {
+    /// Show the full path types were declared with
+    pub qualified: bool,
+    /// Show where types with names were declared, for telling apart types 
+    /// that have the same full path
+    pub decl_locations: bool,
+}
+
+impl TyDisplay {
+    /// Show full paths, but no declaration locations
+    pub fn qualified() -> Self {
+        Self { qualified: true, decl_locations: false }
+    }
+
+    /// Pick the shortest options that make `a` and `b` look different, if 
+    /// they are different types. Otherwise errors like "expected Point, got 
+    /// Point" would be confusing
+    pub fn distinguishing(a: &Ty, b: &Ty) -> Self {
+        let mut opts = Self::default();
+        if a == b {
+            return opts;
+        }
+        if a.display(opts).to_string() == b.display(opts).to_string() {
+            opts.qualified = true;
+        }
+        if a.display(opts).to_string() == b.display(opts).to_string() {
+            opts.decl_locations = true;
+        }
+        opts
+    }
+
+    fn name(&self, name: &str, decl_span: Option<&ArcSpan>) -> String {
+        let name = if self.qualified { name } else { name.rsplit("::").next().unwrap_or(name) };
+        match decl_span {
+            Some(span) if self.decl_locations && !span.is_builtin() => {
+                let (line, col) = span.as_ref().line_col();
+                format!("{name} (declared at {}:{line}:{col})", span.0.name())
+            }
+            _ => name.to_string(),
+        }
+    }
+}
+
+/// A type shown with some `TyDisplay` options, see `Ty::display`
+pub struct TyDisplayed<'a> {
+    ty: &'a Ty,
+    opts: TyDisplay,
+}
+
+impl Ty {
+    /// Show this type with the options `opts` instead of the default ones
+    pub fn display(&self, opts: TyDisplay) -> TyDisplayed<'_> {
+        TyDisplayed { ty: self, opts }
+    }
+}
+
+impl Display for TyDisplayed<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Self::Undecided(name, _) => write!(f, "unknown ({name})"),
-            Self::Invalid => f.write_str("unknown"),
-            Self::Never => f.write_str("never"),
-            Self::Void => f.write_str("void"),
-            Self::Bool => f.write_str("bool"),
-            Self::Int => f.write_str("int"),
-            Self::Float => f.write_str("float"),
-            Self::String => f.write_str("string"),
-            Self::Function { params, ret_ty } => write!(
+        let opts = self.opts;
+        let show = |ty: &Ty| ty.display(opts).to_string();
+        match self.ty {
+            Ty::Undecided(name, _) => write!(f, "unknown ({name})"),
+            Ty::Invalid => f.write_str("unknown"),
+            Ty::Never => f.write_str("never"),
+            Ty::Void => f.write_str("void"),
+            Ty::Bool => f.write_str("bool"),
+            Ty::Int => f.write_str("int"),
+            Ty::Float => f.write_str("float"),
+            Ty::String => f.write_str("string"),
+            Ty::Char => f.write_str("char"),
+            Ty::Range => f.write_str("range"),
+            Ty::Function { params, ret_ty } => write!(
                 f,
-                "fun({}) -> {ret_ty}", params.iter()
-                    .map(|(p, t)| if let Some(p) = p {
-                        format!("{p}: {t}")
+                "fun({}) -> {}", params.iter()
+                    .map(|p| if let Some(ref name) = p.name {
+                        format!("{name}: {}", show(&p.ty))
                     }
                     else {
-                        t.to_string()
+                        show(&p.ty)
                     })
                     .collect::<Vec<_>>()
-                    .join(", ")
+                    .join(", "),
+                show(ret_ty)
             ),
-            Self::Option { ty } => write!(f, "{ty}?"),
-            Self::Alias { name, ty: _, decl_span: _ } => write!(f, "{name}"),
-            Self::Named { name, ty: _, decl_span: _ } => write!(f, "{name}"),
+            Ty::Array(item) => write!(f, "[{}]", show(item)),
+            Ty::Tuple(items) if items.len() == 1 => write!(f, "({},)", show(&items[0])),
+            Ty::Tuple(items) => write!(
+                f, "({})", items.iter().map(show).collect::<Vec<_>>().join(", ")
+            ),
+            Ty::Option { ty } => write!(f, "{}?", show(ty)),
+            Ty::Union(tys) => f.write_str(
+                &tys.iter().map(show).collect::<Vec<_>>().join(" | ")
+            ),
+            Ty::Alias { name, ty: _, decl_span } |
+            Ty::Named { name, ty: _, decl_span } |
+            Ty::Struct { name, fields: _, decl_span } => f.write_str(&opts.name(name, Some(decl_span))),
+            Ty::Overloaded { name, overloads: _ } => write!(f, "overloaded function {}", opts.name(name, None)),
         }
     }
 }
+
+impl Display for Ty {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.display(TyDisplay::default()).fmt(f)
+    }
+}
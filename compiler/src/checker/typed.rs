@@ -0,0 +1,53 @@
+
+use std::collections::BTreeMap;
+
+use crate::{
+    parser::parse::{NodeID, NodePool, Ref},
+    shared::src::{ArcSpan, Src},
+    ast::expr::ExprNode,
+};
+use super::{ty::Ty, pool::AST};
+
+/// The types inferred for every expression in a checked AST, so that later 
+/// passes and tooling don't have to infer them again
+#[derive(Debug, Clone)]
+pub struct TypedAst {
+    ty: Ty,
+    exprs: BTreeMap<NodeID, (ArcSpan, Ty)>,
+}
+
+impl TypedAst {
+    /// Collect the types of the expressions in `ast`. Expressions that 
+    /// couldn't be resolved are left out
+    pub(crate) fn new(ast: &AST, ty: Ty, pool: &NodePool) -> Self {
+        let mut exprs = BTreeMap::new();
+        pool.visit(ast, &mut |id, node, span, ty| {
+            if node.as_any().is::<ExprNode>() {
+                if let (Some(span), Some(ty)) = (span, ty) {
+                    exprs.insert(id, (span, ty));
+                }
+            }
+        });
+        Self { ty, exprs }
+    }
+    /// The type of the whole AST
+    pub fn ty(&self) -> &Ty {
+        &self.ty
+    }
+    /// The type of an expression in this AST
+    pub fn ty_of<R: Ref>(&self, expr: &R) -> Option<&Ty> {
+        expr.ids().first().and_then(|id| self.exprs.get(id)).map(|(_, ty)| ty)
+    }
+    /// Every typed expression along with its span, in the order their nodes 
+    /// were allocated
+    pub fn exprs(&self) -> impl Iterator<Item = (&ArcSpan, &Ty)> {
+        self.exprs.values().map(|(span, ty)| (span, ty))
+    }
+    /// The type of the innermost expression containing the byte `offset` 
+    /// in `src`
+    pub fn ty_at(&self, src: &Src, offset: usize) -> Option<(&ArcSpan, &Ty)> {
+        self.exprs()
+            .filter(|(span, _)| *span.0 == *src && span.1.contains(&offset))
+            .min_by_key(|(span, _)| span.1.len())
+    }
+}
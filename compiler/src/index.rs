@@ -0,0 +1,92 @@
+use std::{collections::BTreeMap, fs, ops::Range, path::Path};
+use serde::{Deserialize, Serialize};
+use crate::checker::symbol::{SymbolInfo, SymbolKind};
+
+/// Version of the index file format. Indexes written with a different 
+/// version are discarded when loaded
+const INDEX_VERSION: u32 = 1;
+
+/// An item in a `SymbolIndex`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedSymbol {
+    pub path: String,
+    pub kind: SymbolKind,
+    /// Byte range of the declaration in its file
+    pub range: Range<usize>,
+    /// 1-based line and column of the start of the declaration
+    pub line: usize,
+    pub column: usize,
+    pub signature: String,
+}
+
+impl IndexedSymbol {
+    pub fn new(info: &SymbolInfo) -> Self {
+        let (line, column) = info.span.as_ref().line_col();
+        Self {
+            path: info.path.to_string(),
+            kind: info.kind,
+            range: info.span.1.clone(),
+            line, column,
+            signature: info.signature.clone(),
+        }
+    }
+}
+
+/// The items declared at the top level of every file of a project, kept on 
+/// disk so tools can answer queries like go-to-definition without checking 
+/// the project again. See `Session::update_symbol_index`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SymbolIndex {
+    version: u32,
+    /// Symbols by the name of the file that declares them, ordered by 
+    /// where they are declared
+    files: BTreeMap<String, Vec<IndexedSymbol>>,
+}
+
+impl Default for SymbolIndex {
+    fn default() -> Self {
+        Self { version: INDEX_VERSION, files: BTreeMap::new() }
+    }
+}
+
+impl SymbolIndex {
+    /// Load an index written by `save`. A missing file, or one written by a 
+    /// different version of the compiler, gives an empty index
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let data = match fs::read_to_string(path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(format!("Unable to read symbol index: {e}")),
+        };
+        let index = serde_json::from_str::<Self>(&data)
+            .map_err(|e| format!("Invalid symbol index: {e}"))?;
+        Ok(if index.version == INDEX_VERSION { index } else { Self::default() })
+    }
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let data = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        fs::write(path, data).map_err(|e| format!("Unable to write symbol index: {e}"))
+    }
+    /// Replace the symbols of `file`
+    pub fn update(&mut self, file: &str, mut symbols: Vec<IndexedSymbol>) {
+        symbols.sort_by_key(|s| s.range.start);
+        self.files.insert(file.to_string(), symbols);
+    }
+    /// Remove the symbols of files for which `keep` returns false, such as 
+    /// files that have been deleted
+    pub fn retain_files<F: FnMut(&str) -> bool>(&mut self, mut keep: F) {
+        self.files.retain(|file, _| keep(file));
+    }
+    /// Find the declaration of the item with the full path `path`, like 
+    /// `::string::shout`, along with the file it is in
+    pub fn find(&self, path: &str) -> Option<(&str, &IndexedSymbol)> {
+        self.iter().find(|(_, s)| s.path == path)
+    }
+    /// Every symbol in the index along with the file it is in
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &IndexedSymbol)> {
+        self.files.iter().flat_map(|(file, symbols)| symbols.iter().map(move |s| (file.as_str(), s)))
+    }
+    /// The symbols declared in `file`
+    pub fn file(&self, file: &str) -> &[IndexedSymbol] {
+        self.files.get(file).map(Vec::as_slice).unwrap_or_default()
+    }
+}
@@ -1,23 +1,149 @@
-
 #![warn(clippy::todo)]
 
+use std::borrow::Borrow;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
 use checker::coherency::Checker;
 use checker::pool::AST;
 use checker::ty::Ty;
-use parser::parse::NodePool;
-use parser::tokenizer::{Tokenizer, Token};
+use parser::config::LanguageConfig;
+use parser::parse::{NodePool, ParseRef, FatalParseError};
+use parser::profile::ProfilerRef;
+use parser::tokenizer::{Tokenizer, Token, TokenIterator, Trivia};
+use shared::cancel::CancellationToken;
+use shared::ice::catch_ice;
 use shared::logger::LoggerRef;
-use shared::src::Src;
+use shared::src::{ArcSpan, Src, Span};
+use shared::timing::Phase;
 
 pub mod parser;
 pub mod shared;
 pub mod ast;
 pub mod checker;
+pub mod artifacts;
+pub mod index;
+pub mod project;
+pub mod session;
+
+pub fn tokenize<'s, 'g: 's>(src: &'s Src, config: Arc<LanguageConfig>, logger: LoggerRef) -> Vec<Token<'s>> {
+    Tokenizer::new(src, config, logger).collect()
+}
+
+/// Tokenize a source while keeping the whitespace and comments between the 
+/// tokens, so the original text can be reproduced byte-for-byte
+pub fn tokenize_with_trivia<'s>(
+    src: &'s Src, config: Arc<LanguageConfig>, logger: LoggerRef
+) -> (Vec<Token<'s>>, Vec<Trivia<'s>>) {
+    let mut tokenizer = Tokenizer::new(src, config, logger);
+    let tokens = tokenizer.by_ref().collect();
+    (tokens, tokenizer.trivia().to_vec())
+}
+
+/// Tokenize multiple sources in parallel. Sources are handed out to a pool of
+/// worker threads (one per available core) as they become free, and the
+/// resulting token lists are returned in the same order as `srcs`
+///
+/// Once `cancel` is cancelled, no more sources are started, and the token 
+/// lists of the remaining ones are left empty
+/// 
+/// Diagnostics from different sources may be logged in any order. Use 
+/// `tokenize_in_order` if the output has to be reproducible
+pub fn tokenize_pool<'s, S>(
+    srcs: &'s [S], config: Arc<LanguageConfig>, cancel: &CancellationToken, logger: LoggerRef
+) -> Vec<Vec<Token<'s>>>
+    where S: Borrow<Src> + Sync
+{
+    // There are no threads on the web
+    if cfg!(target_arch = "wasm32") {
+        return tokenize_in_order(srcs, config, cancel, logger);
+    }
+    let workers = thread::available_parallelism().map(|n| n.get()).unwrap_or(1).min(srcs.len());
+    let next = AtomicUsize::new(0);
+    let results: Vec<Mutex<Vec<Token<'s>>>> = srcs.iter().map(|_| Mutex::default()).collect();
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| {
+                while !cancel.is_cancelled() {
+                    let ix = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(src) = srcs.get(ix) else { break };
+                    *results[ix].lock().unwrap() = tokenize(src.borrow(), config.clone(), logger.clone());
+                }
+            });
+        }
+    });
+    results.into_iter().map(|r| r.into_inner().unwrap()).collect()
+}
+
+/// Tokenize multiple sources one after another on the current thread, so 
+/// diagnostics are always logged in the same order. Cancellation works like 
+/// in `tokenize_pool`
+pub fn tokenize_in_order<'s, S>(
+    srcs: &'s [S], config: Arc<LanguageConfig>, cancel: &CancellationToken, logger: LoggerRef
+) -> Vec<Vec<Token<'s>>>
+    where S: Borrow<Src>
+{
+    srcs.iter()
+        .map(|src| if cancel.is_cancelled() {
+            vec![]
+        }
+        else {
+            tokenize(src.borrow(), config.clone(), logger.clone())
+        })
+        .collect()
+}
+
+/// Parse a source file into an AST
+pub fn parse(
+    src: Arc<Src>, config: Arc<LanguageConfig>, pool: &mut NodePool, logger: LoggerRef
+) -> Result<AST, FatalParseError> {
+    AST::parse_complete(pool, src.clone(), Tokenizer::new(&src, config, logger))
+}
+
+/// Parse a source file into an AST, recording every rule the parser attempts 
+/// to match in `profiler`
+pub fn parse_profiled(
+    src: Arc<Src>, config: Arc<LanguageConfig>, pool: &mut NodePool, profiler: ProfilerRef, logger: LoggerRef
+) -> Result<AST, FatalParseError> {
+    let mut tokenizer = TokenIterator::from(Tokenizer::new(&src, config, logger));
+    tokenizer.set_profiler(Some(profiler));
+    AST::parse_complete(pool, src.clone(), tokenizer)
+}
+
+/// Parse a source file into an AST, guaranteeing that the host process is 
+/// never aborted by a bug in the compiler. If parsing panics, the panic is 
+/// reported through the logger as an internal compiler error and 
+/// `FatalParseError` is returned instead. See `catch_ice`
+pub fn parse_no_panic(
+    src: Arc<Src>, config: Arc<LanguageConfig>, pool: &mut NodePool, logger: LoggerRef
+) -> Result<AST, FatalParseError> {
+    catch_ice(Phase::Parse, Span(&src, 0..0), &logger, || parse(src.clone(), config, pool, logger.clone()))
+        .unwrap_or(Err(FatalParseError))
+}
+
+/// Check an AST like `check_coherency`, reporting a panic in the checker as 
+/// an internal compiler error instead of aborting. Gives `Ty::Invalid` if 
+/// the checker panicked
+pub fn check_no_panic(
+    ast: &mut AST, pool: &mut NodePool, config: Arc<LanguageConfig>, logger: LoggerRef
+) -> Ty {
+    let at = pool.span_of(ast).map(|s| ArcSpan(s.0, 0..0)).unwrap_or_default();
+    catch_ice(Phase::Check, at.as_ref(), &logger, || check_coherency(ast, pool, config, logger.clone()))
+        .unwrap_or(Ty::Invalid)
+}
 
-pub fn tokenize<'s, 'g: 's>(src: &'s Src, logger: LoggerRef) -> Vec<Token<'s>> {
-    Tokenizer::new(src, logger).collect()
+pub fn check_coherency(
+    ast: &mut AST, list: &mut NodePool, config: Arc<LanguageConfig>, logger: LoggerRef
+) -> Ty {
+    Checker::try_resolve(ast, list, config, logger)
 }
 
-pub fn check_coherency(ast: &mut AST, list: &mut NodePool, logger: LoggerRef) -> Ty {
-    Checker::try_resolve(ast, list, logger)
+/// Check multiple ASTs after a prelude, such as a standard library. See 
+/// `Checker::try_resolve_with_prelude`
+pub fn check_coherency_with_prelude(
+    prelude: &mut [AST], asts: &mut [AST],
+    list: &mut NodePool, config: Arc<LanguageConfig>, logger: LoggerRef
+) -> Vec<Ty> {
+    Checker::try_resolve_with_prelude(prelude, asts, list, config, logger)
 }
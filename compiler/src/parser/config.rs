@@ -0,0 +1,303 @@
+
+use std::{collections::{HashMap, HashSet}, fmt::Display, str::FromStr};
+use serde::Deserialize;
+use crate::checker::lint::LintLevel;
+use super::tokenizer::MAX_NESTING_DEPTH;
+
+const STRICT_KEYWORDS: &[&str] = &[
+    // Literals
+    "void", "true", "false", "none",
+    // Constants & special variables
+    "this", "super",
+    // Declarations
+    "var", "let", "fun", "struct", "enum", "using",
+    "macro", "extends", "module", "type",
+    // Prepositions
+    "in", "is", "as", "where", "from",
+    // Loops & conditionals
+    "if", "else", "for", "while", "switch",
+    // Control flow
+    "try", "return", "break", "continue",
+    // Visibility
+    "extern", "public", "private",
+    // Types
+    "typeof", "const", "new",
+    // Other
+    "codegen", "compiler_intrinsic"
+];
+/// Keywords that start a statement, which are suggested when a statement 
+/// starts with a misspelled one
+const STATEMENT_KEYWORDS: &[&str] = &[
+    "var", "let", "fun", "struct", "enum", "using", "macro", "extends", "module", "type",
+    "if", "for", "while", "switch", "try", "return", "break", "continue",
+    "extern", "public", "private", "const", "codegen", "defer",
+];
+const CONTEXTUAL_KEYWORDS: &[&str] = &[
+    // Accessors
+    "get", "set",
+    // Assertions
+    "assert", "static_assert",
+];
+const RESERVED_KEYWORDS: &[&str] = &[
+    // Declarations
+    "trait", "class", "interface",
+    // Control flow
+    "unwrap", "yield", "match",
+    // Visibility
+    "export", "import",
+    // Reactivity
+    "depends", "required",
+    // Macros
+    "reflect",
+    // Other
+    "mut", "mutable", "null"
+];
+
+/// A version of the language. Breaking changes to the syntax or semantics 
+/// are only made in new editions, so code written for an older edition 
+/// keeps compiling as long as it specifies that edition
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+#[serde(try_from = "String")]
+pub enum Edition {
+    Edition2024,
+    Edition2025,
+}
+
+impl Edition {
+    pub const ALL: &'static [Edition] = &[Edition::Edition2024, Edition::Edition2025];
+    pub const LATEST: Edition = Edition::Edition2025;
+
+    /// Words that are keywords starting from this edition
+    fn new_strict_keywords(self) -> &'static [&'static str] {
+        match self {
+            Edition::Edition2024 => &[],
+            Edition::Edition2025 => &["defer"],
+        }
+    }
+    /// Words that are reserved starting from this edition
+    fn new_reserved_keywords(self) -> &'static [&'static str] {
+        match self {
+            Edition::Edition2024 => &[],
+            Edition::Edition2025 => &["loop", "async", "await"],
+        }
+    }
+    /// Find the edition that reserved `word`, if it wasn't reserved from 
+    /// the start
+    pub fn reserving_edition(word: &str) -> Option<Edition> {
+        Edition::ALL.iter().find(|e| e.new_reserved_keywords().contains(&word)).copied()
+    }
+    /// Find the first edition after this one where `word` is a keyword
+    pub fn later_keyword_edition(self, word: &str) -> Option<Edition> {
+        Edition::ALL.iter()
+            .filter(|e| **e > self)
+            .find(|e| e.new_strict_keywords().contains(&word) || e.new_reserved_keywords().contains(&word))
+            .copied()
+    }
+}
+
+impl Default for Edition {
+    fn default() -> Self {
+        Self::LATEST
+    }
+}
+
+impl Display for Edition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Edition::Edition2024 => "2024",
+            Edition::Edition2025 => "2025",
+        })
+    }
+}
+
+impl FromStr for Edition {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Edition::ALL.iter()
+            .find(|e| e.to_string() == s)
+            .copied()
+            .ok_or_else(|| format!(
+                "Unknown edition '{s}', expected one of {}",
+                Edition::ALL.iter().map(|e| format!("'{e}'")).collect::<Vec<_>>().join(", ")
+            ))
+    }
+}
+
+impl TryFrom<String> for Edition {
+    type Error = String;
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+/// How the tokenizer treats a word
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeywordKind {
+    /// Always a keyword, can't be used as an identifier
+    Strict,
+    /// Only a keyword in specific positions, otherwise a normal identifier
+    Contextual,
+    /// Not used by the language yet, but can't be used as an identifier so 
+    /// it may become a keyword in the future
+    Reserved,
+}
+
+/// Options that decide which items marked with `@cfg` are compiled. An 
+/// option is either a flag like `debug` or a key with a value like 
+/// `target = "wasm"`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CfgOptions {
+    flags: HashSet<String>,
+    values: HashMap<String, String>,
+}
+
+impl CfgOptions {
+    pub fn with_flag<S: Into<String>>(mut self, flag: S) -> Self {
+        self.flags.insert(flag.into());
+        self
+    }
+    pub fn with_value<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        self.values.insert(key.into(), value.into());
+        self
+    }
+    /// Add an option written like on the command line, i.e. `debug` or 
+    /// `target=wasm`. Quotes around the value are optional
+    pub fn with_option(self, option: &str) -> Self {
+        match option.split_once('=') {
+            Some((key, value)) => self.with_value(key.trim(), value.trim().trim_matches('"')),
+            None => self.with_flag(option.trim()),
+        }
+    }
+    /// Whether the flag `name` is set if `value` is `None`, or whether `name` 
+    /// is set to `value` otherwise
+    pub fn is_set(&self, name: &str, value: Option<&str>) -> bool {
+        match value {
+            Some(value) => self.values.get(name).is_some_and(|v| v == value),
+            None => self.flags.contains(name),
+        }
+    }
+}
+
+/// Bounds on the input the compiler accepts, so that compiling untrusted 
+/// code, like in the web playground, can't use unbounded memory. Exceeding a 
+/// limit is reported as an error instead
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Limits {
+    /// Maximum size of a source file in bytes. Larger files aren't tokenized
+    pub max_file_size: usize,
+    /// Maximum length of a string literal in bytes, after resolving escapes
+    pub max_string_length: usize,
+    /// How deeply brackets may be nested, and how many rules may be parsed 
+    /// inside each other
+    pub max_nesting_depth: usize,
+    /// How many errors are reported before the rest are dropped, or `None` 
+    /// for no limit. See `Logger::set_max_errors`
+    pub max_errors: Option<usize>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_file_size: 64 * 1024 * 1024,
+            max_string_length: 1024 * 1024,
+            max_nesting_depth: MAX_NESTING_DEPTH,
+            max_errors: None,
+        }
+    }
+}
+
+/// Language settings that can be changed at runtime by whoever is driving 
+/// the compiler, without having to rebuild it
+/// 
+/// The keyword tables decide how the tokenizer classifies words. The parser 
+/// matches keywords by their text regardless of whether they were tokenized 
+/// as strict or contextual, so embedders can freely promote contextual 
+/// keywords, un-reserve words, or add their own keywords for DSLs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LanguageConfig {
+    pub edition: Edition,
+    pub strict_keywords: Vec<String>,
+    pub contextual_keywords: Vec<String>,
+    pub reserved_keywords: Vec<String>,
+    /// Levels of lints that differ from their default level, by lint name
+    pub lints: HashMap<String, LintLevel>,
+    /// Options for conditional compilation with `@cfg`
+    pub cfg: CfgOptions,
+    pub limits: Limits,
+}
+
+impl Default for LanguageConfig {
+    fn default() -> Self {
+        Self::for_edition(Edition::LATEST)
+    }
+}
+
+impl LanguageConfig {
+    /// The default configuration for an edition of the language
+    pub fn for_edition(edition: Edition) -> Self {
+        let to_vec = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let mut strict_keywords = to_vec(STRICT_KEYWORDS);
+        let mut reserved_keywords = to_vec(RESERVED_KEYWORDS);
+        for e in Edition::ALL.iter().filter(|e| **e <= edition) {
+            strict_keywords.extend(to_vec(e.new_strict_keywords()));
+            reserved_keywords.extend(to_vec(e.new_reserved_keywords()));
+        }
+        Self {
+            edition,
+            strict_keywords,
+            contextual_keywords: to_vec(CONTEXTUAL_KEYWORDS),
+            reserved_keywords,
+            lints: HashMap::new(),
+            cfg: CfgOptions::default(),
+            limits: Limits::default(),
+        }
+    }
+    /// Switch to another edition, replacing the keyword tables with the 
+    /// ones of that edition while keeping the lints, cfg options and limits
+    pub fn with_edition(self, edition: Edition) -> Self {
+        Self { lints: self.lints, cfg: self.cfg, limits: self.limits, ..Self::for_edition(edition) }
+    }
+    /// The strict keywords that can start a statement
+    pub fn statement_keywords(&self) -> impl Iterator<Item = &str> {
+        self.strict_keywords.iter()
+            .map(|s| s.as_str())
+            .filter(|s| STATEMENT_KEYWORDS.contains(s))
+    }
+    /// Make `word` a keyword of the given kind, removing it from the other 
+    /// keyword tables
+    pub fn with_keyword<S: Into<String>>(mut self, word: S, kind: KeywordKind) -> Self {
+        let word = word.into();
+        self = self.without_keyword(&word);
+        match kind {
+            KeywordKind::Strict => self.strict_keywords.push(word),
+            KeywordKind::Contextual => self.contextual_keywords.push(word),
+            KeywordKind::Reserved => self.reserved_keywords.push(word),
+        }
+        self
+    }
+    /// Make `word` a normal identifier
+    pub fn without_keyword(mut self, word: &str) -> Self {
+        self.strict_keywords.retain(|k| k != word);
+        self.contextual_keywords.retain(|k| k != word);
+        self.reserved_keywords.retain(|k| k != word);
+        self
+    }
+    /// Check what kind of keyword `word` is, if any. If a word is listed in 
+    /// multiple tables, strict takes precedence over reserved, and reserved 
+    /// over contextual
+    pub fn keyword_kind(&self, word: &str) -> Option<KeywordKind> {
+        if self.strict_keywords.iter().any(|k| k == word) {
+            Some(KeywordKind::Strict)
+        }
+        else if self.reserved_keywords.iter().any(|k| k == word) {
+            Some(KeywordKind::Reserved)
+        }
+        else if self.contextual_keywords.iter().any(|k| k == word) {
+            Some(KeywordKind::Contextual)
+        }
+        else {
+            None
+        }
+    }
+}
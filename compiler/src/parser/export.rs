@@ -0,0 +1,106 @@
+//! Syntax highlighting grammars generated from the keyword and operator 
+//! tables the compiler itself uses, so editor highlighting can't drift from 
+//! what the tokenizer accepts
+
+use serde_json::{json, Value};
+use crate::ast::token::op::{BinaryOp, UnaryOp};
+use super::config::LanguageConfig;
+
+/// Strict keywords that are values rather than syntax
+const LITERAL_KEYWORDS: &[&str] = &["void", "true", "false", "none"];
+
+/// Escape the characters that have a meaning in TextMate's regex flavor
+fn escape_regex(text: &str) -> String {
+    text.chars().fold(String::new(), |mut escaped, c| {
+        if "\\.+*?()|[]{}^$".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+        escaped
+    })
+}
+
+/// Regex matching any of `words` as a whole word
+fn words_regex<'a, I: IntoIterator<Item = &'a String>>(words: I) -> String {
+    let words = words.into_iter().map(|w| escape_regex(w)).collect::<Vec<_>>();
+    format!("\\b({})\\b", words.join("|"))
+}
+
+/// Build a TextMate grammar for the language as configured by `config`, in 
+/// the JSON format used by VS Code. Custom keywords in the config are 
+/// highlighted like the built-in ones
+pub fn textmate_grammar(config: &LanguageConfig) -> Value {
+    let (literals, keywords): (Vec<_>, Vec<_>) = config.strict_keywords.iter()
+        .partition(|k| LITERAL_KEYWORDS.contains(&k.as_str()));
+
+    // Longer operators first so `..=` isn't highlighted as `..` followed by `=`
+    let mut operators = UnaryOp::ALL.iter().map(ToString::to_string)
+        .chain(BinaryOp::ALL.iter().map(ToString::to_string))
+        // The pipeline operator has its own node instead of being a binary op
+        .chain([String::from("|>")])
+        .collect::<Vec<_>>();
+    operators.sort_by(|a, b| b.len().cmp(&a.len()).then(a.cmp(b)));
+    operators.dedup();
+
+    let mut keyword_patterns = vec![];
+    for (words, scope) in [
+        (literals, "constant.language.dash"),
+        (keywords, "keyword.other.dash"),
+        (config.contextual_keywords.iter().collect(), "keyword.other.contextual.dash"),
+        (config.reserved_keywords.iter().collect(), "invalid.illegal.reserved.dash"),
+    ] {
+        if !words.is_empty() {
+            keyword_patterns.push(json!({ "name": scope, "match": words_regex(words) }));
+        }
+    }
+
+    let escape = json!({
+        "name": "constant.character.escape.dash",
+        "match": "\\\\(u\\{[0-9a-fA-F]*\\}|.)"
+    });
+    json!({
+        "$schema": "https://raw.githubusercontent.com/martinring/tmlanguage/master/tmlanguage.json",
+        "name": "dash",
+        "scopeName": "source.dash",
+        "patterns": [
+            { "include": "#comments" },
+            { "include": "#strings" },
+            { "include": "#chars" },
+            { "include": "#labels" },
+            { "include": "#numbers" },
+            { "include": "#keywords" },
+            { "include": "#operators" },
+        ],
+        "repository": {
+            "comments": {
+                "name": "comment.line.double-slash.dash",
+                "match": "//.*$"
+            },
+            "strings": {
+                "name": "string.quoted.double.dash",
+                "begin": "\\bb?\"",
+                "end": "\"",
+                "patterns": [escape]
+            },
+            "chars": {
+                "name": "string.quoted.single.dash",
+                "match": "'(\\\\(u\\{[0-9a-fA-F]*\\}|.)|[^'\\\\])'"
+            },
+            "labels": {
+                "name": "entity.name.label.dash",
+                "match": "'[\\p{L}_][\\p{L}\\p{N}_]*"
+            },
+            "numbers": {
+                "name": "constant.numeric.dash",
+                "match": "\\b[0-9]+(\\.[0-9]+)?[if]?\\b"
+            },
+            "keywords": {
+                "patterns": keyword_patterns
+            },
+            "operators": {
+                "name": "keyword.operator.dash",
+                "match": operators.iter().map(|o| escape_regex(o)).collect::<Vec<_>>().join("|")
+            },
+        }
+    })
+}
@@ -0,0 +1,647 @@
+
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+
+use crate::ast::expr::Expr;
+use crate::checker::consteval::{ConstValue, EvalConst};
+use crate::ice;
+use crate::shared::logger::{LoggerRef, Message, Level, Note};
+use crate::shared::ice::Ice;
+use crate::shared::similar::closest_match;
+use crate::shared::src::{Span, Src};
+use super::config::LanguageConfig;
+use super::parse::{NodePool, ParseRef};
+use super::tokenizer::{Token, TokenKind, TokenTree, Tokenizer, TokenIterator};
+
+/// How many macro invocations and codegen blocks may be expanded inside each 
+/// other before giving up, so a macro that expands to itself can't recurse 
+/// forever
+pub const MAX_EXPANSION_DEPTH: usize = 64;
+
+#[derive(Clone, Copy, PartialEq)]
+enum FragmentKind {
+    TokenTree,
+    Ident,
+    Expr,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Repetition {
+    ZeroOrMore,
+    OneOrMore,
+    Optional,
+}
+
+enum Matcher<'s> {
+    Token(Token<'s>),
+    Tree(Token<'s>, Vec<Matcher<'s>>),
+    Fragment(String, FragmentKind),
+    Repeat(Vec<Matcher<'s>>, Option<Token<'s>>, Repetition),
+}
+
+#[derive(Clone)]
+enum Binding<'s> {
+    Tokens(Vec<Token<'s>>),
+    Repeat(Vec<Binding<'s>>),
+}
+
+type Bindings<'s> = HashMap<String, Binding<'s>>;
+
+struct Rule<'s> {
+    pattern: Vec<Matcher<'s>>,
+    template: Vec<Token<'s>>,
+    /// Variables declared by the template itself, which are renamed on
+    /// every expansion
+    hygienic: HashSet<String>,
+}
+
+struct Macro<'s> {
+    span: Span<'s>,
+    rules: Vec<Rule<'s>>,
+}
+
+type MacroError<'s> = (String, Span<'s>);
+
+fn tree<'a, 's>(token: &'a Token<'s>) -> Option<&'a TokenTree<'s>> {
+    match &token.kind {
+        TokenKind::Parentheses(t) | TokenKind::Brackets(t) | TokenKind::Braces(t) => Some(t),
+        _ => None,
+    }
+}
+
+/// Replace the contents of a bracketed token, keeping its delimiters
+fn with_tree<'s>(token: &Token<'s>, tree: TokenTree<'s>) -> TokenKind<'s> {
+    match token.kind {
+        TokenKind::Parentheses(_) => TokenKind::Parentheses(tree),
+        TokenKind::Brackets(_) => TokenKind::Brackets(tree),
+        TokenKind::Braces(_) => TokenKind::Braces(tree),
+        _ => ice!("{token} is not a bracketed token"),
+    }
+}
+
+fn same_delimiters(a: &Token, b: &Token) -> bool {
+    std::mem::discriminant(&a.kind) == std::mem::discriminant(&b.kind)
+}
+
+fn is_punct(token: Option<&Token>, raw: &str) -> bool {
+    token.is_some_and(|t| matches!(t.kind, TokenKind::Punct) && t.raw == raw)
+}
+
+fn is_ident(token: Option<&Token>) -> bool {
+    token.is_some_and(|t| matches!(t.kind, TokenKind::Ident))
+}
+
+/// Parse the `sep? op` part after the contents of a repetition
+fn parse_repetition<'s>(
+    tokens: &[Token<'s>], after: &Span<'s>
+) -> Result<(Option<Token<'s>>, Repetition, usize), MacroError<'s>> {
+    let op = |t: Option<&Token>| t.and_then(|t| match &*t.raw {
+        "*" => Some(Repetition::ZeroOrMore),
+        "+" => Some(Repetition::OneOrMore),
+        "?" => Some(Repetition::Optional),
+        _ => None,
+    });
+    if let Some(rep) = op(tokens.first()) {
+        return Ok((None, rep, 1));
+    }
+    if let Some(rep) = op(tokens.get(1)) {
+        if tree(&tokens[0]).is_none() {
+            return Ok((Some(tokens[0].clone()), rep, 2));
+        }
+    }
+    Err((
+        String::from("Expected '*', '+' or '?' after repetition"),
+        tokens.first().map(|t| t.span.clone()).unwrap_or(after.clone())
+    ))
+}
+
+fn parse_pattern<'s>(
+    tokens: &[Token<'s>], names: &mut HashSet<String>
+) -> Result<Vec<Matcher<'s>>, MacroError<'s>> {
+    let mut res = vec![];
+    let mut i = 0;
+    while let Some(token) = tokens.get(i) {
+        i += 1;
+        if !is_punct(Some(token), "$") {
+            res.push(match tree(token) {
+                Some(t) => Matcher::Tree(token.clone(), parse_pattern(t.items(), names)?),
+                None => Matcher::Token(token.clone()),
+            });
+            continue;
+        }
+        match tokens.get(i) {
+            Some(name) if is_ident(Some(name)) => {
+                i += 1;
+                let mut kind = FragmentKind::TokenTree;
+                if is_punct(tokens.get(i), ":") {
+                    let Some(spec) = tokens.get(i + 1).filter(|t| is_ident(Some(t))) else {
+                        return Err((
+                            String::from("Expected fragment kind after ':'"),
+                            tokens[i].span.clone()
+                        ));
+                    };
+                    kind = match &*spec.raw {
+                        "tt" => FragmentKind::TokenTree,
+                        "ident" => FragmentKind::Ident,
+                        "expr" => FragmentKind::Expr,
+                        other => return Err((
+                            format!("Unknown fragment kind '{other}', expected 'tt', 'ident' or 'expr'"),
+                            spec.span.clone()
+                        )),
+                    };
+                    i += 2;
+                }
+                if !names.insert(name.raw.to_string()) {
+                    return Err((format!("Fragment '${}' is bound more than once", name.raw), name.span.clone()));
+                }
+                res.push(Matcher::Fragment(name.raw.to_string(), kind));
+            }
+            Some(group @ Token { kind: TokenKind::Parentheses(inner), .. }) => {
+                let inner = parse_pattern(inner.items(), names)?;
+                let (sep, rep, len) = parse_repetition(&tokens[i + 1..], &group.span)?;
+                i += 1 + len;
+                res.push(Matcher::Repeat(inner, sep, rep));
+            }
+            _ => return Err((String::from("Expected fragment name after '$'"), token.span.clone())),
+        }
+    }
+    Ok(res)
+}
+
+/// Find variables declared by a template, like `x` in `let x = 5`
+fn declared_names(tokens: &[Token], names: &mut HashSet<String>) {
+    for (i, token) in tokens.iter().enumerate() {
+        if let Some(t) = tree(token) {
+            declared_names(t.items(), names);
+        }
+        else if matches!(token.kind, TokenKind::Keyword) && matches!(&*token.raw, "let" | "for") {
+            // Fragments like `let $x = ...` are declared at the use site, so 
+            // only literal names are renamed
+            if let Some(name) = tokens.get(i + 1).filter(|t| is_ident(Some(t))) {
+                names.insert(name.raw.to_string());
+            }
+        }
+    }
+}
+
+/// Names of the fragments used in a template
+fn used_fragments(tokens: &[Token], names: &mut Vec<String>) {
+    for (i, token) in tokens.iter().enumerate() {
+        if let Some(t) = tree(token) {
+            used_fragments(t.items(), names);
+        }
+        else if is_ident(Some(token)) && i > 0 && is_punct(tokens.get(i - 1), "$") {
+            names.push(token.raw.to_string());
+        }
+    }
+}
+
+fn fragment_names(matchers: &[Matcher], names: &mut Vec<String>) {
+    for m in matchers {
+        match m {
+            Matcher::Token(_) => {}
+            Matcher::Tree(_, inner) | Matcher::Repeat(inner, _, _) => fragment_names(inner, names),
+            Matcher::Fragment(name, _) => names.push(name.clone()),
+        }
+    }
+}
+
+/// Match `matchers` against `tokens` starting at `pos`, returning the
+/// position after the last matched token
+fn match_at<'s>(
+    matchers: &[Matcher<'s>], tokens: &[Token<'s>], mut pos: usize, bindings: &mut Bindings<'s>
+) -> Option<usize> {
+    for m in matchers {
+        match m {
+            Matcher::Token(expected) => {
+                let token = tokens.get(pos)?;
+                if tree(token).is_some() || token.raw != expected.raw {
+                    return None;
+                }
+                pos += 1;
+            }
+            Matcher::Tree(expected, inner) => {
+                let token = tokens.get(pos).filter(|t| same_delimiters(t, expected))?;
+                if !match_all(inner, tree(token)?.items(), bindings) {
+                    return None;
+                }
+                pos += 1;
+            }
+            Matcher::Fragment(name, kind) => {
+                let len = match kind {
+                    FragmentKind::TokenTree => usize::from(pos < tokens.len()),
+                    FragmentKind::Ident => usize::from(is_ident(tokens.get(pos))),
+                    FragmentKind::Expr => tokens[pos.min(tokens.len())..].iter()
+                        .take_while(|t| !is_punct(Some(t), ",") && !is_punct(Some(t), ";"))
+                        .count(),
+                };
+                if len == 0 {
+                    return None;
+                }
+                bindings.insert(name.clone(), Binding::Tokens(tokens[pos..pos + len].to_vec()));
+                pos += len;
+            }
+            Matcher::Repeat(inner, sep, rep) => {
+                let mut iterations = vec![];
+                loop {
+                    let mut start = pos;
+                    if !iterations.is_empty() {
+                        if let Some(sep) = sep {
+                            if !tokens.get(pos).is_some_and(|t| tree(t).is_none() && t.raw == sep.raw) {
+                                break;
+                            }
+                            start += 1;
+                        }
+                    }
+                    let mut found = Bindings::new();
+                    match match_at(inner, tokens, start, &mut found) {
+                        // Stop on empty matches so `$()*` can't loop forever
+                        Some(end) if end > pos => {
+                            iterations.push(found);
+                            pos = end;
+                        }
+                        _ => break,
+                    }
+                    if *rep == Repetition::Optional {
+                        break;
+                    }
+                }
+                if *rep == Repetition::OneOrMore && iterations.is_empty() {
+                    return None;
+                }
+                let mut names = vec![];
+                fragment_names(inner, &mut names);
+                for name in names {
+                    let seq = iterations.iter_mut()
+                        .map(|b| b.remove(&name).unwrap_or(Binding::Repeat(vec![])))
+                        .collect();
+                    bindings.insert(name, Binding::Repeat(seq));
+                }
+            }
+        }
+    }
+    Some(pos)
+}
+
+fn match_all<'s>(matchers: &[Matcher<'s>], tokens: &[Token<'s>], bindings: &mut Bindings<'s>) -> bool {
+    match_at(matchers, tokens, 0, bindings) == Some(tokens.len())
+}
+
+impl<'s> Rule<'s> {
+    /// Produce the tokens this rule expands to. Tokens from the template
+    /// are placed at `at`, while tokens from fragments keep their own spans
+    fn transcribe(
+        &self, tokens: &[Token<'s>], bindings: &Bindings<'s>, at: &Span<'s>, hygiene: usize
+    ) -> Result<Vec<Token<'s>>, MacroError<'s>> {
+        let mut res = vec![];
+        let mut i = 0;
+        while let Some(token) = tokens.get(i) {
+            i += 1;
+            if is_punct(Some(token), "$") {
+                match tokens.get(i) {
+                    Some(name) if is_ident(Some(name)) => {
+                        i += 1;
+                        match bindings.get(&*name.raw) {
+                            Some(Binding::Tokens(tokens)) => res.extend(tokens.iter().cloned()),
+                            Some(Binding::Repeat(_)) => return Err((
+                                format!("Fragment '${}' is still repeating here", name.raw),
+                                name.span.clone()
+                            )),
+                            None => return Err((
+                                format!("Unknown fragment '${}'", name.raw),
+                                name.span.clone()
+                            )),
+                        }
+                        continue;
+                    }
+                    Some(group @ Token { kind: TokenKind::Parentheses(inner), .. }) => {
+                        let (sep, _, len) = parse_repetition(&tokens[i + 1..], &group.span)?;
+                        i += 1 + len;
+                        let mut names = vec![];
+                        used_fragments(inner.items(), &mut names);
+                        let repeating = names.into_iter()
+                            .filter_map(|name| match bindings.get(&name) {
+                                Some(Binding::Repeat(seq)) => Some((name, seq)),
+                                _ => None,
+                            })
+                            .collect::<Vec<_>>();
+                        let Some(count) = repeating.first().map(|(_, seq)| seq.len()) else {
+                            return Err((
+                                String::from("Repetition doesn't contain any repeating fragments"),
+                                group.span.clone()
+                            ));
+                        };
+                        if repeating.iter().any(|(_, seq)| seq.len() != count) {
+                            return Err((
+                                String::from("Fragments in this repetition repeat a different number of times"),
+                                group.span.clone()
+                            ));
+                        }
+                        for n in 0..count {
+                            if n > 0 {
+                                if let Some(ref sep) = sep {
+                                    res.push(Token { span: at.clone(), ..sep.clone() });
+                                }
+                            }
+                            let mut iteration = bindings.clone();
+                            for (name, seq) in &repeating {
+                                iteration.insert(name.clone(), seq[n].clone());
+                            }
+                            res.extend(self.transcribe(inner.items(), &iteration, at, hygiene)?);
+                        }
+                        continue;
+                    }
+                    _ => {}
+                }
+            }
+            let kind = match tree(token) {
+                Some(t) => with_tree(token, t.with_items(
+                    self.transcribe(t.items(), bindings, at, hygiene)?
+                ).moved_to(at)),
+                None => token.kind.clone(),
+            };
+            let hygienic = matches!(token.kind, TokenKind::Ident) && self.hygienic.contains(&*token.raw);
+            res.push(Token {
+                kind,
+                raw: token.raw.clone(),
+                span: at.clone(),
+                hygiene: if hygienic { hygiene } else { token.hygiene },
+            });
+        }
+        Ok(res)
+    }
+}
+
+/// Expands declarative macros on the token stream before parsing. A macro 
+/// maps patterns to templates:
+///
+/// ```text
+/// macro swap {
+///     ($a:ident, $b:ident) => { let tmp = $a; $a = $b; $b = tmp; };
+/// }
+/// swap!(x, y);
+/// ```
+///
+/// `$name` matches a single token tree, `$name:ident` an identifier and 
+/// `$name:expr` everything up to the next comma or semicolon. `$( ... ) sep? op`
+/// repeats its contents, where `op` is `*`, `+` or `?`. Macros are visible 
+/// from their definition to the end of the file
+///
+/// `codegen { expr }` blocks are also expanded here. The expression is 
+/// evaluated at compile time and must produce a string, which is tokenized 
+/// and spliced in place of the block
+pub(crate) struct Expander<'s> {
+    config: Arc<LanguageConfig>,
+    logger: LoggerRef,
+    macros: HashMap<String, Macro<'s>>,
+    next_hygiene: usize,
+    /// Only report hitting the depth limit once, as it's usually hit by
+    /// every branch of a runaway expansion
+    depth_limit_hit: bool,
+}
+
+impl<'s> Expander<'s> {
+    pub(crate) fn new(config: Arc<LanguageConfig>, logger: LoggerRef) -> Self {
+        Self {
+            config,
+            logger,
+            macros: HashMap::new(),
+            next_hygiene: 1,
+            depth_limit_hit: false,
+        }
+    }
+
+    fn error(&self, msg: Message<'s>) {
+        self.logger.lock().unwrap().log(msg);
+    }
+
+    /// Remove macro definitions from a token stream and replace every
+    /// invocation with its expansion
+    pub(crate) fn expand(&mut self, tokens: Vec<Token<'s>>) -> Vec<Token<'s>> {
+        self.expand_at_depth(tokens, 0)
+    }
+
+    fn expand_at_depth(&mut self, tokens: Vec<Token<'s>>, depth: usize) -> Vec<Token<'s>> {
+        let mut tokens = VecDeque::from(tokens);
+        let mut res = vec![];
+        while let Some(token) = tokens.pop_front() {
+            if matches!(token.kind, TokenKind::Keyword) && token.raw == "macro" {
+                self.define(token, &mut tokens);
+            }
+            else if matches!(token.kind, TokenKind::Keyword) && token.raw == "codegen" {
+                match tokens.pop_front_if(|t| matches!(t.kind, TokenKind::Braces(_))) {
+                    Some(body) => res.extend(self.codegen(token, body, depth)),
+                    None => self.error(Message::new(
+                        Level::Error, "Expected braces after codegen", token.span
+                    )),
+                }
+            }
+            // An invocation is a name directly followed by `!` and brackets. 
+            // Tokens from a template all share the span of their expansion
+            else if
+                is_ident(Some(&token)) && is_punct(tokens.front(), "!") &&
+                (tokens[0].span.1.start == token.span.1.end || tokens[0].span.1 == token.span.1) &&
+                tokens.get(1).is_some_and(|t| tree(t).is_some())
+            {
+                tokens.pop_front();
+                let args = tokens.pop_front().ice("macro arguments disappeared");
+                res.extend(self.invoke(token, args, depth));
+            }
+            else if let Some(t) = tree(&token) {
+                let items = self.expand_at_depth(t.items().to_vec(), depth);
+                res.push(Token { kind: with_tree(&token, t.with_items(items)), ..token });
+            }
+            else {
+                res.push(token);
+            }
+        }
+        res
+    }
+
+    fn define(&mut self, kw: Token<'s>, tokens: &mut VecDeque<Token<'s>>) {
+        let Some(name) = tokens.pop_front_if(|t| is_ident(Some(t))) else {
+            self.error(Message::new(Level::Error, "Expected macro name", kw.span));
+            return;
+        };
+        let span = Span(kw.span.0, kw.span.1.start..name.span.1.end);
+        let Some(body) = tokens.pop_front_if(|t| matches!(t.kind, TokenKind::Braces(_))) else {
+            self.error(Message::new(Level::Error, "Expected macro rules in braces", name.span));
+            return;
+        };
+        if is_punct(tokens.front(), ";") {
+            tokens.pop_front();
+        }
+        match Self::parse_rules(tree(&body).ice("macro body is not bracketed").items()) {
+            Ok(rules) => {
+                self.macros.insert(name.raw.to_string(), Macro { span, rules });
+            }
+            Err((info, span)) => {
+                self.error(Message::new(Level::Error, info, span));
+            }
+        }
+    }
+
+    fn parse_rules(tokens: &[Token<'s>]) -> Result<Vec<Rule<'s>>, MacroError<'s>> {
+        let mut rules = vec![];
+        let mut tokens = tokens.iter().peekable();
+        while let Some(pattern) = tokens.next() {
+            let Some(pattern_tree) = tree(pattern) else {
+                return Err((String::from("Expected macro pattern in brackets"), pattern.span.clone()));
+            };
+            if !is_punct(tokens.next(), "=>") {
+                return Err((String::from("Expected '=>' after macro pattern"), pattern.span.clone()));
+            }
+            let Some(template) = tokens.next().and_then(tree) else {
+                return Err((String::from("Expected macro template in brackets"), pattern.span.clone()));
+            };
+            let mut names = HashSet::new();
+            let mut hygienic = HashSet::new();
+            declared_names(template.items(), &mut hygienic);
+            rules.push(Rule {
+                pattern: parse_pattern(pattern_tree.items(), &mut names)?,
+                template: template.items().to_vec(),
+                hygienic,
+            });
+            if tokens.peek().is_some_and(|t| is_punct(Some(t), ";")) {
+                tokens.next();
+            }
+        }
+        Ok(rules)
+    }
+
+    /// Check that another expansion can be nested at `depth`
+    fn check_depth(&mut self, depth: usize, at: &Span<'s>) -> bool {
+        if depth < MAX_EXPANSION_DEPTH {
+            return true;
+        }
+        if !self.depth_limit_hit {
+            self.depth_limit_hit = true;
+            self.error(Message::new(
+                Level::Error,
+                format!("Macro expansion depth limit ({MAX_EXPANSION_DEPTH}) exceeded"),
+                at.clone()
+            ).note(Note::new("This is usually caused by a macro that expands to itself", true)));
+        }
+        false
+    }
+
+    fn invoke(&mut self, name: Token<'s>, args: Token<'s>, depth: usize) -> Vec<Token<'s>> {
+        let at = Span(name.span.0, name.span.1.start..args.span.1.end);
+        if !self.check_depth(depth, &at) {
+            return vec![];
+        }
+        let Some(mac) = self.macros.get(&*name.raw) else {
+            let mut msg = Message::new(Level::Error, format!("Unknown macro '{}'", name.raw), name.span);
+            // Sorted so ties between equally similar names are broken the 
+            // same way every time
+            let mut names = self.macros.keys().map(String::as_str).collect::<Vec<_>>();
+            names.sort_unstable();
+            if let Some(similar) = closest_match(&name.raw, names) {
+                msg = msg.note(Note::new(format!("Did you mean '{similar}'?"), true));
+            }
+            self.error(msg);
+            return vec![];
+        };
+        let hygiene = self.next_hygiene;
+        self.next_hygiene += 1;
+        let args = tree(&args).ice("macro arguments are not bracketed").items();
+        let mut bindings = Bindings::new();
+        let Some(rule) = mac.rules.iter().find(|rule| {
+            bindings.clear();
+            match_all(&rule.pattern, args, &mut bindings)
+        }) else {
+            let def = mac.span.clone();
+            self.error(Message::new(
+                Level::Error,
+                format!("No rule of macro '{}' matches these arguments", name.raw),
+                at
+            ).note(Note::new_at("Macro defined here", def)));
+            return vec![];
+        };
+        let expanded = match rule.transcribe(&rule.template, &bindings, &at, hygiene) {
+            Ok(tokens) => tokens,
+            Err((info, span)) => {
+                self.error(Message::new(Level::Error, info, span));
+                return vec![];
+            }
+        };
+        self.logger.lock().unwrap().add_expansion(
+            &at, format!("In expansion of macro '{}'", name.raw), Some(&mac.span)
+        );
+        self.expand_at_depth(expanded, depth + 1)
+    }
+
+    fn codegen(&mut self, kw: Token<'s>, body: Token<'s>, depth: usize) -> Vec<Token<'s>> {
+        let at = Span(kw.span.0, kw.span.1.start..body.span.1.end);
+        if !self.check_depth(depth, &at) {
+            return vec![];
+        }
+        // The block may use macros to build its code
+        let body = tree(&body).ice("codegen body is not bracketed");
+        let items = self.expand_at_depth(body.items().to_vec(), depth);
+        let body = body.with_items(items);
+
+        // Nodes parsed from the block only live long enough to evaluate it, 
+        // so their spans don't need the real source
+        let mut pool = NodePool::new();
+        let Ok(expr) = Expr::parse_complete(&mut pool, Src::builtin(), TokenIterator::from(body.clone())) else {
+            return vec![];
+        };
+        let code = match expr.eval_const(&pool) {
+            Ok(ConstValue::String(code)) => code,
+            Ok(value) => {
+                self.error(Message::new(
+                    Level::Error,
+                    format!("Codegen block must evaluate to a string, got {value}"),
+                    at
+                ));
+                return vec![];
+            }
+            Err(e) => {
+                self.error(Message::new(
+                    Level::Error, "Codegen block can't be evaluated at compile time", at
+                ).note(Note::new_at(e.info, Span(kw.span.0, e.span.1))));
+                return vec![];
+            }
+        };
+
+        let src = Src::from_memory("<codegen>", code.as_str());
+        let tokens = Tokenizer::new(&src, self.config.clone(), self.logger.clone())
+            .map(|t| Self::adopt(t, &body, &at))
+            .collect();
+        self.logger.lock().unwrap().add_expansion(
+            &at, format!("In code generated by this block: {}", code.trim()), None
+        );
+        self.expand_at_depth(tokens, depth + 1)
+    }
+
+    /// Move a token from generated code into the source at `at`
+    fn adopt(token: Token, tree: &TokenTree<'s>, at: &Span<'s>) -> Token<'s> {
+        let subtree = |t: TokenTree| tree.with_items(
+            t.map(|t| Self::adopt(t, tree, at)).collect()
+        ).moved_to(at);
+        let kind = match token.kind {
+            TokenKind::Keyword => TokenKind::Keyword,
+            TokenKind::Ident => TokenKind::Ident,
+            TokenKind::Punct => TokenKind::Punct,
+            TokenKind::Int(i) => TokenKind::Int(i),
+            TokenKind::Float(f) => TokenKind::Float(f),
+            TokenKind::String(s) => TokenKind::String(s),
+            TokenKind::Bytes(b) => TokenKind::Bytes(b),
+            TokenKind::Char(c) => TokenKind::Char(c),
+            TokenKind::Label => TokenKind::Label,
+            TokenKind::Parentheses(t) => TokenKind::Parentheses(subtree(t)),
+            TokenKind::Brackets(t) => TokenKind::Brackets(subtree(t)),
+            TokenKind::Braces(t) => TokenKind::Braces(subtree(t)),
+            TokenKind::Reserved => TokenKind::Reserved,
+            TokenKind::Error(e) => TokenKind::Error(e),
+        };
+        Token {
+            kind,
+            raw: Cow::Owned(token.raw.into_owned()),
+            span: at.clone(),
+            hygiene: token.hygiene,
+        }
+    }
+}
@@ -1,3 +1,9 @@
 
+pub mod config;
+pub mod export;
+pub(crate) mod macros;
 pub mod parse;
+pub mod profile;
 pub(crate) mod tokenizer;
+
+pub use tokenizer::{Token, TokenKind, Trivia, TriviaKind};
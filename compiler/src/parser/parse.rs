@@ -1,7 +1,7 @@
 
-use std::{sync::Arc, marker::PhantomData, cell::RefCell};
+use std::{sync::Arc, marker::PhantomData, cell::RefCell, fmt::Write};
 use crate::{
-    shared::{src::{Src, ArcSpan}, logger::LoggerRef},
+    shared::{src::{Src, ArcSpan}, logger::LoggerRef, ice::Ice},
     checker::{resolve::{ResolveRef, ResolveNode}, coherency::Checker, ty::Ty}
 };
 use super::tokenizer::TokenIterator;
@@ -62,6 +62,13 @@ pub trait Node: AsAny {
     fn span_or_builtin(&self, pool: &NodePool) -> ArcSpan {
         self.span(pool).unwrap_or(ArcSpan::builtin())
     }
+
+    /// Get the name of this Node's type, for debugging
+    fn name(&self) -> &'static str {
+        let name = std::any::type_name::<Self>();
+        let name = name.split('<').next().unwrap_or(name);
+        name.rsplit("::").next().unwrap_or(name)
+    }
 }
 
 pub trait ParseNode: Node + Sized {
@@ -326,6 +333,10 @@ impl<T: Ref, S: Ref> SeparatedWithTrailing<T, S> {
     pub fn iter(&self) -> std::slice::Iter<'_, T> {
         self.items.iter()
     }
+    /// Whether the list ended with a separator, like `(a,)`
+    pub fn has_trailing(&self) -> bool {
+        self.trailing.is_some()
+    }
     // pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, T> {
     //     self.items.iter_mut()
     // }
@@ -341,7 +352,12 @@ impl<T: Ref, S: Ref> Ref for SeparatedWithTrailing<T, S> {
 
 impl<T: ParseRef, S: ParseRef> ParseRef for SeparatedWithTrailing<T, S> {
     fn parse_ref(pool: &mut NodePool, src: Arc<Src>, tokenizer: &mut TokenIterator) -> Result<Self, FatalParseError> {
-        let mut items = Vec::from([T::parse_ref(pool, src.clone(), tokenizer)?]);
+        // Always used inside delimiters, where an empty list like `()` is 
+        // fine
+        let Some(first) = T::peek_and_parse(pool, src.clone(), tokenizer)? else {
+            return Ok(Self { items: Vec::new(), trailing: None, _phantom: PhantomData });
+        };
+        let mut items = Vec::from([first]);
         let mut trailing = None;
         while let Some(sep) = S::peek_and_parse(pool, src.clone(), tokenizer)? {
             if let Some(item) = T::peek_and_parse(pool, src.clone(), tokenizer)? {
@@ -392,6 +408,80 @@ impl<T: ParseRef, M: CompileMessage> ResolveRef for DontExpect<T, M> {
     }
 }
 
+/// An expression that is followed by a block, like the condition of an `if`. 
+/// Braces after a call in it start that block instead of being passed to 
+/// the call as a trailing block
+#[derive(Debug)]
+pub struct BeforeBlock<T: Ref>(T);
+
+impl<T: Ref> std::ops::Deref for BeforeBlock<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: Ref> Ref for BeforeBlock<T> {
+    fn ids(&self) -> Vec<NodeID> {
+        self.0.ids()
+    }
+}
+
+impl<T: ParseRef> ParseRef for BeforeBlock<T> {
+    fn parse_ref(pool: &mut NodePool, src: Arc<Src>, tokenizer: &mut TokenIterator) -> Result<Self, FatalParseError> {
+        let prev = tokenizer.set_allow_trailing_blocks(false);
+        let res = T::parse_ref(pool, src, tokenizer);
+        tokenizer.set_allow_trailing_blocks(prev);
+        Ok(Self(res?))
+    }
+    fn peek(pos: usize, tokenizer: &TokenIterator) -> bool {
+        T::peek(pos, tokenizer)
+    }
+}
+
+impl<T: ResolveRef> ResolveRef for BeforeBlock<T> {
+    fn try_resolve_ref(&self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        self.0.try_resolve_ref(pool, checker)
+    }
+}
+
+/// An expression in a list separated by `|`, like the values of a switch 
+/// case. A `|` in it separates the list instead of being the bitwise or 
+/// operator, unless it is inside parentheses
+#[derive(Debug)]
+pub struct WithoutBitOr<T: Ref>(T);
+
+impl<T: Ref> std::ops::Deref for WithoutBitOr<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: Ref> Ref for WithoutBitOr<T> {
+    fn ids(&self) -> Vec<NodeID> {
+        self.0.ids()
+    }
+}
+
+impl<T: ParseRef> ParseRef for WithoutBitOr<T> {
+    fn parse_ref(pool: &mut NodePool, src: Arc<Src>, tokenizer: &mut TokenIterator) -> Result<Self, FatalParseError> {
+        let prev = tokenizer.set_allow_bit_or(false);
+        let res = T::parse_ref(pool, src, tokenizer);
+        tokenizer.set_allow_bit_or(prev);
+        Ok(Self(res?))
+    }
+    fn peek(pos: usize, tokenizer: &TokenIterator) -> bool {
+        T::peek(pos, tokenizer)
+    }
+}
+
+impl<T: ResolveRef> ResolveRef for WithoutBitOr<T> {
+    fn try_resolve_ref(&self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        self.0.try_resolve_ref(pool, checker)
+    }
+}
+
 /// Marker trait for structs representing single tokens
 pub trait IsToken {
     fn assert_ty_is_token() {}
@@ -400,7 +490,7 @@ impl<T: IsToken> IsToken for Option<T> {}
 impl<T: IsToken + ResolveNode> IsToken for RefToNode<T> {}
 
 /// An unique ID for a node in the NodePool
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct NodeID(usize);
 
 struct NodeData {
@@ -437,41 +527,111 @@ impl NodePool {
     pub fn new() -> Self {
         Self { nodes: vec![] }
     }
+    /// How many nodes have been added to this pool
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
     /// Add a new Node to this pool. Returns the added node's ID
     pub fn add<N: ResolveNode>(&mut self, t: N) -> NodeID {
         let id = NodeID(self.nodes.len());
         self.nodes.push(RefCell::from(NodeData::new(t)));
         id
     }
+    fn cell(&self, id: NodeID) -> &RefCell<NodeData> {
+        self.nodes.get(id.0).ice("node ID is not in this pool")
+    }
     fn get(&self, id: NodeID) -> std::cell::Ref<'_, dyn ResolveNode> {
-        std::cell::Ref::map(
-            self.nodes.get(id.0).unwrap().borrow(),
-            |e| e.node.as_ref()
-        )
+        std::cell::Ref::map(self.cell(id).borrow(), |e| e.node.as_ref())
     }
     fn get_as<T: Node>(&self, id: NodeID) -> std::cell::Ref<'_, T> {
         std::cell::Ref::map(
-            self.nodes.get(id.0).unwrap().borrow(),
-            |e| e.node.as_ref().as_any().downcast_ref().unwrap()
+            self.cell(id).borrow(),
+            |e| e.node.as_ref().as_any().downcast_ref().ice("node ID refers to a node of another type")
         )
     }
     fn get_data(&self, id: NodeID) -> std::cell::Ref<'_, NodeData> {
-        self.nodes.get(id.0).unwrap().borrow()
+        self.cell(id).borrow()
     }
     fn get_mut(&self, id: NodeID) -> std::cell::RefMut<'_, dyn ResolveNode> {
-        std::cell::RefMut::map(
-            self.nodes.get(id.0).unwrap().borrow_mut(),
-            |e| e.node.as_mut()
-        )
+        std::cell::RefMut::map(self.cell(id).borrow_mut(), |e| e.node.as_mut())
     }
     fn get_as_mut<T: ResolveNode>(&self, id: NodeID) -> std::cell::RefMut<'_, T> {
         std::cell::RefMut::map(
-            self.nodes.get(id.0).unwrap().borrow_mut(),
-            |e| e.node.as_mut().as_any_mut().downcast_mut().unwrap()
+            self.cell(id).borrow_mut(),
+            |e| e.node.as_mut().as_any_mut().downcast_mut().ice("node ID refers to a node of another type")
         )
     }
     fn get_data_mut(&self, id: NodeID) -> std::cell::RefMut<'_, NodeData> {
-        self.nodes.get(id.0).unwrap().borrow_mut()
+        self.cell(id).borrow_mut()
+    }
+    /// Mark the nodes referenced by `root` and all of their children as 
+    /// resolved without checking them, for code that is not compiled
+    pub(crate) fn mark_skipped<R: Ref>(&self, root: &R) {
+        for id in root.ids() {
+            self.mark_skipped_node(id);
+        }
+    }
+    fn mark_skipped_node(&self, id: NodeID) {
+        let children = self.get(id).children().into_iter().flat_map(|c| c.ids()).collect::<Vec<_>>();
+        {
+            let mut data = self.get_data_mut(id);
+            data.ty = Some(Ty::Invalid);
+            data.previous_resolve_state = true;
+        }
+        for child in children {
+            self.mark_skipped_node(child);
+        }
+    }
+    /// The span of all nodes referenced by `r`
+    pub(crate) fn span_of<R: Ref>(&self, r: &R) -> Option<ArcSpan> {
+        calculate_span(r.ids().into_iter().map(|id| self.get(id).span(self)))
+    }
+    /// Render the tree of nodes referenced by `root` for debugging. Nodes 
+    /// without children are shown with the source code they span
+    pub fn dump<R: Ref>(&self, root: &R) -> String {
+        let mut out = String::new();
+        for id in root.ids() {
+            self.dump_node(id, 0, &mut out);
+        }
+        out
+    }
+    fn dump_node(&self, id: NodeID, indent: usize, out: &mut String) {
+        let node = self.get(id);
+        write!(out, "{:indent$}{}", "", node.name()).unwrap();
+        let children = node.children().into_iter().flat_map(|c| c.ids()).collect::<Vec<_>>();
+        if let Some(ArcSpan(src, range)) = node.span(self) {
+            write!(out, " @ {}..{}", range.start, range.end).unwrap();
+            if children.is_empty() {
+                write!(out, " {:?}", &src.data()[range]).unwrap();
+            }
+        }
+        out.push('\n');
+        for child in children {
+            self.dump_node(child, indent + 2, out);
+        }
+    }
+    /// Call `f` on every node referenced by `root` and their children, 
+    /// depth-first, with each node's span and resolved type
+    pub(crate) fn visit<R, F>(&self, root: &R, f: &mut F)
+        where
+            R: Ref,
+            F: FnMut(NodeID, &dyn ResolveNode, Option<ArcSpan>, Option<Ty>)
+    {
+        for id in root.ids() {
+            self.visit_node(id, f);
+        }
+    }
+    fn visit_node<F>(&self, id: NodeID, f: &mut F)
+        where F: FnMut(NodeID, &dyn ResolveNode, Option<ArcSpan>, Option<Ty>)
+    {
+        let node = self.get(id);
+        f(id, &*node, node.span(self), self.get_data(id).ty.clone());
+        for child in node.children().into_iter().flat_map(|c| c.ids()) {
+            self.visit_node(child, f);
+        }
     }
     pub fn release_unresolved(&self, checker: &Checker, logger: LoggerRef) {
         for node in &self.nodes {
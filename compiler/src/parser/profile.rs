@@ -0,0 +1,101 @@
+
+use std::{collections::HashMap, time::{Duration, Instant}, sync::{Arc, Mutex}, fmt::Display, io::Write};
+
+/// How often a parser rule was invoked and how long it took
+#[derive(Debug, Default, Clone)]
+pub struct RuleStats {
+    pub calls: usize,
+    pub failures: usize,
+    /// Cumulative time spent in this rule, including the rules it invoked. 
+    /// Time spent in recursive invocations is counted once per invocation
+    pub total: Duration,
+    /// Cumulative time spent in this rule itself, excluding the rules it 
+    /// invoked
+    pub own: Duration,
+}
+
+struct Frame {
+    rule: &'static str,
+    start: Instant,
+    children: Duration,
+}
+
+/// Records every rule the parser attempts to match. Useful for finding 
+/// pathological backtracking in the grammar
+///
+/// Rules that are attempted many times at the same position but usually 
+/// fail show up as a high failure count
+#[derive(Default)]
+pub struct ParseProfiler {
+    stack: Vec<Frame>,
+    rules: HashMap<&'static str, RuleStats>,
+    /// Own time of every distinct rule stack, keyed by the stack joined by ';'
+    folded: HashMap<String, Duration>,
+}
+
+pub type ProfilerRef = Arc<Mutex<ParseProfiler>>;
+
+impl ParseProfiler {
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new() -> ProfilerRef {
+        Arc::new(Mutex::new(Self::default()))
+    }
+    pub(crate) fn enter(&mut self, rule: &'static str) {
+        self.stack.push(Frame { rule, start: Instant::now(), children: Duration::ZERO });
+    }
+    pub(crate) fn exit(&mut self, matched: bool) {
+        let Some(frame) = self.stack.pop() else { return };
+        let elapsed = frame.start.elapsed();
+        let own = elapsed.saturating_sub(frame.children);
+        if let Some(parent) = self.stack.last_mut() {
+            parent.children += elapsed;
+        }
+        let stats = self.rules.entry(frame.rule).or_default();
+        stats.calls += 1;
+        stats.failures += usize::from(!matched);
+        stats.total += elapsed;
+        stats.own += own;
+
+        let path = self.stack.iter()
+            .map(|f| f.rule)
+            .chain([frame.rule])
+            .collect::<Vec<_>>()
+            .join(";");
+        *self.folded.entry(path).or_default() += own;
+    }
+    /// Statistics for each rule, sorted by cumulative own time
+    pub fn rules(&self) -> Vec<(&'static str, &RuleStats)> {
+        let mut rules = self.rules.iter().map(|(r, s)| (*r, s)).collect::<Vec<_>>();
+        rules.sort_by(|a, b| b.1.own.cmp(&a.1.own).then(a.0.cmp(b.0)));
+        rules
+    }
+    pub fn rule(&self, rule: &str) -> Option<&RuleStats> {
+        self.rules.get(rule)
+    }
+    /// Write the recorded rule stacks in the folded stack format accepted by 
+    /// flamegraph tools (such as `inferno-flamegraph` or `flamegraph.pl`), 
+    /// with sample counts in microseconds
+    pub fn write_folded<W: Write>(&self, mut out: W) -> std::io::Result<()> {
+        let mut stacks = self.folded.iter().collect::<Vec<_>>();
+        stacks.sort_by(|a, b| a.0.cmp(b.0));
+        for (stack, time) in stacks {
+            writeln!(out, "{stack} {}", time.as_micros())?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for ParseProfiler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{:<24} {:>8} {:>8} {:>10} {:>10}", "rule", "calls", "failed", "own (ms)", "total (ms)")?;
+        for (rule, stats) in self.rules() {
+            writeln!(
+                f, "{rule:<24} {:>8} {:>8} {:>10.3} {:>10.3}",
+                stats.calls, stats.failures,
+                stats.own.as_secs_f64() * 1000.0,
+                stats.total.as_secs_f64() * 1000.0
+            )?;
+        }
+        Ok(())
+    }
+}
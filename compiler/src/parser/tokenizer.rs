@@ -1,62 +1,47 @@
 
+use std::borrow::Cow;
 use std::fmt::Display;
+use std::io;
 use std::ops::Range;
+use std::sync::{Arc, Mutex};
 
 use crate::shared::char_iter::CharIter;
 use crate::shared::src::{Src, Span};
-use crate::shared::logger::{LoggerRef, Message, Level};
+use crate::shared::logger::{LoggerRef, Message, Level, Note};
 use unicode_xid::UnicodeXID;
 
-pub(crate) const STRICT_KEYWORDS: &[&str] = &[
-    // Literals
-    "void", "true", "false", "none",
-    // Constants & special variables
-    "this", "super",
-    // Declarations
-    "var", "let", "fun", "struct", "enum", "using",
-    "macro", "extends", "module", "type",
-    // Prepositions
-    "in", "is", "as", "where", "from",
-    // Loops & conditionals
-    "if", "else", "for", "while",
-    // Control flow
-    "try", "return", "break", "continue",
-    // Visibility
-    "extern", "public", "private",
-    // Types
-    "typeof", "const",
-    // Other
-    "codegen", "compiler_intrinsic"
-];
-pub(crate) const RESERVED_KEYWORDS: &[&str] = &[
-    // Declarations
-    "trait", "class", "interface",
-    // Control flow
-    "unwrap", "yield", "match", "switch",
-    // Visibility
-    "export", "import",
-    // Reactivity
-    "depends", "required",
-    // Macros
-    "reflect", "codegen",
-    // Other
-    "mut", "mutable", "new", "null"
-];
+use super::config::{LanguageConfig, KeywordKind, Edition};
+use super::parse::FatalParseError;
+use super::profile::ProfilerRef;
+use super::macros::Expander;
+use crate::shared::cancel::CancellationToken;
+use crate::shared::ice::Ice;
+use crate::ice;
 
 pub const MAX_PEEK_COUNT: usize = 2;
 
+/// How many rules may be nested inside each other while parsing before 
+/// giving up by default, so deeply nested input can't overflow the stack. 
+/// See `Limits::max_nesting_depth`
+pub const MAX_NESTING_DEPTH: usize = 256;
+
+/// Where the parser writes a line for every rule it attempts to match, see 
+/// `TokenIterator::set_debug_log_matches`
+pub type MatchLogRef = Arc<Mutex<dyn io::Write + Send>>;
+
 fn closing_paren(ch: char) -> char {
     match ch {
         '(' => ')',
         '[' => ']',
         '{' => '}',
-        _   => unreachable!(),
+        _   => ice!("'{ch}' is not an opening bracket"),
     }
 }
 
 trait IsTokenChar {
     fn is_op_char(&self) -> bool;
     fn is_punct_char(&self) -> bool;
+    fn is_invalid_char(&self) -> bool;
 }
 
 impl IsTokenChar for char {
@@ -64,10 +49,19 @@ impl IsTokenChar for char {
         matches!(self, '=' | '+' | '-' | '/' | '%' | '&' | '|' | '^' | '*' | '~' | '!' | '?' | '<' | '>' | '#')
     }
     fn is_punct_char(&self) -> bool {
-        matches!(self, ',' | ';' | '.' | ':' | '@')
+        matches!(self, ',' | ';' | '.' | ':' | '@' | '$')
+    }
+    /// Whether this character can't start any token
+    fn is_invalid_char(&self) -> bool {
+        !(
+            self.is_whitespace() || self.is_xid_start() || self.is_ascii_digit() ||
+            self.is_op_char() || self.is_punct_char() ||
+            matches!(self, '"' | '\'' | '(' | '[' | '{' | ')' | ']' | '}')
+        )
     }
 }
 
+#[derive(Clone)]
 pub enum TokenKind<'s> {
     Keyword,
     Ident,
@@ -75,16 +69,79 @@ pub enum TokenKind<'s> {
     Int(i64),
     Float(f64),
     String(String),
+    /// Byte string like `b"GIF89a"`
+    Bytes(Vec<u8>),
+    Char(char),
+    /// Loop label like `'outer`
+    Label,
     Parentheses(TokenTree<'s>),
     Brackets(TokenTree<'s>),
     Braces(TokenTree<'s>),
+    /// A word reserved for future use, which is reported and skipped like 
+    /// an error token
+    Reserved,
     Error(String),
 }
 
+#[derive(Clone)]
 pub struct Token<'s> {
     pub kind: TokenKind<'s>,
-    pub raw: &'s str,
+    /// The source text of this token. Owned for tokens that don't come 
+    /// from the source directly, such as ones produced by a codegen block
+    pub raw: Cow<'s, str>,
     pub span: Span<'s>,
+    /// Identifies the macro expansion that introduced this token, so 
+    /// variables declared inside a macro template can't clash with ones 
+    /// at the use site. Zero for tokens written directly in the source
+    pub(crate) hygiene: usize,
+}
+
+impl<'s> Token<'s> {
+    /// Short name of this token's kind, for tools like syntax highlighters
+    pub fn kind_name(&self) -> &'static str {
+        match &self.kind {
+            TokenKind::Keyword => "keyword",
+            TokenKind::Ident => "ident",
+            TokenKind::Punct => "punct",
+            TokenKind::Int(_) => "int",
+            TokenKind::Float(_) => "float",
+            TokenKind::String(_) => "string",
+            TokenKind::Bytes(_) => "bytes",
+            TokenKind::Char(_) => "char",
+            TokenKind::Label => "label",
+            TokenKind::Parentheses(_) => "parentheses",
+            TokenKind::Brackets(_) => "brackets",
+            TokenKind::Braces(_) => "braces",
+            TokenKind::Reserved => "reserved",
+            TokenKind::Error(_) => "error",
+        }
+    }
+    /// The text of this token as seen by the parser. Raw identifiers like 
+    /// `r#match` lose their prefix, and identifiers introduced by a macro 
+    /// expansion get the expansion appended to keep them distinct
+    pub fn text(&self) -> Cow<'s, str> {
+        let raw = match (&self.kind, &self.raw) {
+            (TokenKind::Ident, Cow::Borrowed(raw)) => Cow::Borrowed(raw.strip_prefix("r#").unwrap_or(raw)),
+            (TokenKind::Ident, Cow::Owned(raw)) => Cow::Owned(raw.strip_prefix("r#").unwrap_or(raw).to_string()),
+            _ => self.raw.clone(),
+        };
+        if self.hygiene == 0 {
+            raw
+        }
+        else {
+            Cow::Owned(format!("{raw}#{}", self.hygiene))
+        }
+    }
+    /// Replace bracketed token trees with the tokens inside them, 
+    /// recursively. The brackets themselves are not included
+    pub fn flatten(self) -> Vec<Token<'s>> {
+        match self.kind {
+            TokenKind::Parentheses(tree) | TokenKind::Brackets(tree) | TokenKind::Braces(tree) => {
+                tree.flat_map(Token::flatten).collect()
+            }
+            _ => vec![self],
+        }
+    }
 }
 
 impl Display for Token<'_> {
@@ -96,9 +153,13 @@ impl Display for Token<'_> {
             TokenKind::Int(_) => write!(f, "integer"),
             TokenKind::Float(_) => write!(f, "float"),
             TokenKind::String(_) => write!(f, "string"),
+            TokenKind::Bytes(_) => write!(f, "byte string"),
+            TokenKind::Char(_) => write!(f, "character"),
+            TokenKind::Label => write!(f, "label {}", self.raw),
             TokenKind::Parentheses(_) => write!(f, "parenthesized expression"),
             TokenKind::Brackets(_) => write!(f, "bracketed expression"),
             TokenKind::Braces(_) => write!(f, "braced expression"),
+            TokenKind::Reserved => write!(f, "reserved keyword {}", self.raw),
             TokenKind::Error(err) => write!(f, "invalid token ({err})"),
         }
     }
@@ -115,10 +176,38 @@ impl std::fmt::Debug for Token<'_> {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriviaKind {
+    Whitespace,
+    /// Line comment like `// hi`, not including the newline that ends it
+    Comment,
+}
+
+/// Source text between tokens that the parser ignores. Kept so that tools 
+/// like formatters can reproduce the original file exactly
+#[derive(Clone)]
+pub struct Trivia<'s> {
+    pub kind: TriviaKind,
+    pub span: Span<'s>,
+}
+
+impl<'s> Trivia<'s> {
+    pub fn raw(&self) -> &'s str {
+        &self.span.0.data()[self.span.1.clone()]
+    }
+}
+
 pub struct Tokenizer<'s> {
     src: &'s Src,
     iter: CharIter<'s>,
+    config: Arc<LanguageConfig>,
     logger: LoggerRef,
+    /// Whitespace and comments skipped so far, in source order
+    trivia: Vec<Trivia<'s>>,
+    max_depth: usize,
+    /// Set if the source is larger than `Limits::max_file_size`, in which 
+    /// case no tokens are produced
+    too_large: bool,
 }
 
 impl std::fmt::Debug for Tokenizer<'_> {
@@ -128,34 +217,310 @@ impl std::fmt::Debug for Tokenizer<'_> {
 }
 
 impl<'s> Tokenizer<'s> {
-    pub fn new(src: &'s Src, logger: LoggerRef) -> Self {
-        Self { src, iter: src.iter(), logger, }
+    pub fn new(src: &'s Src, config: Arc<LanguageConfig>, logger: LoggerRef) -> Self {
+        let (size, max_size) = (src.data().len(), config.limits.max_file_size);
+        let too_large = size > max_size;
+        if too_large {
+            logger.lock().unwrap().log(Message::new(
+                Level::Error,
+                format!("File is too large ({size} bytes, maximum is {max_size})"),
+                Span(src, 0..0)
+            ));
+        }
+        for invalid in src.invalid_utf8() {
+            let end = invalid.offset + char::REPLACEMENT_CHARACTER.len_utf8();
+            logger.lock().unwrap().log(Message::new(
+                Level::Error,
+                format!("Invalid UTF-8 at byte {} of the file", invalid.byte_offset),
+                Span(src, invalid.offset..end)
+            ).note(Note::new("Source files must be saved as UTF-8", true)));
+        }
+        Self {
+            src, iter: src.iter(),
+            max_depth: config.limits.max_nesting_depth,
+            config, logger,
+            trivia: vec![],
+            too_large,
+        }
+    }
+    /// Whitespace and comments skipped so far, including those inside 
+    /// bracketed expressions. Together with the tokens, these cover the 
+    /// whole source without gaps or overlaps
+    pub fn trivia(&self) -> &[Trivia<'s>] {
+        &self.trivia
+    }
+    /// Set how deeply brackets may be nested before the whole bracketed 
+    /// expression is turned into an error token
+    pub fn with_max_nesting_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+    /// Skip the rest of a bracketed expression whose opening bracket has 
+    /// already been consumed, without building a tree of it
+    fn skip_brackets(&mut self) {
+        let mut depth = 0usize;
+        loop {
+            self.skip_ws();
+            match self.iter.peek() {
+                Some('(' | '[' | '{') => {
+                    self.iter.next();
+                    depth += 1;
+                }
+                Some(')' | ']' | '}') => {
+                    self.iter.next();
+                    if depth == 0 {
+                        break;
+                    }
+                    depth -= 1;
+                }
+                // Skip other tokens properly so brackets in strings and 
+                // comments aren't counted
+                Some(_) => {
+                    self.next();
+                }
+                None => break,
+            }
+        }
+    }
+    /// Tokenize a bracketed expression, starting at its opening bracket. 
+    /// Brackets nested inside it are kept on a stack rather than handled 
+    /// by recursing into `next`, whose frame is large enough that nesting 
+    /// up to the maximum depth could overflow the stack
+    fn token_tree(&mut self) -> Token<'s> {
+        // Brackets that have been opened but not closed yet, innermost last
+        let mut open: Vec<(char, usize, Vec<Token<'s>>)> = vec![];
+        loop {
+            self.skip_ws();
+            let start = self.offset();
+            let token = match self.iter.peek() {
+                Some(c @ ('(' | '[' | '{')) => {
+                    self.iter.next();
+                    if open.len() < self.max_depth {
+                        open.push((c, start, vec![]));
+                        continue;
+                    }
+                    self.skip_brackets();
+                    self.token_from(start, TokenKind::Error(format!(
+                        "brackets nested too deeply (maximum depth is {})", self.max_depth
+                    )))
+                }
+                Some(c) if open.last().is_some_and(|(opening, ..)| c == closing_paren(*opening)) => {
+                    self.iter.next();
+                    let (opening, start, items) = open.pop().ice("no open bracket to close");
+                    let tree = TokenTree {
+                        src: self.src,
+                        items: items.into_iter(),
+                        start_offset: start,
+                        eof: self.offset() - 1..self.offset(),
+                        config: self.config.clone(),
+                        logger: self.logger.clone(),
+                    };
+                    self.token_from(start, match opening {
+                        '(' => TokenKind::Parentheses(tree),
+                        '[' => TokenKind::Brackets(tree),
+                        '{' => TokenKind::Braces(tree),
+                        _ => ice!("'{opening}' is not an opening bracket"),
+                    })
+                }
+                Some(_) => self.next().ice("no token before closing bracket"),
+                None => {
+                    // Only the outermost bracket is reported as unclosed
+                    let (_, start, _) = open.first().ice("no open bracket at end of file");
+                    return self.token_from(*start, TokenKind::Error("unclosed parenthesis".to_string()));
+                }
+            };
+            match open.last_mut() {
+                Some((_, _, items)) => items.push(token),
+                None => return token,
+            }
+        }
+    }
+    /// Make a token spanning from `start` to the current position
+    fn token_from(&self, start: usize, kind: TokenKind<'s>) -> Token<'s> {
+        let end = self.offset();
+        let raw = &self.iter.src_str()[start..end];
+        Token { kind, raw: Cow::Borrowed(raw), span: Span(self.src, start..end), hygiene: 0 }
     }
     fn skip_ws(&mut self) {
         loop {
+            let start = self.offset();
             // Ignore comments
             if self.iter.peek().is_some_and(|c| c == '/') &&
                 self.iter.peek1().is_some_and(|c| c == '/')
             {
-                self.iter.next();
-                self.iter.next();
-                for c in &mut self.iter {
-                    if c == '\n' {
-                        break;
-                    }
+                while self.iter.peek().is_some_and(|c| c != '\n') {
+                    self.iter.next();
                 }
+                self.push_trivia(TriviaKind::Comment, start);
                 continue;
             }
             // Continue skipping until we encounter a non-whitespace character
             if self.iter.peek().is_some_and(|c| c.is_whitespace()) {
-                self.iter.next();
+                while self.iter.peek().is_some_and(|c| c.is_whitespace()) {
+                    self.iter.next();
+                }
+                self.push_trivia(TriviaKind::Whitespace, start);
                 continue;
             }
             break;
         }
     }
+    fn push_trivia(&mut self, kind: TriviaKind, start: usize) {
+        self.trivia.push(Trivia { kind, span: Span(self.src, start..self.offset()) });
+    }
     fn offset(&self) -> usize {
-        self.iter.offset() - 1
+        self.iter.offset()
+    }
+    /// Parse the rest of a string literal after the opening quote, 
+    /// resolving escape sequences. Returns `None` if the string is never 
+    /// closed. Characters past `Limits::max_string_length` are skipped 
+    /// rather than stored, see `string_token`
+    fn string_contents(&mut self) -> Option<String> {
+        let max = self.config.limits.max_string_length;
+        let mut escaped = String::new();
+        loop {
+            let c = match self.iter.next()? {
+                '"' => return Some(escaped),
+                '\\' => self.escape(),
+                c => c,
+            };
+            if escaped.len() <= max {
+                escaped.push(c);
+            }
+        }
+    }
+    /// Parse the rest of a byte string literal after the opening quote, like 
+    /// `string_contents`. Besides the escapes of strings, bytes can be 
+    /// written as `\xNN`. Characters outside ASCII are reported as errors
+    fn byte_string_contents(&mut self) -> Option<Vec<u8>> {
+        let max = self.config.limits.max_string_length;
+        let mut bytes = Vec::new();
+        loop {
+            let start = self.offset();
+            let c = match self.iter.next()? {
+                '"' => return Some(bytes),
+                '\\' if self.iter.peek() == Some('x') => {
+                    self.iter.next();
+                    Some(self.byte_escape(start))
+                }
+                '\\' => u8::try_from(self.escape()).ok().filter(u8::is_ascii),
+                c => u8::try_from(c).ok().filter(u8::is_ascii),
+            };
+            let Some(byte) = c else {
+                self.logger.lock().unwrap().log(Message::new(
+                    Level::Error,
+                    "Byte strings may only contain ASCII characters",
+                    Span(self.src, start..self.offset())
+                ).note(Note::new("Other bytes are written like \\xFF", true)));
+                continue;
+            };
+            if bytes.len() <= max {
+                bytes.push(byte);
+            }
+        }
+    }
+    /// Parse the rest of a `\xNN` escape in a byte string after the `\x`. 
+    /// On error, the diagnostic spans from `escape_start` to the end of the 
+    /// escape and zero is returned
+    fn byte_escape(&mut self, escape_start: usize) -> u8 {
+        let mut digits = String::new();
+        while digits.len() < 2 {
+            let Some(c) = self.iter.peek().filter(char::is_ascii_hexdigit) else { break };
+            digits.push(c);
+            self.iter.next();
+        }
+        match u8::from_str_radix(&digits, 16) {
+            Ok(byte) if digits.len() == 2 => byte,
+            _ => {
+                self.logger.lock().unwrap().log(Message::new(
+                    Level::Error,
+                    "Byte escape must have exactly 2 hex digits",
+                    Span(self.src, escape_start..self.offset())
+                ).note(Note::new("Byte escapes are written like \\x7F", true)));
+                0
+            }
+        }
+    }
+    /// Make the kind of a string literal token out of the result of 
+    /// `string_contents` or `byte_string_contents`
+    fn string_token<T, F>(&self, contents: Option<T>, what: &str, kind: F) -> TokenKind<'s>
+        where T: AsRef<[u8]>, F: FnOnce(T) -> TokenKind<'s>
+    {
+        let max = self.config.limits.max_string_length;
+        match contents {
+            Some(contents) if contents.as_ref().len() > max => TokenKind::Error(format!(
+                "{what} literal is too long (maximum length is {max} bytes)"
+            )),
+            Some(contents) => kind(contents),
+            None => TokenKind::Error(format!("unclosed {what} literal")),
+        }
+    }
+    /// Parse the rest of an escape sequence in a string or character literal 
+    /// after the backslash
+    fn escape(&mut self) -> char {
+        // Position of the backslash, so diagnostics cover the whole escape
+        let escape_start = self.offset() - 1;
+        match self.iter.next() {
+            Some('n')  => '\n',
+            Some('t')  => '\t',
+            Some('0')  => '\0',
+            Some('r')  => '\r',
+            Some('\\') => '\\',
+            Some('\"') => '\"',
+            Some('\'') => '\'',
+            Some('u')  => self.unicode_escape(escape_start),
+            Some(c) => {
+                self.logger.lock().unwrap().log(Message::new(
+                    Level::Warning,
+                    format!("Invalid escape sequence '\\{c}'"),
+                    Span(self.src, escape_start..self.offset())
+                ));
+                c
+            }
+            None => {
+                self.logger.lock().unwrap().log(Message::new(
+                    Level::Warning,
+                    "Expected escape sequence",
+                    Span(self.src, escape_start..self.offset())
+                ));
+                '\\'
+            }
+        }
+    }
+    /// Parse the rest of a `\u{...}` escape after the `\u`. On error, the 
+    /// diagnostic spans from `escape_start` to the end of the escape and the 
+    /// replacement character is returned
+    fn unicode_escape(&mut self, escape_start: usize) -> char {
+        let error = |this: &Self, info: String| {
+            this.logger.lock().unwrap().log(Message::new(
+                Level::Error,
+                info,
+                Span(this.src, escape_start..this.offset())
+            ).note(Note::new("Unicode escapes are written like \\u{1F600}", true)));
+            char::REPLACEMENT_CHARACTER
+        };
+        if self.iter.peek() != Some('{') {
+            return error(self, String::from("Expected '{' after '\\u'"));
+        }
+        self.iter.next();
+        let mut digits = String::new();
+        while let Some(c) = self.iter.peek().filter(char::is_ascii_hexdigit) {
+            digits.push(c);
+            self.iter.next();
+        }
+        // Don't eat the next character if the brace is missing, since it 
+        // may be the closing quote of the string
+        if self.iter.peek() != Some('}') {
+            return error(self, String::from("Unclosed unicode escape, expected '}'"));
+        }
+        self.iter.next();
+        if digits.is_empty() || digits.len() > 6 {
+            return error(self, String::from("Unicode escape must have between 1 and 6 hex digits"));
+        }
+        match u32::from_str_radix(&digits, 16).ok().and_then(char::from_u32) {
+            Some(c) => c,
+            None => error(self, format!("Invalid unicode code point U+{digits}")),
+        }
     }
 }
 
@@ -221,6 +586,10 @@ impl<'s> Iterator for Tokenizer<'s> {
             };
         }
 
+        if self.too_large {
+            return None;
+        }
+
         // Skip whitespace & check for EOF
         self.skip_ws();
         self.iter.peek()?;
@@ -238,7 +607,7 @@ impl<'s> Iterator for Tokenizer<'s> {
             ($kind: expr) => { {
                 let end = self.offset();
                 let raw = &self.iter.src_str()[start..end];
-                Some(Token { kind: $kind, raw, span: Span(self.src, start..end) })
+                Some(Token { kind: $kind, raw: Cow::Borrowed(raw), span: Span(self.src, start..end), hygiene: 0 })
             } };
         }
 
@@ -246,13 +615,25 @@ impl<'s> Iterator for Tokenizer<'s> {
         if parse!(next is_xid_start) {
             parse!(next_while is_xid_continue);
             let raw = raw!();
-            if STRICT_KEYWORDS.contains(&raw) {
-                return make_token!(TokenKind::Keyword);
+            // Raw identifier like `r#match`, which is never a keyword. The 
+            // prefix is kept in the raw text so the parser doesn't match it 
+            // as one, see `Token::text`
+            if raw == "r" && parse!(next '#', is_xid_start) {
+                parse!(next_while is_xid_continue);
+                return make_token!(TokenKind::Ident);
             }
-            if RESERVED_KEYWORDS.contains(&raw) {
-                return make_token!(TokenKind::Error(format!("reserved keyword '{raw}'")));
+            // Byte string like `b"GIF89a"`. Only ASCII characters are 
+            // allowed, other bytes are written with `\x` escapes
+            if raw == "b" && parse!(next '"') {
+                let contents = self.byte_string_contents();
+                return make_token!(self.string_token(contents, "byte string", TokenKind::Bytes));
             }
-            return make_token!(TokenKind::Ident);
+            return match self.config.keyword_kind(raw) {
+                Some(KeywordKind::Strict) => make_token!(TokenKind::Keyword),
+                Some(KeywordKind::Reserved) => make_token!(TokenKind::Reserved),
+                Some(KeywordKind::Contextual) => make_token!(TokenKind::Ident),
+                None => make_token!(TokenKind::Ident),
+            };
         }
 
         // Number
@@ -262,74 +643,97 @@ impl<'s> Iterator for Tokenizer<'s> {
 
             // If there's a .[0-9]+, then it's a float, 
             // otherwise it should be parsed as a member access like 0.abc
-            if parse!(next '.', is_ascii_digit) {
+            let is_float = parse!(next '.', is_ascii_digit);
+            if is_float {
                 parse!(next_while is_ascii_digit);
-                return match raw!().parse::<f64>() {
+            }
+            let digits = raw!().to_string();
+
+            // A suffix gives the literal an explicit type, like `10f` for a 
+            // float or `2i` for an int
+            parse!(next_while is_xid_continue);
+            let suffix = &raw!()[digits.len()..];
+            return match (suffix, is_float) {
+                ("f", _) | ("", true) => match digits.parse::<f64>() {
                     Ok(num) => make_token!(TokenKind::Float(num)),
                     Err(e) => make_token!(TokenKind::Error(format!("invalid float ({e}"))),
-                };
-            }
-            else {
-                return match raw!().parse::<i64>() {
+                },
+                ("i", false) | ("", false) => match digits.parse::<i64>() {
                     Ok(num) => make_token!(TokenKind::Int(num)),
                     Err(e) => make_token!(TokenKind::Error(format!("invalid integer ({e}"))),
-                };
-            }
+                },
+                ("i", true) => make_token!(TokenKind::Error(
+                    "a number with a fractional part can't be an int".to_string()
+                )),
+                (suffix, _) => make_token!(TokenKind::Error(format!(
+                    "invalid suffix '{suffix}' on number, expected 'i' for int or 'f' for float"
+                ))),
+            };
         }
 
         // String
         if parse!(next '"') {
-            let mut escaped = String::new();
-            while match self.iter.next() {
-                Some('"') => {
-                    false
-                }
-                Some(c) => {
-                    escaped.push(match c {
-                        '\\' => match self.iter.next() {
-                            Some('n')  => '\n',
-                            Some('t')  => '\t',
-                            Some('0')  => '\0',
-                            Some('r')  => '\r',
-                            Some('\\') => '\\',
-                            Some('\"') => '\"',
-                            Some('\'') => '\'',
-                            Some(c) => {
-                                self.logger.lock().unwrap().log(Message::new(
-                                    Level::Warning,
-                                    format!("Invalid escape sequence '\\{c}'"),
-                                    Span(self.src, self.offset() - 1..self.offset())
-                                ));
-                                c
-                            }
-                            None => {
-                                self.logger.lock().unwrap().log(Message::new(
-                                    Level::Warning,
-                                    "Expected escape sequence",
-                                    Span(self.src, self.offset() - 1..self.offset())
-                                ));
-                                '\\'
-                            }
-                        },
-                        o => o
-                    });
-                    true
+            let contents = self.string_contents();
+            return make_token!(self.string_token(contents, "string", TokenKind::String));
+        }
+
+        // Character or label. A quote followed by a name is a label unless 
+        // the name is a single character closed by another quote
+        if parse!(next '\'') {
+            // A literal with the wrong number of characters is still a 
+            // character, so the parser can carry on past it
+            macro_rules! invalid_char {
+                ($info: literal) => { {
+                    self.logger.lock().unwrap().log(Message::new(
+                        Level::Error, $info, Span(self.src, start..self.offset())
+                    ));
+                    return make_token!(TokenKind::Char(char::REPLACEMENT_CHARACTER));
+                } };
+            }
+            if parse!(peek is_xid_start) && self.iter.peek1() != Some('\'') {
+                parse!(next_while is_xid_continue);
+                if parse!(next '\'') {
+                    invalid_char!("Character literal may only contain one character");
                 }
+                return make_token!(TokenKind::Label);
+            }
+            let value = match self.iter.next() {
+                Some('\'') => invalid_char!("Empty character literal"),
+                Some('\\') => self.escape(),
+                Some(c) => c,
                 None => {
-                    return make_token!(TokenKind::Error("unclosed string literal".to_string()));
+                    return make_token!(TokenKind::Error("unclosed character literal".to_string()));
+                }
+            };
+            if !parse!(next '\'') {
+                // Skip to the closing quote so the rest isn't tokenized as garbage
+                while self.iter.peek().is_some_and(|c| c != '\'' && c != '\n') {
+                    self.iter.next();
+                }
+                if parse!(next '\'') {
+                    invalid_char!("Character literal may only contain one character");
                 }
-            } {}
-            return make_token!(TokenKind::String(escaped))
+                return make_token!(TokenKind::Error("unclosed character literal".to_string()));
+            }
+            return make_token!(TokenKind::Char(value))
         }
 
         // Punctuation
         if
-            // Chained
-            parse!(next_while '.') || parse!(next_while ':') ||
+            // Chained, plus the inclusive range operator `..=`
+            (parse!(next_while '.') && {
+                if raw!() == ".." && parse!(peek '=') {
+                    self.iter.next();
+                }
+                true
+            }) ||
+            parse!(next_while ':') ||
             // Single
-            parse!(next ',' | ';' | '@') ||
+            parse!(next ',' | ';' | '@' | '$') ||
             // Arrows
             parse!(next '-' | '=', '>') ||
+            // Null-safe member access
+            parse!(next '?', '.') ||
             // Operator
             parse!(next_while is_op_char)
         {
@@ -337,50 +741,50 @@ impl<'s> Iterator for Tokenizer<'s> {
         }
 
         // Parentheses
-        let opening = self.iter.peek().unwrap();
-        if parse!(next '(' | '[' | '{') {
-            let mut items = vec![];
-            'find_closing: loop {
-                // skip whitespace
-                self.skip_ws();
-                match self.iter.peek() {
-                    Some(c @ (')' | ']' | '}')) if c == closing_paren(opening) => {
-                        self.iter.next();
-                        break 'find_closing;
-                    },
-                    Some(_) => {}
-                    None => return make_token!(TokenKind::Error("unclosed parenthesis".to_string())),
-                }
-                items.push(self.next().unwrap());
-            }
-            let tree = TokenTree {
-                src: self.src,
-                items: items.into_iter(),
-                start_offset: start,
-                eof: self.offset() - 1..self.offset(),
-                logger: self.logger.clone(),
-            };
-            return make_token!(match opening {
-                '(' => TokenKind::Parentheses(tree),
-                '[' => TokenKind::Brackets(tree),
-                '{' => TokenKind::Braces(tree),
-                _ => unreachable!(),
-            });
+        if parse!(peek '(' | '[' | '{') {
+            return Some(self.token_tree());
         }
 
-        let c = self.iter.next().unwrap();
+        // Coalesce consecutive invalid characters into a single error token. 
+        // Stray closing brackets are not coalesced since an enclosing 
+        // bracket tree might be looking for one
+        let c = self.iter.next().ice("no character after checking for end of file");
+        if c.is_invalid_char() && parse!(next_while is_invalid_char) {
+            return make_token!(TokenKind::Error(format!("invalid characters '{}'", raw!())));
+        }
         make_token!(TokenKind::Error(format!("invalid character '{c}'")))
     }
 }
 
+#[derive(Clone)]
 pub struct TokenTree<'s> {
     src: &'s Src,
     items: std::vec::IntoIter<Token<'s>>,
     start_offset: usize,
     eof: Range<usize>,
+    config: Arc<LanguageConfig>,
     logger: LoggerRef,
 }
 
+impl<'s> TokenTree<'s> {
+    pub(crate) fn items(&self) -> &[Token<'s>] {
+        self.items.as_slice()
+    }
+    /// Create a tree with the same delimiters as this one but different 
+    /// contents
+    pub(crate) fn with_items(&self, items: Vec<Token<'s>>) -> Self {
+        Self { items: items.into_iter(), ..self.clone() }
+    }
+    /// Move this tree to `span`, so that reaching its end is reported there
+    pub(crate) fn moved_to(self, span: &Span<'s>) -> Self {
+        Self {
+            start_offset: span.1.start,
+            eof: span.1.end.saturating_sub(1)..span.1.end,
+            ..self
+        }
+    }
+}
+
 impl<'s> Iterator for TokenTree<'s> {
     type Item = Token<'s>;
     fn next(&mut self) -> Option<Self::Item> {
@@ -389,7 +793,7 @@ impl<'s> Iterator for TokenTree<'s> {
 }
 
 enum TokenIterSrc<'s> {
-    Tokenizer(Tokenizer<'s>),
+    Expanded(std::vec::IntoIter<Token<'s>>),
     Tree(TokenTree<'s>),
 }
 
@@ -397,7 +801,7 @@ impl<'s> Iterator for TokenIterSrc<'s> {
     type Item = Token<'s>;
     fn next(&mut self) -> Option<Self::Item> {
         match self {
-            Self::Tokenizer(t) => t.next(),
+            Self::Expanded(t) => t.next(),
             Self::Tree(t) => t.next(),
         }
     }
@@ -409,8 +813,53 @@ pub struct TokenIterator<'s> {
     peek: [Option<Token<'s>>; MAX_PEEK_COUNT],
     start_of_last_token: usize,
     last_was_braced: bool,
+    /// Whether braces after a call are a trailing block argument, see 
+    /// `BeforeBlock`
+    allow_trailing_blocks: bool,
+    /// Whether `|` is the bitwise or operator, see `WithoutBitOr`
+    allow_bit_or: bool,
+    /// Keyword that the identifier at the given range of the current 
+    /// statement is likely a typo of, see `set_keyword_typo`
+    keyword_typo: Option<(String, Range<usize>)>,
     eof: Option<Range<usize>>,
+    config: Arc<LanguageConfig>,
     logger: LoggerRef,
+    /// How many rules and chain links are currently being parsed inside 
+    /// each other, see `enter_chain_link`
+    depth: usize,
+    /// Output of match debug logs, or None if match debug logging is 
+    /// disabled
+    debug_log: Option<MatchLogRef>,
+    /// Current indentation of match debug logs
+    debug_log_indent: usize,
+    profiler: Option<ProfilerRef>,
+    cancel: Option<CancellationToken>,
+}
+
+/// Get the next token that isn't an error token. Error tokens are treated as 
+/// trivia by the parser: they are reported here and then skipped, so that 
+/// parsing can recover from them
+fn next_non_error<'s>(iter: &mut TokenIterSrc<'s>, logger: &LoggerRef) -> Option<Token<'s>> {
+    loop {
+        let token = iter.next()?;
+        let msg = match token.kind {
+            TokenKind::Error(ref err) => {
+                let mut chars = err.chars();
+                let msg = chars.next().map(|c| c.to_uppercase().chain(chars).collect::<String>());
+                Message::new(Level::Error, msg.unwrap_or_default(), token.span)
+            }
+            TokenKind::Reserved => {
+                let raw = &token.raw;
+                let mut msg = Message::new(Level::Error, format!("'{raw}' is reserved for future use"), token.span);
+                if let Some(edition) = Edition::reserving_edition(raw) {
+                    msg = msg.note(Note::new(format!("'{raw}' has been reserved since edition {edition}"), false));
+                }
+                msg.note(Note::new(format!("Write r#{raw} to use it as a name"), true))
+            }
+            _ => return Some(token),
+        };
+        logger.lock().unwrap().log(msg);
+    }
 }
 
 impl<'s> TokenIterator<'s> {
@@ -418,14 +867,126 @@ impl<'s> TokenIterator<'s> {
         src: &'s Src,
         start_offset: usize,
         eof: Option<Range<usize>>,
+        config: Arc<LanguageConfig>,
         logger: LoggerRef,
         mut iter: TokenIterSrc<'s>,
     ) -> Self {
-        let peek = core::array::from_fn(|_| iter.next());
+        let peek = core::array::from_fn(|_| next_non_error(&mut iter, &logger));
         Self {
-            src, logger, iter, peek,
+            src, config, logger, iter, peek,
             start_of_last_token: start_offset, eof,
             last_was_braced: false,
+            allow_trailing_blocks: true,
+            allow_bit_or: true,
+            keyword_typo: None,
+            depth: 0,
+            debug_log: None,
+            debug_log_indent: 0,
+            profiler: None,
+            cancel: None,
+        }
+    }
+    /// Create an iterator for parsing the contents of a token tree. The 
+    /// subtree inherits the nesting depth and debug logging state of this 
+    /// iterator, and reports to the same profiler. Trailing blocks and the 
+    /// bitwise or operator are always allowed inside the subtree
+    pub(crate) fn subtree(&self, tree: TokenTree<'s>) -> TokenIterator<'s> {
+        let mut iter = TokenIterator::from(tree);
+        iter.depth = self.depth;
+        iter.debug_log = self.debug_log.clone();
+        iter.debug_log_indent = self.debug_log_indent;
+        iter.profiler = self.profiler.clone();
+        iter.cancel = self.cancel.clone();
+        iter
+    }
+    /// Write every rule the parser attempts to match to `log`, or disable 
+    /// match debug logging if `None`
+    pub fn set_debug_log_matches(&mut self, log: Option<MatchLogRef>) {
+        self.debug_log = log;
+        self.debug_log_indent = 0;
+    }
+    /// Record every rule the parser attempts to match in a profiler
+    pub fn set_profiler(&mut self, profiler: Option<ProfilerRef>) {
+        self.profiler = profiler;
+    }
+    /// Stop parsing as soon as `cancel` is cancelled
+    pub fn set_cancellation_token(&mut self, cancel: Option<CancellationToken>) {
+        self.cancel = cancel;
+    }
+    fn debug_log_pos(&self) -> String {
+        match self.peek(0) {
+            Some(token) => format!("{token} at {}", token.span),
+            None => format!("{} at {}", self.eof_name(), self.eof_span()),
+        }
+    }
+    /// Write a line to the match debug log. Like diagnostics, a trace that 
+    /// can't be written can't be reported anywhere, so write errors are 
+    /// ignored
+    fn debug_log(&self, line: std::fmt::Arguments) {
+        if let Some(ref log) = self.debug_log {
+            let mut log = log.lock().unwrap();
+            let _ = writeln!(log, "{:indent$}{line}", "", indent = self.debug_log_indent);
+        }
+    }
+    /// Called by generated parsers when they start parsing a rule. Fails if 
+    /// the maximum nesting depth has been reached, or silently if parsing 
+    /// has been cancelled
+    pub(crate) fn enter_rule(&mut self, rule: &'static str) -> Result<(), FatalParseError> {
+        if self.cancel.as_ref().is_some_and(|c| c.is_cancelled()) {
+            return Err(FatalParseError);
+        }
+        self.enter_depth()?;
+        if let Some(ref profiler) = self.profiler {
+            profiler.lock().unwrap().enter(rule);
+        }
+        if self.debug_log.is_some() {
+            self.debug_log(format_args!("> {rule} ({})", self.debug_log_pos()));
+            self.debug_log_indent += 2;
+        }
+        Ok(())
+    }
+    /// Go one level deeper, failing if the maximum nesting depth has been 
+    /// reached
+    fn enter_depth(&mut self) -> Result<(), FatalParseError> {
+        let max_depth = self.config.limits.max_nesting_depth;
+        if self.depth >= max_depth {
+            let span = self.peek(0).map(|t| t.span.clone()).unwrap_or(self.eof_span());
+            self.logger.lock().unwrap().log(Message::new(
+                Level::Error,
+                format!("Maximum nesting depth ({max_depth}) exceeded"),
+                span
+            ).note(Note::new("Try splitting this expression into smaller parts", true)));
+            return Err(FatalParseError);
+        }
+        self.depth += 1;
+        Ok(())
+    }
+    /// Called by parsers of chains like `a + b + c`, `a.b().c` or `int??` 
+    /// before parsing each link after the first. Chains are parsed in a 
+    /// loop, but every link nests the chain one level deeper in the AST, 
+    /// which is then checked recursively, so links count toward the nesting 
+    /// depth like rules do until `exit_chain` is called
+    pub(crate) fn enter_chain_link(&mut self) -> Result<(), FatalParseError> {
+        self.enter_depth()
+    }
+    /// Called by parsers of chains when the chain ends, whether parsing it 
+    /// succeeded or not, with the number of links entered
+    pub(crate) fn exit_chain(&mut self, links: usize) {
+        self.depth -= links;
+    }
+    /// Called by generated parsers when they finish parsing a rule
+    pub(crate) fn exit_rule(&mut self, rule: &str, matched: bool) {
+        self.depth -= 1;
+        if let Some(ref profiler) = self.profiler {
+            profiler.lock().unwrap().exit(matched);
+        }
+        if self.debug_log.is_some() {
+            self.debug_log_indent = self.debug_log_indent.saturating_sub(2);
+            self.debug_log(format_args!(
+                "< {rule} {} ({})",
+                if matched { "matched" } else { "failed" },
+                self.debug_log_pos()
+            ));
         }
     }
     pub fn peek(&self, n: usize) -> Option<&Token<'s>> {
@@ -434,12 +995,42 @@ impl<'s> TokenIterator<'s> {
     pub(crate) fn last_was_braced(&self) -> bool {
         self.last_was_braced
     }
+    /// End of the last token that was consumed
+    pub(crate) fn last_token_end(&self) -> usize {
+        self.start_of_last_token
+    }
+    /// Record that the statement being parsed starts with an identifier 
+    /// at `range` that is likely a typo of `keyword`, so the error about the 
+    /// statement not ending can point it out
+    pub(crate) fn set_keyword_typo(&mut self, keyword: String, range: Range<usize>) {
+        self.keyword_typo = Some((keyword, range));
+    }
+    /// Take the typo given by `set_keyword_typo`, if any
+    pub(crate) fn take_keyword_typo(&mut self) -> Option<(String, Span<'s>)> {
+        self.keyword_typo.take().map(|(keyword, range)| (keyword, Span(self.src, range)))
+    }
+    pub(crate) fn allow_trailing_blocks(&self) -> bool {
+        self.allow_trailing_blocks
+    }
+    /// Set whether braces after a call are a trailing block argument, 
+    /// returning the previous setting
+    pub(crate) fn set_allow_trailing_blocks(&mut self, allow: bool) -> bool {
+        std::mem::replace(&mut self.allow_trailing_blocks, allow)
+    }
+    pub(crate) fn allow_bit_or(&self) -> bool {
+        self.allow_bit_or
+    }
+    /// Set whether `|` is the bitwise or operator, returning the previous 
+    /// setting
+    pub(crate) fn set_allow_bit_or(&mut self, allow: bool) -> bool {
+        std::mem::replace(&mut self.allow_bit_or, allow)
+    }
     fn eof_span(&self) -> Span<'s> {
         if let Some(r) = self.eof.clone() {
             Span(self.src, r)
         }
         else {
-            Span(self.src, self.start_of_last_token - 1..self.start_of_last_token)
+            Span(self.src, self.start_of_last_token.saturating_sub(1)..self.start_of_last_token)
         }
     }
     fn eof_name(&self) -> String {
@@ -450,21 +1041,37 @@ impl<'s> TokenIterator<'s> {
     pub(crate) fn logger(&self) -> LoggerRef {
         self.logger.clone()
     }
+    /// The language settings the source is being parsed with
+    pub fn config(&self) -> &LanguageConfig {
+        &self.config
+    }
+    /// The edition the source is being parsed with, for gating syntax changes
+    pub fn edition(&self) -> Edition {
+        self.config.edition
+    }
     pub fn error<S: Display>(&mut self, msg: S) {
-        if let Some(token) = self.next() {
-            self.logger.lock().unwrap().log(Message::new(Level::Error, msg, token.span));
-        }
-        else {
-            self.logger.lock().unwrap().log(Message::new(Level::Error, msg, self.eof_span()))
-        }
+        self.error_with_notes(msg, vec![]);
+    }
+    fn error_with_notes<S: Display>(&mut self, msg: S, notes: Vec<Note<'s>>) {
+        let span = match self.next() {
+            Some(token) => token.span,
+            None => self.eof_span(),
+        };
+        let msg = notes.into_iter().fold(Message::new(Level::Error, msg, span), Message::note);
+        self.logger.lock().unwrap().log(msg);
     }
     pub fn expected<S: Display>(&mut self, expected: S) {
-        self.error(if let Some(token) = &self.peek(0) {
+        self.expected_with_notes(expected, vec![]);
+    }
+    /// Like `expected`, with notes that explain what may have gone wrong
+    pub fn expected_with_notes<S: Display>(&mut self, expected: S, notes: Vec<Note<'s>>) {
+        let msg = if let Some(token) = &self.peek(0) {
             format!("Expected {expected}, got {token}")
         }
         else {
             format!("Expected {expected}, got {}", self.eof_name())
-        })
+        };
+        self.error_with_notes(msg, notes);
     }
     pub fn expected_eof(&mut self) {
         self.expected(self.eof_name())
@@ -477,6 +1084,7 @@ impl<'s> TokenIterator<'s> {
             items: vec![].into_iter(),
             start_offset: 0,
             eof: 0..0,
+            config: self.config.clone(),
             logger: self.logger.clone()
         }
     }
@@ -485,25 +1093,30 @@ impl<'s> TokenIterator<'s> {
 impl<'s> Iterator for TokenIterator<'s> {
     type Item = Token<'s>;
     fn next(&mut self) -> Option<Self::Item> {
-        let next = self.iter.next();
+        let next = next_non_error(&mut self.iter, &self.logger);
         if let Some(peek) = self.peek(0) {
             (self.last_was_braced, self.start_of_last_token) = (
                 matches!(peek.kind, TokenKind::Braces(_)), peek.span.1.end
             );
         }
         self.peek.rotate_left(1);
-        std::mem::replace(self.peek.last_mut().unwrap(), next)
+        std::mem::replace(&mut self.peek[MAX_PEEK_COUNT - 1], next)
     }
 }
 
 impl<'s> From<Tokenizer<'s>> for TokenIterator<'s> {
-    fn from(value: Tokenizer<'s>) -> Self {
+    /// Macros are expanded before parsing, which requires the whole source 
+    /// to be tokenized up front
+    fn from(mut value: Tokenizer<'s>) -> Self {
+        let start_offset = value.offset();
+        let tokens = Expander::new(value.config.clone(), value.logger.clone()).expand(value.by_ref().collect());
         Self::new(
             value.src,
-            value.offset(),
+            start_offset,
             None,
+            value.config.clone(),
             value.logger.clone(),
-            TokenIterSrc::Tokenizer(value)
+            TokenIterSrc::Expanded(tokens.into_iter())
         )
     }
 }
@@ -514,6 +1127,7 @@ impl<'s> From<TokenTree<'s>> for TokenIterator<'s> {
             value.src,
             value.start_offset,
             Some(value.eof.clone()),
+            value.config.clone(),
             value.logger.clone(),
             TokenIterSrc::Tree(value)
         )
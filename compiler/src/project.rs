@@ -0,0 +1,108 @@
+
+use std::{collections::HashMap, path::{Path, PathBuf}, fs, str::FromStr};
+use serde::Deserialize;
+use crate::{parser::config::{Edition, LanguageConfig, Limits}, shared::src::SrcPool};
+
+pub use crate::checker::lint::LintLevel;
+
+/// Name of the file that marks the root directory of a project
+pub const MANIFEST_FILE_NAME: &str = "gemscript.toml";
+
+/// The `[project]` table of a manifest
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProjectInfo {
+    pub name: String,
+    pub version: String,
+    /// Edition the project is written in. Defaults to the latest one
+    #[serde(default)]
+    pub edition: Edition,
+    /// Directories containing the project's source files, relative to the 
+    /// project root
+    #[serde(default = "default_sources")]
+    pub sources: Vec<PathBuf>,
+    /// Platform the project is compiled for, if it targets a specific one
+    pub target: Option<String>,
+    /// Directory of a standard library to load before the project's own 
+    /// sources, relative to the project root
+    pub std: Option<PathBuf>,
+}
+
+fn default_sources() -> Vec<PathBuf> {
+    vec![PathBuf::from("src")]
+}
+
+/// Contents of a `gemscript.toml` file
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    pub project: ProjectInfo,
+    /// Overrides for the levels of individual lints, by lint name
+    #[serde(default)]
+    pub lints: HashMap<String, LintLevel>,
+    /// Overrides for the limits on the input, see `Limits`
+    #[serde(default)]
+    pub limits: Limits,
+}
+
+impl FromStr for Manifest {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        toml::from_str(s).map_err(|e| e.message().to_string())
+    }
+}
+
+/// A project on disk, described by a manifest in its root directory
+#[derive(Debug, Clone)]
+pub struct Project {
+    root: PathBuf,
+    manifest: Manifest,
+}
+
+impl Project {
+    /// Load the project whose manifest is in `root`
+    pub fn load(root: &Path) -> Result<Self, String> {
+        let path = root.join(MANIFEST_FILE_NAME);
+        let data = fs::read_to_string(&path)
+            .map_err(|e| format!("Unable to read {}: {e}", path.display()))?;
+        let manifest = data.parse::<Manifest>()
+            .map_err(|e| format!("Invalid manifest {}: {e}", path.display()))?;
+        Ok(Self { root: root.to_path_buf(), manifest })
+    }
+    /// Find the project that `dir` is part of by looking for a manifest in 
+    /// it and its parent directories. Returns `None` if `dir` isn't inside 
+    /// a project
+    pub fn discover(dir: &Path) -> Result<Option<Self>, String> {
+        dir.ancestors()
+            .find(|d| d.join(MANIFEST_FILE_NAME).is_file())
+            .map(Self::load)
+            .transpose()
+    }
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+    pub fn manifest(&self) -> &Manifest {
+        &self.manifest
+    }
+    /// The language settings the project should be compiled with
+    pub fn language_config(&self) -> LanguageConfig {
+        let mut config = LanguageConfig::for_edition(self.manifest.project.edition);
+        config.lints = self.manifest.lints.clone();
+        config.limits = self.manifest.limits;
+        if let Some(ref target) = self.manifest.project.target {
+            config.cfg = config.cfg.with_value("target", target);
+        }
+        config
+    }
+    /// Load the source files of the project's standard library, if it has 
+    /// one
+    pub fn std_src_pool(&self) -> Option<Result<SrcPool, String>> {
+        self.manifest.project.std.as_ref().map(|std| SrcPool::new_from_dir(self.root.join(std)))
+    }
+    /// Load all of the project's source files
+    pub fn src_pool(&self) -> Result<SrcPool, String> {
+        SrcPool::new_from_dirs(
+            self.manifest.project.sources.iter().map(|s| self.root.join(s)).collect()
+        )
+    }
+}
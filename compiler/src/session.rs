@@ -0,0 +1,397 @@
+
+use std::{io, path::Path, sync::{Arc, Mutex}, time::Instant};
+
+use crate::{
+    index::{IndexedSymbol, SymbolIndex},
+    artifacts::{Artifact, ArtifactKind, Artifacts, EmitTarget},
+    ast::expr::ExprList,
+    checker::{
+        pool::{ASTPool, AST}, coherency::{Checker, ScopeID}, typed::TypedAst,
+        path::{FullIdentPath, Ident}, symbol::SymbolInfo
+    },
+    parser::{
+        config::LanguageConfig, parse::{NodePool, ParseRef}, profile::ProfilerRef,
+        tokenizer::{Tokenizer, TokenIterator, Token, MatchLogRef}
+    },
+    shared::{
+        logger::{LoggerRef, Message, Level}, src::{ArcSpan, Src, SrcPool, Span}, ice::catch_ice,
+        similar::fuzzy_score, timing::{Timings, TimingEntry, Phase},
+        events::{Event, EventLog},
+        cancel::{CancellationToken, Cancelled}
+    },
+    tokenize_pool, tokenize_in_order,
+};
+
+/// Receives updates on how far along a compilation is, for showing progress 
+/// bars or status messages. Every method does nothing by default
+pub trait ProgressHandler: Send {
+    /// Called when a phase starts, with the number of files it will process
+    fn on_phase_start(&mut self, _phase: Phase, _files: usize) {}
+    /// Called after each file of a phase has been processed. `n` is the 
+    /// number of files done so far, starting at 1
+    fn on_file_done(&mut self, _phase: Phase, _src: &Src, _n: usize, _total: usize) {}
+}
+
+/// Drives the compilation of a codebase: owns the node pool and the settings 
+/// shared by every phase, and records timings and progress as it goes
+/// 
+/// A bug in the compiler never aborts the process running a session. It is 
+/// reported as an internal compiler error instead, see `catch_ice`
+pub struct Session {
+    config: Arc<LanguageConfig>,
+    logger: LoggerRef,
+    pool: NodePool,
+    timings: Timings,
+    profiler: Option<ProfilerRef>,
+    progress: Option<Box<dyn ProgressHandler>>,
+    event_log: Option<EventLog>,
+    /// When the phase that is running started, for the event log
+    phase_started: Instant,
+    debug_log_matches: Option<MatchLogRef>,
+    deterministic: bool,
+    cancel: CancellationToken,
+    emit: Vec<ArtifactKind>,
+    emit_target: EmitTarget,
+    artifacts: Artifacts,
+    /// The checker of the last call to `check`, kept for looking up symbols
+    checker: Option<Checker>,
+    /// Scopes of the top level of the ASTs checked by the last call to 
+    /// `check`, from first to last, along with the names of their sources
+    checked_scopes: Vec<(ScopeID, String)>,
+}
+
+impl Session {
+    /// Create a session. The error limit of `config` is applied to `logger`
+    pub fn new(config: Arc<LanguageConfig>, logger: LoggerRef) -> Self {
+        logger.lock().unwrap().set_max_errors(config.limits.max_errors);
+        Self {
+            config, logger,
+            pool: NodePool::new(),
+            timings: Timings::new(),
+            profiler: None,
+            progress: None,
+            event_log: None,
+            phase_started: Instant::now(),
+            debug_log_matches: None,
+            deterministic: false,
+            cancel: CancellationToken::default(),
+            emit: Vec::new(),
+            emit_target: EmitTarget::default(),
+            artifacts: Artifacts::default(),
+            checker: None,
+            checked_scopes: Vec::new(),
+        }
+    }
+    pub fn set_progress_handler<P: ProgressHandler + 'static>(&mut self, handler: P) {
+        self.progress = Some(Box::from(handler));
+    }
+    /// Write an event to `out` as newline-delimited JSON whenever a phase 
+    /// starts or ends, a file is done and the session is finished. See `Event`
+    pub fn set_event_log<W: io::Write + Send + 'static>(&mut self, out: W) {
+        self.event_log = Some(EventLog::new(out));
+    }
+    /// Record every rule the parser attempts to match in a profiler
+    pub fn set_profiler(&mut self, profiler: Option<ProfilerRef>) {
+        self.profiler = profiler;
+    }
+    /// Write every rule the parser attempts to match to `out`, indented by 
+    /// nesting depth and with the position of the parser
+    pub fn set_debug_log_matches<W: io::Write + Send + 'static>(&mut self, out: W) {
+        self.debug_log_matches = Some(Arc::new(Mutex::new(out)));
+    }
+    /// Only use scheduling that gives the same diagnostics and artifacts, in 
+    /// the same order, every time the same sources are compiled. This 
+    /// currently means tokenizing on a single thread. Timings are still 
+    /// measured and naturally differ between runs
+    pub fn set_deterministic(&mut self, deterministic: bool) {
+        self.deterministic = deterministic;
+    }
+    /// Abort the phase that is running when `cancel` is cancelled. The phase 
+    /// then returns `Err(Cancelled)`
+    pub fn set_cancellation_token(&mut self, cancel: CancellationToken) {
+        self.cancel = cancel;
+    }
+    /// Emit artifacts of the given kinds as the phases that produce them 
+    /// are run. Tokens are only emitted by `tokenize`, and timings by `finish`
+    pub fn set_emit<I: IntoIterator<Item = ArtifactKind>>(&mut self, kinds: I, target: EmitTarget) {
+        self.emit = kinds.into_iter().collect();
+        self.emit_target = target;
+    }
+    /// Whether artifacts of `kind` are emitted, see `set_emit`
+    pub fn emits(&self, kind: ArtifactKind) -> bool {
+        self.emit.contains(&kind)
+    }
+    pub fn config(&self) -> Arc<LanguageConfig> {
+        self.config.clone()
+    }
+    pub fn logger(&self) -> LoggerRef {
+        self.logger.clone()
+    }
+    /// The pool all ASTs parsed in this session are allocated in
+    pub fn pool(&self) -> &NodePool {
+        &self.pool
+    }
+    /// How long each phase run in this session has taken so far
+    pub fn timings(&self) -> &Timings {
+        &self.timings
+    }
+
+    /// Emit the timings if requested and return every artifact emitted by 
+    /// this session
+    pub fn finish(&mut self) -> Artifacts {
+        if self.emits(ArtifactKind::Timings) {
+            self.emit_artifact(ArtifactKind::Timings, None, self.timings.to_string());
+        }
+        let (errors, warnings) = self.diagnostic_counts();
+        self.log_event(Event::Finish { errors, warnings });
+        std::mem::take(&mut self.artifacts)
+    }
+
+    fn emit_artifact(&mut self, kind: ArtifactKind, src: Option<&Src>, contents: String) {
+        let src = src.map(Src::name);
+        let (path, contents) = match self.emit_target {
+            EmitTarget::Memory => (None, Some(contents)),
+            EmitTarget::Dir(ref dir) => {
+                let file_name = match src {
+                    Some(ref src) => format!(
+                        "{}.{kind}",
+                        Path::new(src).file_name().map(|n| n.to_string_lossy()).unwrap_or_default()
+                    ),
+                    None => format!("{kind}.txt"),
+                };
+                let path = dir.join(file_name);
+                if let Err(e) = std::fs::create_dir_all(dir).and_then(|_| std::fs::write(&path, contents)) {
+                    self.logger.lock().unwrap().log(Message::new(
+                        Level::Error,
+                        format!("Unable to write {kind} to {}: {e}", path.display()),
+                        Span::builtin()
+                    ));
+                    return;
+                }
+                (Some(path), None)
+            }
+        };
+        self.artifacts.push(Artifact { kind, src, path, contents });
+    }
+
+    fn log_event(&mut self, event: Event) {
+        if let Some(ref mut log) = self.event_log {
+            log.log(&event);
+        }
+    }
+    fn diagnostic_counts(&self) -> (usize, usize) {
+        let logger = self.logger.lock().unwrap();
+        (logger.errors(), logger.warnings())
+    }
+    fn phase_start(&mut self, phase: Phase, files: usize) {
+        self.phase_started = Instant::now();
+        self.log_event(Event::PhaseStart { phase, files });
+        if let Some(ref mut progress) = self.progress {
+            progress.on_phase_start(phase, files);
+        }
+    }
+    fn file_done(&mut self, phase: Phase, src: &Src, n: usize, total: usize) {
+        if self.event_log.is_some() {
+            self.log_event(Event::FileDone { phase, file: src.name(), n, total });
+        }
+        if let Some(ref mut progress) = self.progress {
+            progress.on_file_done(phase, src, n, total);
+        }
+    }
+    fn phase_end(&mut self, phase: Phase) {
+        if self.event_log.is_none() {
+            return;
+        }
+        let duration_ms = self.phase_started.elapsed().as_secs_f64() * 1000.0;
+        let (errors, warnings) = self.diagnostic_counts();
+        self.log_event(Event::PhaseEnd { phase, duration_ms, errors, warnings });
+    }
+
+    /// Tokenize sources on their own. Normally tokens are produced on demand 
+    /// while parsing, so this is only needed for inspecting them
+    pub fn tokenize<'s>(&mut self, srcs: &'s SrcPool) -> Result<Vec<Vec<Token<'s>>>, Cancelled> {
+        self.phase_start(Phase::Lex, srcs.srcs().len());
+        let tokenize = if self.deterministic { tokenize_in_order } else { tokenize_pool };
+        let tokens = self.timings.time(
+            Phase::Lex, None,
+            || catch_ice(
+                Phase::Lex, Span::builtin(), &self.logger,
+                || tokenize(srcs.srcs(), self.config.clone(), &self.cancel, self.logger.clone())
+            )
+        ).unwrap_or_else(|| srcs.srcs().iter().map(|_| vec![]).collect());
+        self.cancel.check()?;
+        // Tokenizing is done in parallel, so there is no sensible per-file 
+        // progress to report until it is finished
+        for (i, src) in srcs.iter().enumerate() {
+            self.file_done(Phase::Lex, &src, i + 1, srcs.srcs().len());
+        }
+        if self.emits(ArtifactKind::Tokens) {
+            for (src, tokens) in srcs.iter().zip(&tokens) {
+                let dump = tokens.iter().map(|t| format!("{t:?}\n")).collect();
+                self.emit_artifact(ArtifactKind::Tokens, Some(&src), dump);
+            }
+        }
+        self.phase_end(Phase::Lex);
+        Ok(tokens)
+    }
+
+    /// Parse every source in a pool. Sources that fail to parse are left out 
+    /// of the result
+    pub fn parse(&mut self, srcs: &SrcPool) -> Result<ASTPool, Cancelled> {
+        let total = srcs.srcs().len();
+        self.phase_start(Phase::Parse, total);
+        let mut asts = Vec::new();
+        for (i, src) in srcs.iter().enumerate() {
+            self.cancel.check()?;
+            let start = Instant::now();
+            let start_nodes = self.pool.len();
+            let mut tokenizer = TokenIterator::from(Tokenizer::new(&src, self.config.clone(), self.logger.clone()));
+            tokenizer.set_debug_log_matches(self.debug_log_matches.clone());
+            tokenizer.set_profiler(self.profiler.clone());
+            tokenizer.set_cancellation_token(Some(self.cancel.clone()));
+            let ast = catch_ice(
+                Phase::Parse, Span(&src, 0..0), &self.logger,
+                || ExprList::parse_complete(&mut self.pool, src.clone(), tokenizer).ok()
+            ).flatten();
+            self.timings.record(TimingEntry {
+                phase: Phase::Parse,
+                file: Some(src.name()),
+                duration: start.elapsed(),
+                nodes: self.pool.len() - start_nodes,
+            });
+            // A file that was cut short by cancellation must not be reported 
+            // as done
+            self.cancel.check()?;
+            self.file_done(Phase::Parse, &src, i + 1, total);
+            if let Some(ast) = ast {
+                if self.emits(ArtifactKind::Ast) {
+                    let dump = self.pool.dump(&ast);
+                    self.emit_artifact(ArtifactKind::Ast, Some(&src), dump);
+                }
+                asts.push(ast);
+            }
+        }
+        self.phase_end(Phase::Parse);
+        Ok(ASTPool::new(asts))
+    }
+
+    /// Check ASTs after a prelude, such as a standard library, returning the 
+    /// inferred types of the ASTs in `asts`. See 
+    /// `Checker::try_resolve_with_prelude`. After an internal compiler 
+    /// error, the remaining ASTs aren't checked and are left out of the result
+    pub fn check(&mut self, prelude: &mut [AST], asts: &mut [AST]) -> Result<Vec<TypedAst>, Cancelled> {
+        self.checker = None;
+        self.checked_scopes.clear();
+        let total = prelude.len() + asts.len();
+        self.phase_start(Phase::Check, total);
+        let mut checker = Checker::new(self.config.clone(), self.logger.clone());
+        checker.set_cancellation_token(self.cancel.clone());
+        let mut n = 0;
+        let mut scopes = Vec::new();
+        for ast in prelude {
+            let start = Instant::now();
+            let at = self.file_start(ast);
+            let checked = catch_ice(
+                Phase::Check, at.as_ref(), &self.logger,
+                || checker.resolve_prelude(ast, &mut self.pool)
+            );
+            // The checker may be left in an inconsistent state, so nothing 
+            // is checked after an internal compiler error
+            if checked.is_none() {
+                self.phase_end(Phase::Check);
+                return Ok(vec![]);
+            }
+            scopes.extend(self.checked_scope(ast));
+            self.cancel.check()?;
+            n += 1;
+            self.check_done(ast, start, n, total);
+        }
+        let mut tys = Vec::new();
+        for ast in asts {
+            let start = Instant::now();
+            let at = self.file_start(ast);
+            let Some(ty) = catch_ice(
+                Phase::Check, at.as_ref(), &self.logger,
+                || checker.resolve_to_completion(ast, &mut self.pool)
+            ) else {
+                self.phase_end(Phase::Check);
+                return Ok(tys);
+            };
+            tys.push(TypedAst::new(ast, ty, &self.pool));
+            scopes.extend(self.checked_scope(ast));
+            self.cancel.check()?;
+            n += 1;
+            self.check_done(ast, start, n, total);
+        }
+        self.checker = Some(checker);
+        self.checked_scopes = scopes;
+        self.phase_end(Phase::Check);
+        Ok(tys)
+    }
+    /// Where internal compiler errors are reported if the node that caused 
+    /// them isn't known
+    fn file_start(&self, ast: &AST) -> ArcSpan {
+        self.pool.span_of(ast).map(|s| ArcSpan(s.0, 0..0)).unwrap_or_default()
+    }
+    fn checked_scope(&self, ast: &AST) -> Option<(ScopeID, String)> {
+        Some((ast.get(&self.pool).scope()?, self.pool.span_of(ast)?.0.name()))
+    }
+    /// Look up the item declared with the full name `path` by the ASTs 
+    /// checked last. Items declared in later ASTs take precedence. Variables 
+    /// only exist while their scope is being checked, so they aren't found
+    pub fn symbol_info(&self, path: &FullIdentPath) -> Option<SymbolInfo> {
+        let checker = self.checker.as_ref()?;
+        self.checked_scopes.iter().rev()
+            .flat_map(|(scope, _)| checker.scopes_from(*scope))
+            .find_map(|s| s.entities().get(path).map(|e| SymbolInfo::new(path, e)))
+    }
+    /// Search the items declared at the top level of the ASTs checked last 
+    /// for ones whose name fuzzily matches `query`, best matches first. If 
+    /// the query contains `::`, it is matched against the whole path instead 
+    /// of the name, like `string::sh` for the `shout` method of strings
+    pub fn workspace_symbols(&self, query: &str) -> Vec<SymbolInfo> {
+        let Some(checker) = self.checker.as_ref() else { return vec![] };
+        let mut found: Vec<(usize, String, SymbolInfo)> = Vec::new();
+        // Later ASTs take precedence, like in `symbol_info`
+        for scope in self.checked_scopes.iter().rev().filter_map(|(s, _)| checker.scopes_from(*s).next()) {
+            for (path, entity) in scope.entities().iter() {
+                let Some(Ident::Name(name)) = path.last() else { continue };
+                let text = path.to_string();
+                let matched = if query.contains("::") { text.trim_start_matches("::") } else { name };
+                let Some(score) = fuzzy_score(query, matched) else { continue };
+                if found.iter().any(|(_, t, _)| *t == text) {
+                    continue;
+                }
+                found.push((score, text, SymbolInfo::new(path, entity)));
+            }
+        }
+        found.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(&b.1)));
+        found.into_iter().map(|(_, _, info)| info).collect()
+    }
+    /// Replace the entries of every source checked by the last call to 
+    /// `check` in `index` with the items those sources declare at the top 
+    /// level. Entries of other sources are kept, so the index can be updated 
+    /// by compiling only the sources that changed
+    pub fn update_symbol_index(&self, index: &mut SymbolIndex) {
+        let Some(checker) = self.checker.as_ref() else { return };
+        for (scope, file) in &self.checked_scopes {
+            let Some(scope) = checker.scopes_from(*scope).next() else { continue };
+            // Imported items are indexed in the file that declares them
+            let symbols = scope.entities().iter()
+                .filter(|(path, e)| matches!(path.last(), Some(Ident::Name(_))) && e.span().0.name() == *file)
+                .map(|(path, e)| IndexedSymbol::new(&SymbolInfo::new(path, e)))
+                .collect();
+            index.update(file, symbols);
+        }
+    }
+    fn check_done(&mut self, ast: &AST, start: Instant, n: usize, total: usize) {
+        let Some(src) = self.pool.span_of(ast).map(|s| s.0) else { return };
+        self.timings.record(TimingEntry {
+            phase: Phase::Check,
+            file: Some(src.name()),
+            duration: start.elapsed(),
+            nodes: 0,
+        });
+        self.file_done(Phase::Check, &src, n, total);
+    }
+}
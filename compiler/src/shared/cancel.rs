@@ -0,0 +1,39 @@
+
+use std::{sync::{Arc, atomic::{AtomicBool, Ordering}}, fmt::Display};
+
+/// Shared flag for aborting a compilation from another thread, for example 
+/// when a language server receives a new edit while the previous one is 
+/// still being analyzed. Clones of a token share the same flag
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Ask every compilation using this token to stop as soon as possible
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+    /// Returns `Err(Cancelled)` if this token has been cancelled
+    pub fn check(&self) -> Result<(), Cancelled> {
+        if self.is_cancelled() { Err(Cancelled) } else { Ok(()) }
+    }
+}
+
+/// Returned instead of a result when a compilation was cancelled. The 
+/// diagnostics logged before cancellation are incomplete and should be 
+/// discarded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl Display for Cancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Compilation was cancelled")
+    }
+}
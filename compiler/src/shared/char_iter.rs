@@ -28,57 +28,34 @@ impl<I: Iterator, const SIZE: usize> Iterator for CachedLookahead<I, SIZE> {
     }
 }
 
-struct CharIndicesWithOffset<'s> {
+pub struct CharIter<'s> {
     src: &'s str,
-    iter: CharIndices<'s>,
-    offset: usize,
+    iter: CachedLookahead<CharIndices<'s>, 2>,
 }
 
-impl<'s> CharIndicesWithOffset<'s> {
-    pub fn new(src: &'s str) -> Self {
-        Self {
-            src,
-            iter: src.char_indices(),
-            offset: 0,
-        }
-    }
-}
-
-impl<'s> Iterator for CharIndicesWithOffset<'s> {
-    type Item = char;
-    fn next(&mut self) -> Option<Self::Item> {
-        let Some((i, n)) = self.iter.next() else {
-            self.offset = self.src.len();
-            return None;
-        };
-        self.offset = i;
-        Some(n)
-    }
-}
-
-pub struct CharIter<'s>(CachedLookahead<CharIndicesWithOffset<'s>, 2>);
-
 impl<'s> CharIter<'s> {
     pub fn new(src: &'s str) -> Self {
-        Self(CachedLookahead::new(CharIndicesWithOffset::new(src)))
+        Self { src, iter: CachedLookahead::new(src.char_indices()) }
     }
+    /// Byte offset of the next character, or the length of the source if 
+    /// there are no more characters
     pub fn offset(&self) -> usize {
-        self.0.iter.offset
+        self.iter.peek().map(|(i, _)| *i).unwrap_or(self.src.len())
     }
     pub fn src_str(&self) -> &'s str {
-        self.0.iter.src
+        self.src
     }
     pub fn peek(&self) -> Option<char> {
-        self.0.peek().copied()
+        self.iter.peek().map(|(_, c)| *c)
     }
     pub fn peek1(&self) -> Option<char> {
-        self.0.peek_n(1).copied()
+        self.iter.peek_n(1).map(|(_, c)| *c)
     }
 }
 
 impl<'s> Iterator for CharIter<'s> {
     type Item = char;
     fn next(&mut self) -> Option<Self::Item> {
-        self.0.next()
+        self.iter.next().map(|(_, c)| c)
     }
 }
@@ -0,0 +1,39 @@
+use std::io;
+use serde::Serialize;
+use super::timing::Phase;
+
+/// Something that happened during a compilation, recorded in an `EventLog`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    /// A phase started, with the number of files it will process
+    PhaseStart { phase: Phase, files: usize },
+    /// A file was processed by a phase. `n` is the number of files done so 
+    /// far, starting at 1
+    FileDone { phase: Phase, file: String, n: usize, total: usize },
+    /// A phase finished, with the number of diagnostics logged in the 
+    /// session so far
+    PhaseEnd { phase: Phase, duration_ms: f64, errors: usize, warnings: usize },
+    /// The session was finished, with the total number of diagnostics
+    Finish { errors: usize, warnings: usize },
+}
+
+/// Writes events as newline-delimited JSON, one object per line, so CI and 
+/// build dashboards can follow what the compiler does over time. See 
+/// `Session::set_event_log`
+pub struct EventLog {
+    out: Box<dyn io::Write + Send>,
+}
+
+impl EventLog {
+    pub fn new<W: io::Write + Send + 'static>(out: W) -> Self {
+        Self { out: Box::new(out) }
+    }
+    pub fn log(&mut self, event: &Event) {
+        let Ok(json) = serde_json::to_string(event) else { return };
+        // Like `JsonSink`, flush every line in case the process exits without 
+        // dropping the session, and ignore write errors since a broken log 
+        // shouldn't stop the compilation
+        let _ = writeln!(self.out, "{json}").and_then(|_| self.out.flush());
+    }
+}
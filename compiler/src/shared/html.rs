@@ -0,0 +1,134 @@
+
+use std::fmt::Write;
+use line_col::LineColLookup;
+use super::{logger::{Message, Note, Level}, src::{Span, Underline, Src}};
+
+const STYLE: &str = "\
+body { background: #1e1e1e; color: #d4d4d4; font-family: monospace; }
+.diagnostic { margin: 1em 0; }
+.level { font-weight: bold; }
+.error .level { color: #f14c4c; }
+.warning .level { color: #cca700; }
+.location { color: #808080; }
+.source { margin: 0.25em 0 0.25em 1em; }
+.line-number { color: #cca700; user-select: none; padding-right: 1em; }
+.underline.squiggle { text-decoration: underline wavy #f14c4c; }
+.underline.highlight { text-decoration: underline solid #29b8db; }
+.underline.normal { text-decoration: underline solid #808080; }
+.notes { margin: 0; }
+.note-kind { font-weight: bold; }
+";
+
+fn escape(text: &str) -> String {
+    let mut res = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '<' => res.push_str("&lt;"),
+            '>' => res.push_str("&gt;"),
+            '&' => res.push_str("&amp;"),
+            '"' => res.push_str("&quot;"),
+            c => res.push(c),
+        }
+    }
+    res
+}
+
+impl Underline {
+    fn css_class(&self) -> &'static str {
+        match self {
+            Self::Squiggle => "squiggle",
+            Self::Highlight => "highlight",
+            Self::Normal => "normal",
+        }
+    }
+}
+
+impl Span<'_> {
+    /// Render the lines this span covers with the span itself underlined. 
+    /// Returns an empty string for builtin spans, since they have no source
+    fn to_html(&self, style: Underline) -> String {
+        if matches!(self.0, Src::Builtin) {
+            return String::new();
+        }
+        let data = self.0.data();
+        let range = self.1.start.min(data.len())..self.1.end.min(data.len());
+        let mut res = format!(
+            "<div class=\"location\">--&gt; {}</div><pre class=\"source\">",
+            escape(&self.to_string())
+        );
+        // Columns are byte offsets from the start of the line, so they index 
+        // into the lines from `Src::lines` whether they end with `\n` or `\r\n`
+        let lookup = LineColLookup::new(data);
+        let (start_line, start_col) = lookup.get(range.start);
+        let (mut end_line, mut end_col) = lookup.get(range.end);
+        // A span that ends right after a line break doesn't cover anything 
+        // on the next line
+        if end_col == 1 && end_line > start_line {
+            end_line -= 1;
+            end_col = usize::MAX;
+        }
+        let lines = self.0.lines().enumerate().skip(start_line - 1).take(end_line - start_line + 1);
+        for (n, line) in lines {
+            let line_num = n + 1;
+            let from = if line_num == start_line { (start_col - 1).min(line.len()) } else { 0 };
+            let to = if line_num == end_line { (end_col - 1).clamp(from, line.len()) } else { line.len() };
+            writeln!(
+                res, "<span class=\"line-number\">{line_num}</span>{}<span class=\"underline {}\">{}</span>{}",
+                escape(&line[..from]),
+                style.css_class(),
+                escape(&line[from..to]),
+                escape(&line[to..])
+            ).unwrap();
+        }
+        res.push_str("</pre>");
+        res
+    }
+}
+
+impl Note<'_> {
+    fn to_html(&self) -> String {
+        format!(
+            "<li class=\"{kind}\"><span class=\"note-kind\">{}:</span> {}{}</li>",
+            self.kind, escape(&self.info),
+            self.at.as_ref().map(|at| at.to_html(self.kind.underline_style())).unwrap_or_default(),
+            kind = self.kind.to_string().to_lowercase(),
+        )
+    }
+}
+
+impl Message<'_> {
+    /// Render this message as an HTML fragment. The fragment uses CSS 
+    /// classes instead of inline styles, so it should be placed in a 
+    /// document created by `html_document` or one with equivalent styles
+    pub fn to_html(&self) -> String {
+        let level = match self.level {
+            Level::Info => "info",
+            Level::Warning => "warning",
+            Level::Error => "error",
+        };
+        format!(
+            "<div class=\"diagnostic {level}\"><div><span class=\"level\">{}</span>: {}</div>{}<ul class=\"notes\">{}</ul></div>",
+            capitalized(level), escape(&self.info),
+            self.span.to_html(self.level.underline_style()),
+            self.notes.iter().map(|n| n.to_html()).collect::<String>()
+        )
+    }
+}
+
+fn capitalized(s: &str) -> String {
+    let mut chars = s.chars();
+    chars.next().map(|c| c.to_uppercase().chain(chars).collect()).unwrap_or_default()
+}
+
+/// Create a standalone HTML document out of messages rendered with 
+/// `Message::to_html`
+pub fn html_document<I: IntoIterator<Item = String>>(messages: I) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Diagnostics</title>\n\
+        <style>\n{STYLE}</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        messages.into_iter().fold(String::new(), |mut acc, m| {
+            writeln!(acc, "{m}").unwrap();
+            acc
+        })
+    )
+}
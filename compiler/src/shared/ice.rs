@@ -0,0 +1,91 @@
+use std::{fmt::Display, panic::{self, AssertUnwindSafe}};
+use super::{logger::{LoggerRef, Message, Level, Note}, src::{ArcSpan, Span}, timing::Phase};
+
+/// A bug in the compiler, as opposed to an error in the code being compiled. 
+/// Raised with `ice!` or the `Ice` trait in places that should be 
+/// unreachable, and turned into a diagnostic by `catch_ice` so that a bug 
+/// never aborts the process doing the compiling
+#[derive(Debug, Clone)]
+pub struct InternalCompilerError {
+    pub msg: String,
+    /// The node that was being processed, if known
+    pub span: Option<ArcSpan>,
+}
+
+impl InternalCompilerError {
+    pub fn new<S: Into<String>>(msg: S) -> Self {
+        Self { msg: msg.into(), span: None }
+    }
+    pub fn at<S: Into<String>>(msg: S, span: ArcSpan) -> Self {
+        Self { msg: msg.into(), span: Some(span) }
+    }
+    /// Abort the running phase with this error. The phase must be run 
+    /// through `catch_ice` for it to be reported
+    pub fn raise(self) -> ! {
+        panic::panic_any(self)
+    }
+}
+
+impl Display for InternalCompilerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Internal compiler error: {}", self.msg)
+    }
+}
+
+pub(crate) trait Ice: Sized {
+    type R;
+    fn ice(self, msg: &str) -> Self::R;
+    fn ice_at(self, msg: &str, span: &ArcSpan) -> Self::R;
+}
+
+impl<T> Ice for Option<T> {
+    type R = T;
+    fn ice(self, msg: &str) -> Self::R {
+        self.unwrap_or_else(|| InternalCompilerError::new(msg).raise())
+    }
+    fn ice_at(self, msg: &str, span: &ArcSpan) -> Self::R {
+        self.unwrap_or_else(|| InternalCompilerError::at(msg, span.clone()).raise())
+    }
+}
+
+/// Raise an `InternalCompilerError`, optionally at the span of a node, like 
+/// `ice!(at span, "node has no type")`
+#[macro_export]
+macro_rules! ice {
+    (at $span:expr, $msg:literal $($rest:tt)*) => {
+        $crate::shared::ice::InternalCompilerError::at(format!($msg $($rest)*), $span.clone()).raise()
+    };
+    ($msg:literal $($rest:tt)*) => {
+        $crate::shared::ice::InternalCompilerError::new(format!($msg $($rest)*)).raise()
+    };
+}
+
+/// Run `f`, which does the work of `phase`, reporting any panic in it as an 
+/// internal compiler error through `logger` instead of letting it unwind 
+/// further. The diagnostic points to the node given by the error, or to 
+/// `at` if there is none. Returns `None` if `f` panicked
+pub fn catch_ice<R, F: FnOnce() -> R>(phase: Phase, at: Span, logger: &LoggerRef, f: F) -> Option<R> {
+    let payload = match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(res) => return Some(res),
+        Err(payload) => payload,
+    };
+    // Bugs that weren't raised through `ice!`, like an out-of-bounds index,
+    // are reported the same way
+    let ice = match payload.downcast::<InternalCompilerError>() {
+        Ok(ice) => *ice,
+        Err(payload) => InternalCompilerError::new(
+            payload.downcast_ref::<&str>().map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or(String::from("unknown panic"))
+        ),
+    };
+    let span = ice.span.as_ref().map(ArcSpan::as_ref).unwrap_or(at);
+    // The panic may have happened while the logger was locked
+    logger.clear_poison();
+    logger.lock().unwrap().log(Message::new(
+        Level::Error,
+        format!("Internal compiler error during {phase}: {}", ice.msg),
+        span
+    ).note(Note::new("This is a bug in the compiler, please report it along with the code that caused it", false)));
+    None
+}
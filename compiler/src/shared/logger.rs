@@ -1,12 +1,14 @@
 
-use std::{sync::{Arc, Mutex}, fmt::{Display, Write}};
+use std::{sync::{Arc, Mutex}, fmt::{Display, Write}, io, ops::Range};
 use crate::shared::src::Span;
 use colored::Colorize;
+use serde::Serialize;
 
 use super::src::Underline;
 
 #[allow(unused)]
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Level {
     Info,
     Warning,
@@ -34,13 +36,13 @@ impl Display for Level {
 }
 
 #[derive(Debug)]
-enum NoteKind {
+pub(crate) enum NoteKind {
     Note,
     Hint,
 }
 
 impl NoteKind {
-    fn underline_style(&self) -> Underline {
+    pub(crate) fn underline_style(&self) -> Underline {
         match self {
             Self::Note => Underline::Normal,
             Self::Hint => Underline::Highlight,
@@ -59,9 +61,9 @@ impl Display for NoteKind {
 
 #[derive(Debug)]
 pub struct Note<'s> {
-    info: String,
-    at: Option<Span<'s>>,
-    kind: NoteKind,
+    pub(crate) info: String,
+    pub(crate) at: Option<Span<'s>>,
+    pub(crate) kind: NoteKind,
 }
 
 impl<'s> Note<'s> {
@@ -99,19 +101,89 @@ impl Display for Note<'_> {
 #[derive(Debug)]
 pub struct Message<'s> {
     pub(crate) level: Level,
-    info: String,
-    notes: Vec<Note<'s>>,
-    span: Span<'s>,
+    pub(crate) code: Option<String>,
+    pub(crate) info: String,
+    pub(crate) notes: Vec<Note<'s>>,
+    pub(crate) span: Span<'s>,
 }
 
 impl<'s> Message<'s> {
     pub fn new<S: Display>(level: Level, info: S, span: Span<'s>) -> Self {
-        Self { level, info: info.to_string(), notes: vec![], span }
+        Self { level, code: None, info: info.to_string(), notes: vec![], span }
+    }
+    /// Set the code that identifies what kind of diagnostic this is, such as 
+    /// the name of the lint that emitted it
+    pub fn code<S: Into<String>>(mut self, code: S) -> Self {
+        self.code = Some(code.into());
+        self
     }
     pub fn note(mut self, note: Note<'s>) -> Self {
         self.notes.push(note);
         self
     }
+    pub fn level(&self) -> Level {
+        self.level
+    }
+    pub fn info(&self) -> &str {
+        &self.info
+    }
+    pub fn span(&self) -> &Span<'s> {
+        &self.span
+    }
+    /// Copy this message into an `OwnedDiagnostic` that doesn't borrow its 
+    /// source. The rendered text follows the current color settings
+    pub fn to_owned(&self) -> OwnedDiagnostic {
+        let (line, column) = self.span.line_col();
+        OwnedDiagnostic {
+            level: self.level,
+            code: self.code.clone(),
+            info: self.info.clone(),
+            file: self.span.0.name(),
+            range: self.span.1.clone(),
+            line, column,
+            excerpt: self.span.underlined(self.level.underline_style()),
+            rendered: self.to_string(),
+            notes: self.notes.iter().map(|note| OwnedNote {
+                hint: matches!(note.kind, NoteKind::Hint),
+                info: note.info.clone(),
+                at: note.at.as_ref().map(|at| (at.0.name(), at.1.clone())),
+            }).collect(),
+        }
+    }
+    /// Render this message like `Display` does, but without colors no 
+    /// matter what the current color settings are
+    pub fn to_plain_string(&self) -> String {
+        strip_colors(&self.to_string())
+    }
+}
+
+/// A note on an `OwnedDiagnostic`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OwnedNote {
+    pub hint: bool,
+    pub info: String,
+    /// File name and byte range the note points to, if any
+    pub at: Option<(String, Range<usize>)>,
+}
+
+/// A copy of a `Message` that doesn't borrow its source, so it can be kept 
+/// around after compilation has finished or sent to another thread
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OwnedDiagnostic {
+    pub level: Level,
+    pub code: Option<String>,
+    pub info: String,
+    pub file: String,
+    /// Byte range of the diagnostic in the file
+    pub range: Range<usize>,
+    /// 1-based line and column of the start of the range
+    pub line: usize,
+    pub column: usize,
+    /// The underlined source excerpt
+    pub excerpt: String,
+    /// The full diagnostic as it would be printed, including notes
+    pub rendered: String,
+    pub notes: Vec<OwnedNote>,
 }
 
 impl Display for Message<'_> {
@@ -128,8 +200,9 @@ impl Display for Message<'_> {
         }
         
         f.write_fmt(format_args!(
-            "{}:\n{}{}\n{}",
+            "{}{}:\n{}{}\n{}",
             self.level,
+            self.code.as_ref().map(|c| format!("[{c}]")).unwrap_or_default(),
             self.span.underlined(self.level.underline_style()),
             self.info,
             self.notes
@@ -142,10 +215,82 @@ impl Display for Message<'_> {
     }
 }
 
+/// A destination for logged messages. Any `FnMut(&Message)` closure is a 
+/// sink
+pub trait Sink: Send {
+    fn log(&mut self, msg: &Message);
+}
+
+impl<F: FnMut(&Message) + Send> Sink for F {
+    fn log(&mut self, msg: &Message) {
+        self(msg)
+    }
+}
+
+/// Writes every message as a JSON object on its own line
+pub struct JsonSink<W: io::Write + Send> {
+    out: W,
+}
+
+impl<W: io::Write + Send> JsonSink<W> {
+    pub fn new(out: W) -> Self {
+        Self { out }
+    }
+}
+
+impl<W: io::Write + Send> Sink for JsonSink<W> {
+    fn log(&mut self, msg: &Message) {
+        let Ok(json) = serde_json::to_string(&msg.to_owned()) else { return };
+        // The process may exit without dropping the logger, so each message 
+        // is flushed right away. A diagnostic that can't be written can't be 
+        // reported anywhere either, so write errors are ignored
+        let _ = writeln!(self.out, "{json}").and_then(|_| self.out.flush());
+    }
+}
+
+/// Sink that stores every message it receives. Cloning a collector gives 
+/// another handle to the same messages
+#[derive(Debug, Clone, Default)]
+pub struct Collector {
+    messages: Arc<Mutex<Vec<OwnedDiagnostic>>>,
+}
+
+impl Collector {
+    /// All messages collected so far, in the order they were logged
+    pub fn messages(&self) -> Vec<OwnedDiagnostic> {
+        self.messages.lock().unwrap().clone()
+    }
+    /// Collected messages of the given level
+    pub fn of_level(&self, level: Level) -> Vec<OwnedDiagnostic> {
+        self.messages.lock().unwrap().iter().filter(|m| m.level == level).cloned().collect()
+    }
+}
+
+impl Sink for Collector {
+    fn log(&mut self, msg: &Message) {
+        self.messages.lock().unwrap().push(msg.to_owned());
+    }
+}
+
+struct LoggerSink {
+    sink: Box<dyn Sink>,
+    min_level: Level,
+}
+
+/// A place where code was generated, see `Logger::add_expansion`
+struct Expansion {
+    src: String,
+    at: Range<usize>,
+    note: String,
+    def: Option<Range<usize>>,
+}
+
 pub struct Logger {
-    logger: Box<dyn FnMut(Message)>,
+    sinks: Vec<LoggerSink>,
     error_count: usize,
     warn_count: usize,
+    max_errors: Option<usize>,
+    expansions: Vec<Expansion>,
 }
 
 impl std::fmt::Debug for Logger {
@@ -155,24 +300,106 @@ impl std::fmt::Debug for Logger {
 }
 
 impl Logger {
-    pub fn new<F: FnMut(Message) + 'static>(logger: F) -> LoggerRef {
+    /// Create a logger that sends every message to `logger`
+    pub fn new<F: FnMut(&Message) + Send + 'static>(logger: F) -> LoggerRef {
+        let res = Self::empty();
+        res.lock().unwrap().add_sink(logger, Level::Info);
+        res
+    }
+    /// Create a logger that stores every message in memory instead of 
+    /// printing it. Mostly useful for asserting on diagnostics in tests
+    pub fn collect() -> (LoggerRef, Collector) {
+        let collector = Collector::default();
+        let logger = Self::empty();
+        logger.lock().unwrap().add_sink(collector.clone(), Level::Info);
+        (logger, collector)
+    }
+    /// Create a logger that only counts messages until sinks are added to it
+    pub fn empty() -> LoggerRef {
         Arc::from(Mutex::from(Self {
-            logger: Box::from(logger),
+            sinks: vec![],
             error_count: 0,
             warn_count: 0,
+            max_errors: None,
+            expansions: vec![],
         }))
     }
+    /// Send every message whose level is at least `min_level` to `sink` in 
+    /// addition to the existing sinks. Closures passed here need their 
+    /// parameter annotated as `&Message`
+    pub fn add_sink<S: Sink + 'static>(&mut self, sink: S, min_level: Level) {
+        self.sinks.push(LoggerSink { sink: Box::from(sink), min_level });
+    }
+    /// Stop sending errors to the sinks after `max` of them have been 
+    /// logged, with one last error saying the rest were dropped. Dropped 
+    /// errors are still counted by `errors`
+    pub fn set_max_errors(&mut self, max: Option<usize>) {
+        self.max_errors = max;
+    }
+    /// Whether errors are being dropped because of `set_max_errors`
+    pub fn error_limit_reached(&self) -> bool {
+        self.max_errors.is_some_and(|max| self.error_count >= max)
+    }
     #[allow(clippy::should_implement_trait)]
     pub fn default() -> LoggerRef {
         Self::new(default_console_logger)
     }
-    pub fn log(&mut self, msg: Message) {
+    /// Record that the code at `at` was generated, for example by a macro. 
+    /// Every message logged inside `at` afterwards gets `note` attached, 
+    /// pointing to `def` if given, since the code the message refers to 
+    /// isn't visible in the source
+    pub fn add_expansion(&mut self, at: &Span, note: String, def: Option<&Span>) {
+        let expansion = Expansion {
+            src: at.0.name(),
+            at: at.1.clone(),
+            note,
+            def: def.map(|d| d.1.clone()),
+        };
+        let exists = self.expansions.iter().any(|e|
+            e.src == expansion.src && e.at == expansion.at && 
+            e.note == expansion.note && e.def == expansion.def
+        );
+        if !exists {
+            self.expansions.push(expansion);
+        }
+    }
+    pub fn log(&mut self, mut msg: Message) {
+        if msg.level == Level::Error && self.error_limit_reached() {
+            if self.max_errors == Some(self.error_count) {
+                let max = self.error_count;
+                self.send(&Message::new(
+                    Level::Error,
+                    format!("Too many errors, stopped reporting after {max}"),
+                    msg.span
+                ));
+            }
+            self.error_count += 1;
+            return;
+        }
+        if !self.expansions.is_empty() {
+            let src = msg.span.0.name();
+            for e in &self.expansions {
+                if e.src == src && e.at.start <= msg.span.1.start && msg.span.1.end <= e.at.end {
+                    msg.notes.push(match e.def {
+                        Some(ref def) => Note::new_at(e.note.clone(), Span(msg.span.0, def.clone())),
+                        None => Note::new(e.note.clone(), false),
+                    });
+                }
+            }
+        }
         match msg.level {
             Level::Info => {}
             Level::Warning => self.warn_count += 1,
             Level::Error => self.error_count += 1,
         }
-        (self.logger)(msg);
+        self.send(&msg);
+    }
+    fn send(&mut self, msg: &Message) {
+        for sink in &mut self.sinks {
+            if msg.level >= sink.min_level {
+                sink.sink.log(msg);
+            }
+        }
     }
     pub fn errors(&self) -> usize {
         self.error_count
@@ -182,8 +409,28 @@ impl Logger {
     }
 }
 
-pub(crate) type LoggerRef = Arc<Mutex<Logger>>;
+/// Shared handle to a Logger. The logging callback is required to be `Send`, 
+/// so a LoggerRef can be freely cloned across threads, and since every 
+/// message is logged while holding the lock, concurrently logged messages 
+/// never get interleaved
+pub type LoggerRef = Arc<Mutex<Logger>>;
+
+/// Remove the ANSI escape sequences used for colors from rendered text
+pub fn strip_colors(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' && chars.clone().next() == Some('[') {
+            // Skip the parameters up to the final byte, like the `m` in `\x1b[1;31m`
+            chars.next();
+            chars.find(|c| ('@'..='~').contains(c));
+            continue;
+        }
+        plain.push(c);
+    }
+    plain
+}
 
-pub fn default_console_logger(msg: Message) {
+pub fn default_console_logger(msg: &Message) {
     println!("{msg}");
 }
@@ -1,4 +1,11 @@
 
+pub mod cancel;
 pub(crate) mod char_iter;
+pub mod events;
+pub mod html;
+pub mod ice;
 pub mod logger;
+pub mod similar;
+pub mod snapshot;
 pub mod src;
+pub mod timing;
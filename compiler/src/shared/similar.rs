@@ -0,0 +1,69 @@
+
+/// Number of single-character insertions, deletions, substitutions and 
+/// swaps of adjacent characters needed to turn `a` into `b`
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a = a.chars().collect::<Vec<_>>();
+    let b = b.chars().collect::<Vec<_>>();
+    // d[i][j] is the distance between the first i chars of a and the first 
+    // j chars of b
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    d[0] = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1).min(d[i][j - 1] + 1).min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+/// Find the candidate most similar to `name`, if any of them is close 
+/// enough that `name` is plausibly a typo of it
+pub fn closest_match<'a, I>(name: &str, candidates: I) -> Option<&'a str>
+    where I: IntoIterator<Item = &'a str>
+{
+    let max_distance = (name.chars().count() / 3).max(1);
+    candidates.into_iter()
+        .map(|c| (edit_distance(name, c), c))
+        .filter(|(d, _)| *d <= max_distance)
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, c)| c)
+}
+
+/// Score how well `candidate` matches `query` in a fuzzy search, where the 
+/// characters of the query have to appear in the candidate in order, 
+/// ignoring case. Lower is better: exact matches score 0, prefixes 1, other 
+/// substrings a bit more the later they start, and the rest more the more 
+/// characters are skipped between the matched ones. Returns None if 
+/// `candidate` doesn't match at all
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<usize> {
+    let query = query.to_lowercase();
+    let candidate = candidate.to_lowercase();
+    if query == candidate {
+        return Some(0);
+    }
+    if let Some(pos) = candidate.find(&query) {
+        return Some(if pos == 0 { 1 } else { 2 + pos });
+    }
+    let mut chars = candidate.chars().skip_while({
+        let first = query.chars().next()?;
+        move |c| *c != first
+    });
+    let mut skipped = 0;
+    for q in query.chars() {
+        loop {
+            let c = chars.next()?;
+            if c == q {
+                break;
+            }
+            skipped += 1;
+        }
+    }
+    Some(candidate.len() + 1 + skipped)
+}
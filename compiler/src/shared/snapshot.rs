@@ -0,0 +1,130 @@
+
+use std::{path::{Path, PathBuf}, fs, sync::{Arc, Mutex}, fmt::Write};
+use crate::{parse, check_coherency, parser::{parse::NodePool, config::{LanguageConfig, Edition}}, checker::lint::LintLevel};
+use super::{src::Src, logger::Logger};
+
+/// The output of compiling a single snapshot fixture
+pub struct SnapshotOutput {
+    /// All diagnostics emitted while compiling, rendered without colors
+    pub diagnostics: String,
+    /// Dump of the parsed AST, if parsing succeeded
+    pub ast: Option<String>,
+}
+
+/// Read the language config for a fixture from `// edition: <edition>`, 
+/// `// cfg: <option>` and `// warn: <lint>` comments at the start of it
+fn fixture_config(data: &str) -> Result<LanguageConfig, String> {
+    let header = || data.lines().map_while(|line| line.strip_prefix("//")).map(str::trim);
+    let edition = header()
+        .find_map(|line| line.strip_prefix("edition:"))
+        .map(|e| e.trim().parse::<Edition>())
+        .transpose()?;
+    let mut config = LanguageConfig::for_edition(edition.unwrap_or_default());
+    for option in header().filter_map(|line| line.strip_prefix("cfg:")) {
+        config.cfg = config.cfg.with_option(option);
+    }
+    for lint in header().filter_map(|line| line.strip_prefix("warn:")) {
+        config.lints.insert(lint.trim().to_string(), LintLevel::Warn);
+    }
+    Ok(config)
+}
+
+/// Compile a source file and capture its diagnostics and AST dump. The file
+/// is given `name` as its source name, so that the output does not depend
+/// on where the fixtures are located on disk. Fails if the header comments 
+/// of the file are invalid
+pub fn compile_snapshot(name: &str, data: &str) -> Result<SnapshotOutput, String> {
+    let diagnostics = Arc::new(Mutex::new(String::new()));
+    let logger = {
+        let diagnostics = diagnostics.clone();
+        Logger::new(move |msg| writeln!(diagnostics.lock().unwrap(), "{}", msg.to_plain_string()).unwrap())
+    };
+    let config = Arc::new(fixture_config(data)?);
+    let mut pool = NodePool::new();
+    let ast = parse(
+        Src::from_memory(name, data), config.clone(), &mut pool, logger.clone()
+    ).ok().map(|mut ast| {
+        check_coherency(&mut ast, &mut pool, config, logger.clone());
+        pool.dump(&ast)
+    });
+    let diagnostics = diagnostics.lock().unwrap().clone();
+    Ok(SnapshotOutput { diagnostics, ast })
+}
+
+/// A fixture whose output didn't match its golden file
+#[derive(Debug)]
+pub struct SnapshotMismatch {
+    pub golden: PathBuf,
+    pub expected: String,
+    pub found: String,
+}
+
+impl std::fmt::Display for SnapshotMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f, "snapshot {} does not match\n--- expected ---\n{}\n--- found ---\n{}",
+            self.golden.display(), self.expected, self.found
+        )
+    }
+}
+
+/// Runs every `.dash` fixture in a directory and compares its output
+/// against golden files next to it: `<name>.diagnostics` for the emitted
+/// diagnostics and `<name>.ast` for the AST dump
+///
+/// In bless mode, golden files are (re)written with the current output
+/// instead of being compared against
+pub struct SnapshotRunner {
+    dir: PathBuf,
+    bless: bool,
+}
+
+impl SnapshotRunner {
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        Self { dir: dir.into(), bless: false }
+    }
+    pub fn bless(mut self, bless: bool) -> Self {
+        self.bless = bless;
+        self
+    }
+    /// Run all fixtures, returning the mismatches. Missing golden files count
+    /// as mismatches unless blessing
+    pub fn run(&self) -> Result<Vec<SnapshotMismatch>, String> {
+        let mut fixtures = fs::read_dir(&self.dir)
+            .map_err(|e| format!("Can't read fixture directory: {e}"))?
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.extension().is_some_and(|e| e == "dash"))
+            .collect::<Vec<_>>();
+        fixtures.sort();
+
+        let mut mismatches = vec![];
+        for fixture in fixtures {
+            let data = fs::read_to_string(&fixture)
+                .map_err(|e| format!("Can't read fixture {}: {e}", fixture.display()))?;
+            let name = fixture.strip_prefix(&self.dir).unwrap_or(&fixture).to_string_lossy();
+            let output = compile_snapshot(&name, &data)
+                .map_err(|e| format!("Invalid fixture {}: {e}", fixture.display()))?;
+            mismatches.extend(self.check(&fixture.with_extension("diagnostics"), Some(output.diagnostics))?);
+            mismatches.extend(self.check(&fixture.with_extension("ast"), output.ast)?);
+        }
+        Ok(mismatches)
+    }
+    fn check(&self, golden: &Path, found: Option<String>) -> Result<Option<SnapshotMismatch>, String> {
+        let expected = fs::read_to_string(golden).ok();
+        if expected == found {
+            return Ok(None);
+        }
+        if self.bless {
+            match found {
+                Some(found) => fs::write(golden, found),
+                None => fs::remove_file(golden),
+            }.map_err(|e| format!("Can't bless {}: {e}", golden.display()))?;
+            return Ok(None);
+        }
+        Ok(Some(SnapshotMismatch {
+            golden: golden.to_path_buf(),
+            expected: expected.unwrap_or(String::from("<no file>")),
+            found: found.unwrap_or(String::from("<no output>")),
+        }))
+    }
+}
@@ -44,16 +44,22 @@ impl<'s> Span<'s> {
     pub fn builtin() -> Self {
         Self(&Src::Builtin, 0..0)
     }
+    /// The 1-based line and column of the start of this span
+    pub fn line_col(&self) -> (usize, usize) {
+        LineColLookup::new(self.0.data()).get(self.1.start)
+    }
     pub fn underlined(&self, style: Underline) -> String {
         // Get the starting and ending linecols as 0-based indices
         let sub_tuple = |a: (usize, usize)| { (a.0 - 1, a.1 - 1) };
         let lookup = LineColLookup::new(self.0.data());
         let start = sub_tuple(lookup.get(self.1.start));
-        let end = sub_tuple(lookup.get(self.1.end));
-
-        let mut lines = self.0
-            .data().lines()
-            .skip(start.0).take(end.0 - start.0 + 1);
+        let mut end = sub_tuple(lookup.get(self.1.end));
+        // A span that ends right after a line break doesn't cover anything 
+        // on the next line
+        if end.1 == 0 && end.0 > start.0 {
+            end.0 -= 1;
+            end.1 = usize::MAX;
+        }
 
         let padding = (end.0 + 1).to_string().len();
         let output_line = |line: usize, content, range| {
@@ -66,23 +72,16 @@ impl<'s> Span<'s> {
             )
         };
         
-        let underlined = if end.0 == start.0 {
-            output_line(start.0 + 1, lines.next().unwrap(), start.1..end.1)
+        // Columns are byte offsets from the start of the line, which stay 
+        // the same whether lines end with `\n` or `\r\n`. Parts of the span 
+        // past the end of a line cover its line break, which is shown as one 
+        // mark after the last character
+        let mut underlined = String::new();
+        for (n, line) in self.0.lines().enumerate().skip(start.0).take(end.0 - start.0 + 1) {
+            let from = if n == start.0 { start.1.min(line.len()) } else { 0 };
+            let to = if n == end.0 { end.1.clamp(from, line.len()) } else { line.len() };
+            underlined.push_str(&output_line(n + 1, line, from..to));
         }
-        else {
-            let mut res = String::new();
-            let mut i = 1;
-            let len = end.0 - start.0;
-            for line in lines {
-                res.push_str(&output_line(start.0 + i, line, match i {
-                    _ if i == len => 0..end.1,
-                    1 => start.1..line.len(),
-                    _ => 0..line.len(),
-                }));
-                i += 1;
-            }
-            res
-        };
         format!(
             "{}{}{}\n{}",
             " ".repeat(padding), "--> ".black(), self.to_string().black(),
@@ -117,6 +116,9 @@ impl ArcSpan {
     pub fn builtin() -> Self {
         Self(Src::builtin(), 0..0)
     }
+    pub fn is_builtin(&self) -> bool {
+        matches!(*self.0, Src::Builtin)
+    }
     pub fn as_ref(&self) -> Span {
         Span(self.0.as_ref(), self.1.clone())
     }
@@ -134,12 +136,56 @@ impl Debug for ArcSpan {
     }
 }
 
+/// A sequence of bytes in a source file that isn't valid UTF-8. Such 
+/// sequences are replaced with U+FFFD when the file is read, and reported by 
+/// the tokenizer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidUtf8 {
+    /// Offset of the sequence in the file, as opposed to in `Src::data`
+    pub byte_offset: usize,
+    /// Length of the sequence in the file
+    pub len: usize,
+    /// Offset of the replacement character in `Src::data`
+    pub offset: usize,
+}
+
+/// UTF-8 encoding of the byte order mark that some editors put at the start 
+/// of files
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// Decode the contents of a source file, stripping the byte order mark and 
+/// replacing invalid UTF-8 instead of failing on it. Returns the decoded 
+/// text and the sequences that were replaced
+pub fn decode(bytes: &[u8]) -> (String, Vec<InvalidUtf8>) {
+    let bom = if bytes.starts_with(UTF8_BOM) { UTF8_BOM.len() } else { 0 };
+    let mut data = String::with_capacity(bytes.len() - bom);
+    let mut invalid = vec![];
+    let mut byte_offset = bom;
+    for chunk in bytes[bom..].utf8_chunks() {
+        data.push_str(chunk.valid());
+        byte_offset += chunk.valid().len();
+        if !chunk.invalid().is_empty() {
+            invalid.push(InvalidUtf8 { byte_offset, len: chunk.invalid().len(), offset: data.len() });
+            data.push(char::REPLACEMENT_CHARACTER);
+            byte_offset += chunk.invalid().len();
+        }
+    }
+    (data, invalid)
+}
+
 pub enum Src {
     Builtin,
     File {
         path: PathBuf,
         data: String,
-    }
+        invalid_utf8: Vec<InvalidUtf8>,
+    },
+    /// Source code that does not come from a file, such as a string passed 
+    /// in by a host application
+    Memory {
+        name: String,
+        data: String,
+    },
 }
 
 impl Src {
@@ -147,35 +193,61 @@ impl Src {
         Arc::from(Self::Builtin)
     }
 
+    /// Read a source file. A leading byte order mark is skipped, and 
+    /// sequences that aren't valid UTF-8 are replaced rather than making 
+    /// the read fail, see `invalid_utf8`
     pub fn from_file<P: Into<PathBuf>>(path: P) -> Result<Arc<Self>, String> {
         let path = path.into();
-        Ok(Arc::from(Src::File {
-            data: fs::read_to_string(&path).map_err(|e| format!("Can't read file: {}", e))?,
-            path,
-        }))
+        let bytes = fs::read(&path).map_err(|e| format!("Can't read file: {}", e))?;
+        let (data, invalid_utf8) = decode(&bytes);
+        Ok(Arc::from(Src::File { path, data, invalid_utf8 }))
+    }
+    /// Create a source that doesn't come from a file. A leading byte order 
+    /// mark is skipped like for files
+    pub fn from_memory<N: Into<String>, D: Into<String>>(name: N, data: D) -> Arc<Self> {
+        let (data, _) = decode(data.into().as_bytes());
+        Arc::from(Src::Memory { name: name.into(), data })
     }
     pub fn name(&self) -> String {
         match self {
             Src::Builtin => String::from("<compiler built-in>"),
-            Src::File { path, data: _ } => path.to_string_lossy().to_string(),
+            Src::File { path, .. } => path.to_string_lossy().to_string(),
+            Src::Memory { name, data: _ } => name.clone(),
         }
     }
     pub fn data(&self) -> &str {
         match self {
             Src::Builtin => "",
-            Src::File { path: _, data } => data.as_str(),
+            Src::File { data, .. } => data.as_str(),
+            Src::Memory { name: _, data } => data.as_str(),
         }
     }
     pub fn iter(&self) -> CharIter {
         CharIter::new(self.data())
     }
+    /// The lines of the source without their line breaks, which may be 
+    /// either `\n` or `\r\n`. Unlike `str::lines`, a line break at the end of 
+    /// the source is followed by an empty line, so there is a line for every 
+    /// offset into the source
+    pub fn lines(&self) -> impl Iterator<Item = &str> {
+        self.data().split('\n').map(|line| line.strip_suffix('\r').unwrap_or(line))
+    }
+    /// The invalid UTF-8 sequences that were replaced when the source was 
+    /// read, in order
+    pub fn invalid_utf8(&self) -> &[InvalidUtf8] {
+        match self {
+            Src::File { invalid_utf8, .. } => invalid_utf8,
+            Src::Builtin | Src::Memory { .. } => &[],
+        }
+    }
 }
 
 impl Debug for Src {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::Builtin => f.write_str("Builtin"),
-            Self::File { path, data: _ } => f.write_fmt(format_args!("File({path:?})")),
+            Self::File { path, .. } => f.write_fmt(format_args!("File({path:?})")),
+            Self::Memory { name, data: _ } => f.write_fmt(format_args!("Memory({name:?})")),
         }
     }
 }
@@ -190,7 +262,8 @@ impl PartialEq for Src {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
             (Src::Builtin, Src::Builtin) => true,
-            (Src::File { path: a, data: _ }, Self::File { path: b, data: _ }) => a == b,
+            (Src::File { path: a, .. }, Self::File { path: b, .. }) => a == b,
+            (Src::Memory { name: a, data: _ }, Self::Memory { name: b, data: _ }) => a == b,
             (_, _) => false
         }
     }
@@ -202,7 +275,8 @@ impl Hash for Src {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         match self {
             Src::Builtin => 0.hash(state),
-            Src::File { path, data: _ } => path.hash(state),
+            Src::File { path, .. } => path.hash(state),
+            Src::Memory { name, data: _ } => name.hash(state),
         }
     }
 }
@@ -233,11 +307,30 @@ impl SrcPool {
             Self::new(srcs)
         }
     }
+    /// Create a pool of all the source files in multiple directories
+    pub fn new_from_dirs(dirs: Vec<PathBuf>) -> Result<Self, String> {
+        let mut srcs = vec![];
+        for dir in dirs {
+            if !dir.exists() {
+                Err(format!("Source directory {} does not exist", dir.display()))?;
+            }
+            srcs.extend(Self::find_src_files(dir));
+        }
+        if srcs.is_empty() {
+            Err("No source files found".to_string())
+        }
+        else {
+            Self::new(srcs)
+        }
+    }
+    /// Find all source files in a directory and its subdirectories. The 
+    /// files are sorted by path, since the order they are checked in 
+    /// affects the output and `read_dir` order differs between platforms. 
+    /// Entries that can't be read are skipped
     fn find_src_files(dir: PathBuf) -> Vec<PathBuf> {
         let mut res = vec![];
         if let Ok(entries) = std::fs::read_dir(dir) { 
-            for entry in entries {
-                let file = entry.unwrap();
+            for file in entries.flatten() {
                 if let Ok(ty) = file.file_type() {
                     if ty.is_dir() {
                         res.extend(Self::find_src_files(file.path()));
@@ -248,11 +341,15 @@ impl SrcPool {
                 }
             }
         }
+        res.sort();
         res
     }
     pub fn iter(&self) -> impl Iterator<Item = Arc<Src>> + '_ {
         self.into_iter()
     }
+    pub fn srcs(&self) -> &[Arc<Src>] {
+        &self.srcs
+    }
 }
 
 impl<'a> IntoIterator for &'a SrcPool {
@@ -0,0 +1,96 @@
+
+use std::{time::{Duration, Instant}, fmt::Display};
+use serde::Serialize;
+
+/// A phase of compilation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Phase {
+    /// Tokenizing on its own. Tokens are normally produced on demand while 
+    /// parsing, in which case lexing is counted as part of parsing
+    Lex,
+    Parse,
+    Check,
+    Codegen,
+}
+
+impl Phase {
+    pub const ALL: [Phase; 4] = [Phase::Lex, Phase::Parse, Phase::Check, Phase::Codegen];
+}
+
+impl Display for Phase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Lex => "lex",
+            Self::Parse => "parse",
+            Self::Check => "check",
+            Self::Codegen => "codegen",
+        })
+    }
+}
+
+/// How long a phase took, optionally for a single file
+#[derive(Debug, Clone)]
+pub struct TimingEntry {
+    pub phase: Phase,
+    /// The file the phase was run on, or None if it was run on all of them
+    pub file: Option<String>,
+    pub duration: Duration,
+    /// How many AST nodes were allocated during the phase
+    pub nodes: usize,
+}
+
+/// Records how long each phase of compilation takes
+#[derive(Debug, Default, Clone)]
+pub struct Timings {
+    entries: Vec<TimingEntry>,
+}
+
+impl Timings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn record(&mut self, entry: TimingEntry) {
+        self.entries.push(entry);
+    }
+    /// Run `f` and record how long it took
+    pub fn time<R, F: FnOnce() -> R>(&mut self, phase: Phase, file: Option<String>, f: F) -> R {
+        let start = Instant::now();
+        let res = f();
+        self.record(TimingEntry { phase, file, duration: start.elapsed(), nodes: 0 });
+        res
+    }
+    pub fn entries(&self) -> &[TimingEntry] {
+        &self.entries
+    }
+    /// Total time spent in a phase, over all files
+    pub fn total(&self, phase: Phase) -> Duration {
+        self.entries.iter().filter(|e| e.phase == phase).map(|e| e.duration).sum()
+    }
+}
+
+impl Display for Timings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{:<8} {:>10} {:>8}  file", "phase", "time (ms)", "nodes")?;
+        for entry in &self.entries {
+            writeln!(
+                f, "{:<8} {:>10.3} {:>8}  {}",
+                entry.phase.to_string(),
+                entry.duration.as_secs_f64() * 1000.0,
+                entry.nodes,
+                entry.file.as_deref().unwrap_or("<all>")
+            )?;
+        }
+        for phase in Phase::ALL {
+            if self.entries.iter().any(|e| e.phase == phase) {
+                writeln!(
+                    f, "{:<8} {:>10.3} {:>8}  <total>",
+                    phase.to_string(),
+                    self.total(phase).as_secs_f64() * 1000.0,
+                    self.entries.iter().filter(|e| e.phase == phase).map(|e| e.nodes).sum::<usize>()
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,94 @@
+use std::{path::PathBuf, sync::Arc};
+use dash_compiler::{
+    tokenize,
+    parser::{config::LanguageConfig, TokenKind},
+    shared::{src::{Src, InvalidUtf8, decode}, logger::{Logger, Level}},
+};
+
+/// Write `bytes` to a file that is removed when the returned guard is dropped
+struct TempFile(PathBuf);
+
+impl TempFile {
+    fn new(name: &str, bytes: &[u8]) -> Self {
+        let path = std::env::temp_dir().join(format!("{}_{name}", std::process::id()));
+        std::fs::write(&path, bytes).unwrap();
+        Self(path)
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+#[test]
+fn bom() {
+    let file = TempFile::new("bom.dash", b"\xEF\xBB\xBFlet a = 5;");
+    let src = Src::from_file(&file.0).unwrap();
+    assert_eq!(src.data(), "let a = 5;");
+    assert!(src.invalid_utf8().is_empty());
+
+    let (logger, collector) = Logger::collect();
+    tokenize(&src, Arc::new(LanguageConfig::default()), logger);
+    assert!(collector.messages().is_empty());
+}
+
+/// Raw bytes from somewhere other than a file, like fuzz input, can be 
+/// decoded the same way
+#[test]
+fn decode_bytes() {
+    let (data, invalid) = decode(b"\xEF\xBB\xBFa\xFFb");
+    assert_eq!(data, "a\u{FFFD}b");
+    assert_eq!(invalid, [InvalidUtf8 { byte_offset: 4, len: 1, offset: 1 }]);
+}
+
+#[test]
+fn bom_in_memory() {
+    let src = Src::from_memory("test", "\u{FEFF}let a = 5;");
+    assert_eq!(src.data(), "let a = 5;");
+    // Only a mark at the very start is skipped
+    assert_eq!(Src::from_memory("test", "a\u{FEFF}").data(), "a\u{FEFF}");
+}
+
+#[test]
+fn invalid_utf8() {
+    let file = TempFile::new("invalid.dash", b"let a = \"\xFF\xFE\"; // \xC3\n");
+    let src = Src::from_file(&file.0).unwrap();
+    assert_eq!(src.data(), "let a = \"\u{FFFD}\u{FFFD}\"; // \u{FFFD}\n");
+    assert_eq!(src.invalid_utf8(), [
+        InvalidUtf8 { byte_offset: 9, len: 1, offset: 9 },
+        InvalidUtf8 { byte_offset: 10, len: 1, offset: 12 },
+        InvalidUtf8 { byte_offset: 17, len: 1, offset: 21 },
+    ]);
+
+    let (logger, collector) = Logger::collect();
+    tokenize(&src, Arc::new(LanguageConfig::default()), logger);
+    let errors = collector.of_level(Level::Error);
+    assert_eq!(
+        errors.iter().map(|e| (e.info.as_str(), e.range.clone())).collect::<Vec<_>>(),
+        [
+            ("Invalid UTF-8 at byte 9 of the file", 9..12),
+            ("Invalid UTF-8 at byte 10 of the file", 12..15),
+            ("Invalid UTF-8 at byte 17 of the file", 21..24),
+        ]
+    );
+}
+
+#[test]
+fn byte_string_escapes() {
+    let (logger, collector) = Logger::collect();
+    let src = Src::from_memory("test", r#"b"\x00\xffA\x7f\n" b"é\x4""#);
+    let tokens = tokenize(&src, Arc::new(LanguageConfig::default()), logger);
+    let bytes = tokens.iter().map(|t| match t.kind {
+        TokenKind::Bytes(ref b) => b.clone(),
+        _ => panic!("unexpected token {t}"),
+    }).collect::<Vec<_>>();
+    // Invalid bytes are reported but the literal is still a byte string
+    assert_eq!(bytes, [vec![0x00, 0xff, b'A', 0x7f, b'\n'], vec![0x00]]);
+    let errors = collector.of_level(Level::Error).into_iter().map(|e| e.info).collect::<Vec<_>>();
+    assert_eq!(errors, [
+        "Byte strings may only contain ASCII characters",
+        "Byte escape must have exactly 2 hex digits",
+    ]);
+}
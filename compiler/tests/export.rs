@@ -0,0 +1,24 @@
+use dash_compiler::parser::{config::{LanguageConfig, KeywordKind}, export::textmate_grammar};
+
+#[test]
+fn textmate_keywords_follow_config() {
+    let config = LanguageConfig::default().with_keyword("query", KeywordKind::Strict);
+    let grammar = textmate_grammar(&config);
+    let keywords = grammar["repository"]["keywords"]["patterns"].as_array().unwrap();
+    let regex_for = |scope: &str| keywords.iter()
+        .find(|p| p["name"] == scope)
+        .and_then(|p| p["match"].as_str())
+        .unwrap()
+        .to_string();
+
+    assert!(regex_for("keyword.other.dash").contains("|query)"));
+    assert!(regex_for("constant.language.dash").contains("true"));
+    assert!(!regex_for("keyword.other.dash").contains("true"));
+    assert!(regex_for("invalid.illegal.reserved.dash").contains("trait"));
+
+    // Longer operators must come first to be matched whole
+    let operators = grammar["repository"]["operators"]["match"].as_str().unwrap();
+    let pos = |op: &str| operators.find(&format!("{op}|")).unwrap();
+    assert!(pos("\\.\\.=") < pos("\\.\\."));
+    assert!(operators.contains("\\|>"));
+}
@@ -0,0 +1,82 @@
+use dash_compiler::shared::{html::html_document, logger::{Level, Message, Note}, src::{Src, Span}};
+
+#[test]
+fn escaping() {
+    let src = Src::from_memory("<test>", "let s = \"<b>&</b>\";\n");
+    let msg = Message::new(Level::Error, "Can't compare <int> & \"str\"", Span(&src, 8..18));
+    assert_eq!(
+        msg.to_html(),
+        "<div class=\"diagnostic error\">\
+        <div><span class=\"level\">Error</span>: Can't compare &lt;int&gt; &amp; &quot;str&quot;</div>\
+        <div class=\"location\">--&gt; &lt;test&gt;:1:9-1:19</div>\
+        <pre class=\"source\"><span class=\"line-number\">1</span>let s = \
+        <span class=\"underline squiggle\">&quot;&lt;b&gt;&amp;&lt;/b&gt;&quot;</span>;\n</pre>\
+        <ul class=\"notes\"></ul></div>"
+    );
+}
+
+#[test]
+fn multi_line_span() {
+    let src = Src::from_memory("test", "let a = {\n    1\n};\n");
+    let html = Message::new(Level::Warning, "Block", Span(&src, 8..17)).to_html();
+    assert!(html.starts_with("<div class=\"diagnostic warning\">"), "{html}");
+    // Every covered line is shown, underlined from the start of the span on
+    // the first line to its end on the last
+    assert!(html.contains(
+        "<pre class=\"source\">\
+        <span class=\"line-number\">1</span>let a = <span class=\"underline highlight\">{</span>\n\
+        <span class=\"line-number\">2</span><span class=\"underline highlight\">    1</span>\n\
+        <span class=\"line-number\">3</span><span class=\"underline highlight\">}</span>;\n\
+        </pre>"
+    ), "{html}");
+}
+
+#[test]
+fn crlf_line_endings() {
+    let lf = Src::from_memory("test", "let a = {\n    1\n};\n");
+    let crlf = Src::from_memory("test", "let a = {\r\n    1\r\n};\r\n");
+    // Line breaks are left out of the rendered lines, so both render the same
+    let html = Message::new(Level::Warning, "Block", Span(&crlf, 8..19)).to_html();
+    assert!(!html.contains('\r'), "{html:?}");
+    assert_eq!(html, Message::new(Level::Warning, "Block", Span(&lf, 8..17)).to_html());
+    // A span ending with a line break doesn't show the next line
+    let html = Message::new(Level::Warning, "Brace", Span(&crlf, 8..11)).to_html();
+    assert!(!html.contains('\r') && !html.contains("line-number\">2"), "{html:?}");
+    assert_eq!(html, Message::new(Level::Warning, "Brace", Span(&lf, 8..10)).to_html());
+}
+
+#[test]
+fn notes() {
+    let src = Src::from_memory("test", "let a = 1;\nlet a = 2;\n");
+    let html = Message::new(Level::Info, "Redeclared", Span(&src, 15..16))
+        .note(Note::new("Rename <one> of them", true))
+        .note(Note::new_at("First declared here", Span(&src, 4..5)))
+        .to_html();
+    assert!(html.ends_with(
+        "<ul class=\"notes\">\
+        <li class=\"hint\"><span class=\"note-kind\">Hint:</span> Rename &lt;one&gt; of them</li>\
+        <li class=\"note\"><span class=\"note-kind\">Note:</span> First declared here\
+        <div class=\"location\">--&gt; test:1:5-1:6</div>\
+        <pre class=\"source\"><span class=\"line-number\">1</span>let \
+        <span class=\"underline normal\">a</span> = 1;\n</pre></li>\
+        </ul></div>"
+    ), "{html}");
+}
+
+#[test]
+fn document() {
+    let src = Src::from_memory("test", "let a = 1;");
+    let messages = [
+        Message::new(Level::Error, "First", Span(&src, 4..5)).to_html(),
+        Message::new(Level::Warning, "Second", Span(&src, 8..9)).to_html(),
+    ];
+    let doc = html_document(messages.clone());
+    assert!(doc.starts_with("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n"), "{doc}");
+    assert!(doc.contains("<style>\n") && doc.contains(".underline.squiggle {"), "{doc}");
+    // Messages are placed in the body in order, one per line
+    assert!(doc.ends_with(&format!(
+        "</head>\n<body>\n{}\n{}\n</body>\n</html>\n", messages[0], messages[1]
+    )), "{doc}");
+
+    assert!(html_document([]).ends_with("<body>\n</body>\n</html>\n"));
+}
@@ -0,0 +1,38 @@
+use dash_compiler::{
+    ice,
+    shared::{ice::catch_ice, logger::{Logger, Level}, src::{ArcSpan, Src, Span}, timing::Phase},
+};
+
+#[test]
+fn reported_as_diagnostic() {
+    let src = Src::from_memory("test", "let a = 5;");
+    let (logger, collector) = Logger::collect();
+    let span = ArcSpan(src.clone(), 4..5);
+    let res: Option<()> = catch_ice(Phase::Check, Span(&src, 0..0), &logger, || ice!(at span, "{} has no type", "a"));
+    assert!(res.is_none());
+    assert_eq!(catch_ice(Phase::Check, Span(&src, 0..0), &logger, || 5), Some(5));
+
+    let errors = collector.of_level(Level::Error);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].info, "Internal compiler error during check: a has no type");
+    assert_eq!(errors[0].range, 4..5);
+    assert!(errors[0].notes[0].info.contains("This is a bug in the compiler"));
+}
+
+#[test]
+fn other_panics() {
+    let src = Src::from_memory("test", "let a = 5;");
+    let (logger, collector) = Logger::collect();
+    // A panic while the logger is locked poisons it
+    let res = catch_ice(Phase::Parse, Span(&src, 0..0), &logger, || {
+        let _lock = logger.lock().unwrap();
+        let nodes: Vec<usize> = vec![];
+        nodes[3]
+    });
+    assert!(res.is_none());
+
+    let errors = collector.of_level(Level::Error);
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].info.starts_with("Internal compiler error during parse: index out of bounds"), "{}", errors[0].info);
+    assert_eq!(errors[0].range, 0..0);
+}
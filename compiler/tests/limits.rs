@@ -0,0 +1,168 @@
+use std::sync::Arc;
+use dash_compiler::{
+    tokenize, parse, check_coherency,
+    parser::{config::{LanguageConfig, Limits}, parse::NodePool, Token, TokenKind},
+    shared::{src::{Src, Span}, logger::{Logger, Level, Message}},
+};
+
+fn config(limits: Limits) -> Arc<LanguageConfig> {
+    Arc::new(LanguageConfig { limits, ..LanguageConfig::default() })
+}
+
+#[test]
+fn max_string_length() {
+    let config = config(Limits { max_string_length: 4, ..Limits::default() });
+    // The emoji is four bytes long, so it just fits
+    let src = Src::from_memory("test", r#""abcd" "\u{1F600}" "abcde" b"abcde""#);
+    let tokens = tokenize(&src, config, Logger::collect().0);
+    let kinds = tokens.iter().map(|t| match t.kind {
+        TokenKind::String(ref s) => s.clone(),
+        TokenKind::Error(ref e) => e.clone(),
+        _ => panic!("unexpected token {t}"),
+    }).collect::<Vec<_>>();
+    assert_eq!(kinds, [
+        "abcd",
+        "\u{1F600}",
+        "string literal is too long (maximum length is 4 bytes)",
+        "byte string literal is too long (maximum length is 4 bytes)",
+    ]);
+}
+
+#[test]
+fn max_file_size() {
+    let config = config(Limits { max_file_size: 8, ..Limits::default() });
+    let (logger, collector) = Logger::collect();
+    let src = Src::from_memory("test", "let a = 1;");
+    assert!(tokenize(&src, config, logger).is_empty());
+    let errors = collector.of_level(Level::Error);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].info, "File is too large (10 bytes, maximum is 8)");
+}
+
+#[test]
+fn max_nesting_depth() {
+    let config = config(Limits { max_nesting_depth: 8, ..Limits::default() });
+    let (logger, collector) = Logger::collect();
+    let src = Src::from_memory("test", "let a = ((((((((((1))))))))));");
+    assert!(parse(src, config, &mut NodePool::new(), logger).is_err());
+    let errors = collector.of_level(Level::Error);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].info, "Maximum nesting depth (8) exceeded");
+}
+
+/// Brackets nested deeper than the limit are skipped and turned into one 
+/// error token instead of being tokenized recursively
+#[test]
+fn max_bracket_depth() {
+    let config = config(Limits { max_nesting_depth: 4, ..Limits::default() });
+    let tokens = |data: String| {
+        let src = Src::from_memory("test", data);
+        tokenize(&src, config.clone(), Logger::collect().0)
+            .into_iter()
+            .flat_map(Token::flatten)
+            .map(|t| t.to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(tokens(format!("{}1{} 2", "(".repeat(4), ")".repeat(4))), ["integer", "integer"]);
+    // Brackets in strings don't count when skipping
+    assert_eq!(
+        tokens(format!("{}1 \")]\" [2]{} 3", "(".repeat(5), ")".repeat(5))),
+        ["invalid token (brackets nested too deeply (maximum depth is 4))", "integer"]
+    );
+
+    // Brackets are tokenized without recursing, so the default limit 
+    // doesn't overflow the stack
+    let default = Arc::new(LanguageConfig::default());
+    for (depth, kind) in [
+        (256, "integer"),
+        (257, "invalid token (brackets nested too deeply (maximum depth is 256))"),
+    ] {
+        let src = Src::from_memory("test", format!("{}1{}", "[".repeat(depth), "]".repeat(depth)));
+        let tokens = tokenize(&src, default.clone(), Logger::collect().0)
+            .into_iter()
+            .flat_map(Token::flatten)
+            .map(|t| t.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(tokens, [kind]);
+    }
+
+    // The parser gives up before reaching the error token, so only its 
+    // error is reported
+    for depth in [256, 257, 100_000] {
+        let (logger, collector) = Logger::collect();
+        let src = Src::from_memory("test", format!("let a = {}1{};", "[".repeat(depth), "]".repeat(depth)));
+        assert!(parse(src, default.clone(), &mut NodePool::new(), logger).is_err());
+        let errors = collector.of_level(Level::Error);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].info, "Maximum nesting depth (256) exceeded");
+    }
+}
+
+/// Chains like `1 + 1 + ...` are parsed in a loop, but nest one level 
+/// deeper in the AST with every link, so they count toward the nesting depth
+#[test]
+fn long_chains() {
+    let chains = [
+        format!("let a = 1{};", " + 1".repeat(10_000)),
+        format!("let a = 1{};", " |> f()".repeat(10_000)),
+        format!("let a = 2{};", " ** 2".repeat(10_000)),
+        format!("let a = f{};", "()".repeat(10_000)),
+        format!("let a = a{};", ".b()".repeat(10_000)),
+        format!("let a = a{};", "[0]".repeat(10_000)),
+        format!("let a: int{} = 1;", " ?".repeat(10_000)),
+        format!("let a: int{} = 1;", " | int".repeat(10_000)),
+    ];
+    for chain in chains {
+        let (logger, collector) = Logger::collect();
+        let src = Src::from_memory("test", chain);
+        assert!(parse(src, Arc::new(LanguageConfig::default()), &mut NodePool::new(), logger).is_err());
+        let errors = collector.of_level(Level::Error);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].info, "Maximum nesting depth (256) exceeded");
+    }
+    // Shorter chains are fine
+    let src = Src::from_memory("test", format!("let a = 1{};", " + 1".repeat(200)));
+    let (logger, collector) = Logger::collect();
+    assert!(parse(src, Arc::new(LanguageConfig::default()), &mut NodePool::new(), logger).is_ok());
+    assert!(collector.messages().is_empty());
+}
+
+#[test]
+fn max_errors() {
+    let src = Src::from_memory("test", "abc");
+    let (logger, collector) = Logger::collect();
+    let mut logger = logger.lock().unwrap();
+    logger.set_max_errors(Some(2));
+    for _ in 0..5 {
+        logger.log(Message::new(Level::Error, "Bad thing", Span(&src, 0..1)));
+    }
+    logger.log(Message::new(Level::Warning, "Odd thing", Span(&src, 0..1)));
+
+    let infos = collector.messages().into_iter().map(|m| m.info).collect::<Vec<_>>();
+    assert_eq!(infos, ["Bad thing", "Bad thing", "Too many errors, stopped reporting after 2", "Odd thing"]);
+    assert!(logger.error_limit_reached());
+    assert_eq!(logger.errors(), 5);
+}
+
+/// The checker resolves chains recursively, so the longest chains the parser 
+/// accepts must be checked without overflowing the stack, even on a test 
+/// thread with its smaller stack
+#[test]
+fn check_long_chains() {
+    let chains = [
+        ("1", " + 1"), ("1", " |> f()"), ("2", " ** 2"), ("f", "()"), ("a", ".b()"), ("a", "[0]")
+    ];
+    for (start, link) in chains {
+        let config = Arc::new(LanguageConfig::default());
+        let (logger, collector) = Logger::collect();
+        let mut pool = NodePool::new();
+        let src = Src::from_memory("test", format!("let a = {start}{};", link.repeat(240)));
+        let Ok(mut ast) = parse(src, config.clone(), &mut pool, logger.clone()) else {
+            panic!("chain of '{link}' failed to parse");
+        };
+        check_coherency(&mut ast, &mut pool, config, logger);
+        if link == " + 1" {
+            assert!(collector.of_level(Level::Error).is_empty());
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use std::sync::Arc;
+use dash_compiler::{
+    parse, check_coherency,
+    parser::{parse::NodePool, config::LanguageConfig},
+    project::LintLevel,
+    shared::{src::Src, logger::{Logger, Level}},
+};
+
+fn check(config: LanguageConfig, data: &str) -> Vec<(Level, Option<String>)> {
+    let (logger, collector) = Logger::collect();
+    let config = Arc::new(config);
+    let mut pool = NodePool::new();
+    let mut ast = parse(Src::from_memory("test", data), config.clone(), &mut pool, logger.clone())
+        .ok().unwrap();
+    check_coherency(&mut ast, &mut pool, config, logger);
+    collector.messages().into_iter().map(|m| (m.level, m.code)).collect()
+}
+
+#[test]
+fn lint_levels() {
+    let src = "let x = 1;";
+    let code = Some(String::from("unused_variable"));
+    assert_eq!(check(LanguageConfig::default(), src), [(Level::Warning, code.clone())]);
+
+    let mut config = LanguageConfig::default();
+    config.lints.insert(String::from("unused_variable"), LintLevel::Deny);
+    assert_eq!(check(config.clone(), src), [(Level::Error, code)]);
+    // Attributes take precedence over the configured level
+    assert_eq!(check(config, "@allow(unused_variable) let x = 1;"), []);
+
+    let mut config = LanguageConfig::default();
+    config.lints.insert(String::from("unused_variable"), LintLevel::Allow);
+    assert_eq!(check(config, src), []);
+}
@@ -0,0 +1,115 @@
+use std::{io, sync::{Arc, Mutex}};
+use dash_compiler::{
+    tokenize_pool,
+    parser::{config::LanguageConfig, TokenKind},
+    shared::{cancel::CancellationToken, logger::{Logger, Level, Message, Note, JsonSink, OwnedNote, strip_colors}, src::{Src, Span}},
+};
+
+#[derive(Clone, Default)]
+struct SharedBuf(Arc<Mutex<Vec<u8>>>);
+
+impl io::Write for SharedBuf {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn multiple_sinks() {
+    let src = Src::from_memory("test", "let x = 5;");
+    let json = SharedBuf::default();
+    let (logger, collector) = Logger::collect();
+    logger.lock().unwrap().add_sink(JsonSink::new(json.clone()), Level::Error);
+
+    let mut logger = logger.lock().unwrap();
+    logger.log(Message::new(Level::Warning, "Unused variable", Span(&src, 4..5)));
+    logger.log(Message::new(Level::Error, "Bad \"thing\"", Span(&src, 8..9)));
+
+    let all = collector.messages().into_iter().map(|m| m.info).collect::<Vec<_>>();
+    assert_eq!(all, ["Unused variable", "Bad \"thing\""]);
+    let json = String::from_utf8(json.0.lock().unwrap().clone()).unwrap();
+    let lines = json.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 1, "{json}");
+    assert!(lines[0].contains(r#""info":"Bad \"thing\"""#), "{json}");
+    assert!(lines[0].contains(r#""range":{"start":8,"end":9}"#), "{json}");
+    assert_eq!((logger.errors(), logger.warnings()), (1, 1));
+}
+
+#[test]
+fn collect_owned_diagnostics() {
+    let (logger, collector) = Logger::collect();
+    {
+        // The collected messages outlive the source they were logged for
+        let src = Src::from_memory("test", "let x = 5;");
+        logger.lock().unwrap().log(
+            Message::new(Level::Error, "Bad value", Span(&src, 8..9))
+                .code("bad-value")
+                .note(Note::new_at("Declared here", Span(&src, 4..5)))
+        );
+    }
+    let errors = collector.of_level(Level::Error);
+    assert_eq!(errors.len(), 1);
+    let error = &errors[0];
+    assert_eq!(error.info, "Bad value");
+    assert_eq!(error.code.as_deref(), Some("bad-value"));
+    assert_eq!((error.file.as_str(), error.range.clone()), ("test", 8..9));
+    assert_eq!((error.line, error.column), (1, 9));
+    assert!(error.rendered.contains("Declared here"), "{}", error.rendered);
+    assert_eq!(error.notes, [OwnedNote {
+        hint: false,
+        info: String::from("Declared here"),
+        at: Some((String::from("test"), 4..5)),
+    }]);
+    assert!(collector.of_level(Level::Warning).is_empty());
+}
+
+#[test]
+fn log_from_pool() {
+    // Every source logs a warning for each of its invalid escapes
+    let srcs = (0..32)
+        .map(|i| Src::from_memory(format!("src{i}"), format!("{i} \"\\q\\q\\q\\q\"")))
+        .collect::<Vec<_>>();
+    let json = SharedBuf::default();
+    let (logger, collector) = Logger::collect();
+    logger.lock().unwrap().add_sink(JsonSink::new(json.clone()), Level::Info);
+
+    let tokens = tokenize_pool(&srcs, Arc::new(LanguageConfig::default()), &CancellationToken::new(), logger.clone());
+    for (i, tokens) in tokens.iter().enumerate() {
+        assert!(matches!(tokens[0].kind, TokenKind::Int(n) if n == i as i64), "{}", tokens[0]);
+    }
+
+    let messages = collector.messages();
+    assert_eq!(messages.len(), 32 * 4);
+    assert_eq!(logger.lock().unwrap().warnings(), 32 * 4);
+    // Messages from different threads may come in any order, but each one 
+    // is written out whole and the ones of a single source stay in order
+    let json = String::from_utf8(json.0.lock().unwrap().clone()).unwrap();
+    let lines = json.lines()
+        .map(|l| serde_json::from_str::<serde_json::Value>(l).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(lines.len(), messages.len());
+    for (i, src) in srcs.iter().enumerate() {
+        let of_src = messages.iter().filter(|m| m.file == src.name()).collect::<Vec<_>>();
+        assert_eq!(of_src.len(), 4);
+        let start = i.to_string().len() + 2;
+        let ranges = of_src.iter().map(|m| m.range.start).collect::<Vec<_>>();
+        assert_eq!(ranges, [start, start + 2, start + 4, start + 6]);
+    }
+}
+
+#[test]
+fn plain_rendering() {
+    assert_eq!(strip_colors("\x1b[1;31mError\x1b[0m: \x1b[36m^\x1b[0m"), "Error: ^");
+    assert_eq!(strip_colors("no colors [here]"), "no colors [here]");
+
+    let src = Src::from_memory("test", "let x = 5;");
+    let msg = Message::new(Level::Error, "Bad value", Span(&src, 8..9))
+        .note(Note::new_at("Declared here", Span(&src, 4..5)));
+    let plain = msg.to_plain_string();
+    assert!(!plain.contains('\x1b'), "{plain:?}");
+    assert_eq!(plain, strip_colors(&msg.to_string()));
+    assert!(plain.starts_with("Error:\n"), "{plain:?}");
+}
@@ -0,0 +1,27 @@
+use std::sync::Arc;
+use dash_compiler::{
+    parse_profiled,
+    parser::{parse::NodePool, profile::ParseProfiler, config::LanguageConfig},
+    shared::{src::Src, logger::Logger},
+};
+
+#[test]
+fn profile_parser() {
+    let profiler = ParseProfiler::new();
+    let mut pool = NodePool::new();
+    parse_profiled(
+        Src::from_memory("test", "let x = 5 + 2;\nlet y = x * 3;\n"),
+        Arc::new(LanguageConfig::default()),
+        &mut pool, profiler.clone(), Logger::new(|msg| panic!("{msg}"))
+    ).ok().unwrap();
+
+    let profiler = profiler.lock().unwrap();
+    assert_eq!(profiler.rule("ExprListNode").unwrap().calls, 1);
+    assert!(profiler.rule("LetDeclNode").unwrap().calls >= 2);
+
+    let mut folded = vec![];
+    profiler.write_folded(&mut folded).unwrap();
+    let folded = String::from_utf8(folded).unwrap();
+    assert!(folded.lines().all(|line| line.starts_with("ExprListNode")), "{folded}");
+    assert!(folded.lines().any(|line| line.contains(";LetDeclNode")), "{folded}");
+}
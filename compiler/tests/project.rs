@@ -0,0 +1,77 @@
+use std::path::PathBuf;
+use dash_compiler::{project::{Manifest, LintLevel, Project, MANIFEST_FILE_NAME}, parser::config::{Edition, Limits, LanguageConfig, KeywordKind}};
+
+#[test]
+fn manifest() {
+    let manifest = r#"
+        [project]
+        name = "test"
+        version = "1.0.0"
+        edition = "2024"
+
+        [lints]
+        unused = "deny"
+
+        [limits]
+        max_errors = 10
+    "#.parse::<Manifest>().unwrap();
+    assert_eq!(manifest.project.name, "test");
+    assert_eq!(manifest.project.edition, Edition::Edition2024);
+    assert_eq!(manifest.project.sources, vec![PathBuf::from("src")]);
+    assert_eq!(manifest.lints.get("unused"), Some(&LintLevel::Deny));
+    assert_eq!(manifest.limits.max_errors, Some(10));
+    assert_eq!(manifest.limits.max_nesting_depth, Limits::default().max_nesting_depth);
+}
+
+#[test]
+fn invalid_manifest() {
+    let err = "[project]\nname = \"test\"\nversion = \"1.0.0\"\nedition = \"1999\"\n"
+        .parse::<Manifest>().unwrap_err();
+    assert!(err.contains("Unknown edition '1999'"), "{err}");
+    assert!("[project]\nname = \"test\"\n".parse::<Manifest>().is_err());
+}
+
+#[test]
+fn switch_edition() {
+    let mut config = LanguageConfig::for_edition(Edition::Edition2025);
+    config.lints.insert(String::from("shadowing"), LintLevel::Deny);
+    config.cfg = config.cfg.with_flag("debug");
+    config.limits.max_errors = Some(10);
+    assert_eq!(config.keyword_kind("loop"), Some(KeywordKind::Reserved));
+
+    // The keyword tables follow the edition, everything else is kept
+    let config = config.with_edition(Edition::Edition2024);
+    assert_eq!(config.edition, Edition::Edition2024);
+    assert_eq!(config.keyword_kind("loop"), None);
+    assert_eq!(config.lints.get("shadowing"), Some(&LintLevel::Deny));
+    assert!(config.cfg.is_set("debug", None));
+    assert_eq!(config.limits.max_errors, Some(10));
+}
+
+#[test]
+fn project_language_config() {
+    let root = std::env::temp_dir().join(format!("project_config_{}", std::process::id()));
+    std::fs::create_dir_all(&root).unwrap();
+    std::fs::write(root.join(MANIFEST_FILE_NAME), r#"
+        [project]
+        name = "test"
+        version = "1.0.0"
+        edition = "2024"
+        target = "wasm"
+
+        [lints]
+        shadowing = "warn"
+
+        [limits]
+        max_errors = 10
+    "#).unwrap();
+    let project = Project::load(&root);
+    std::fs::remove_dir_all(&root).unwrap();
+
+    let config = project.unwrap().language_config();
+    assert_eq!(config.edition, Edition::Edition2024);
+    assert_eq!(config.lints.get("shadowing"), Some(&LintLevel::Warn));
+    assert_eq!(config.limits.max_errors, Some(10));
+    assert!(config.cfg.is_set("target", Some("wasm")));
+    assert!(!config.cfg.is_set("target", Some("native")));
+}
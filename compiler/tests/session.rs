@@ -0,0 +1,297 @@
+use std::{io, path::PathBuf, sync::{Arc, Mutex}};
+use dash_compiler::{
+    session::{Session, ProgressHandler},
+    artifacts::{ArtifactKind, EmitTarget},
+    index::SymbolIndex,
+    parser::config::LanguageConfig,
+    checker::{ty::Ty, path::FullIdentPath, symbol::SymbolKind},
+    shared::{logger::Logger, src::{Src, SrcPool}, timing::Phase, cancel::{CancellationToken, Cancelled}},
+};
+
+#[derive(Clone, Default)]
+struct Recorder(Arc<Mutex<Vec<String>>>);
+
+impl ProgressHandler for Recorder {
+    fn on_phase_start(&mut self, phase: Phase, files: usize) {
+        self.0.lock().unwrap().push(format!("start {phase} {files}"));
+    }
+    fn on_file_done(&mut self, phase: Phase, _src: &Src, n: usize, total: usize) {
+        self.0.lock().unwrap().push(format!("{phase} {n}/{total}"));
+    }
+}
+
+#[test]
+fn progress() {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/ui");
+    let srcs = SrcPool::new(vec![dir.join("let.dash"), dir.join("call.dash")]).unwrap();
+    let (logger, _) = Logger::collect();
+    let recorder = Recorder::default();
+    let mut session = Session::new(Arc::new(LanguageConfig::default()), logger);
+    session.set_progress_handler(recorder.clone());
+
+    let mut asts = session.parse(&srcs).unwrap();
+    session.check(&mut [], asts.as_mut_slice()).unwrap();
+
+    assert_eq!(
+        *recorder.0.lock().unwrap(),
+        ["start parse 2", "parse 1/2", "parse 2/2", "start check 2", "check 1/2", "check 2/2"]
+    );
+    assert_eq!(session.timings().entries().len(), 4);
+}
+
+/// Cancels its token once the first file of a phase is done
+struct CancelAfterFirst(CancellationToken);
+
+impl ProgressHandler for CancelAfterFirst {
+    fn on_file_done(&mut self, _phase: Phase, _src: &Src, _n: usize, _total: usize) {
+        self.0.cancel();
+    }
+}
+
+#[test]
+fn cancel() {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/ui");
+    let srcs = SrcPool::new(vec![dir.join("let.dash"), dir.join("unknown_item.dash")]).unwrap();
+
+    let cancel = CancellationToken::new();
+    let mut session = Session::new(Arc::new(LanguageConfig::default()), Logger::collect().0);
+    session.set_cancellation_token(cancel.clone());
+    session.set_progress_handler(CancelAfterFirst(cancel.clone()));
+    assert_eq!(session.parse(&srcs).err(), Some(Cancelled));
+    assert_eq!(session.timings().entries().len(), 1);
+
+    // Checking a cancelled session doesn't report anything
+    let (logger, collector) = Logger::collect();
+    let mut session = Session::new(Arc::new(LanguageConfig::default()), logger);
+    let mut asts = session.parse(&srcs).unwrap();
+    let cancel = CancellationToken::new();
+    session.set_cancellation_token(cancel.clone());
+    cancel.cancel();
+    let before = collector.messages().len();
+    assert_eq!(session.check(&mut [], asts.as_mut_slice()).err(), Some(Cancelled));
+    assert_eq!(collector.messages().len(), before);
+}
+
+#[test]
+fn typed_ast() {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/ui");
+    let srcs = SrcPool::new(vec![dir.join("let.dash")]).unwrap();
+    let mut session = Session::new(Arc::new(LanguageConfig::default()), Logger::collect().0);
+    let mut asts = session.parse(&srcs).unwrap();
+    let typed = session.check(&mut [], asts.as_mut_slice()).unwrap();
+    assert_eq!(typed.len(), 1);
+
+    let src = srcs.srcs()[0].clone();
+    let data = src.data();
+    let ty_at = |needle: &str| typed[0].ty_at(&src, data.find(needle).unwrap()).map(|(_, ty)| ty.clone());
+    assert_eq!(ty_at("2.5"), Some(Ty::Float));
+    assert_eq!(ty_at("\"hello\""), Some(Ty::String));
+    // `a * 2` is the innermost expression containing `*`
+    let (span, ty) = typed[0].ty_at(&src, data.find('*').unwrap()).unwrap();
+    assert_eq!((&data[span.1.clone()], ty), ("a * 2", &Ty::Int));
+}
+
+#[test]
+fn artifacts() {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/ui");
+    let srcs = SrcPool::new(vec![dir.join("let.dash"), dir.join("call.dash")]).unwrap();
+    let mut session = Session::new(Arc::new(LanguageConfig::default()), Logger::collect().0);
+    session.set_emit([ArtifactKind::Ast, ArtifactKind::Timings], EmitTarget::Memory);
+    session.tokenize(&srcs).unwrap();
+    let mut asts = session.parse(&srcs).unwrap();
+    session.check(&mut [], asts.as_mut_slice()).unwrap();
+
+    let artifacts = session.finish();
+    assert_eq!(
+        artifacts.iter().map(|a| a.kind).collect::<Vec<_>>(),
+        [ArtifactKind::Ast, ArtifactKind::Ast, ArtifactKind::Timings]
+    );
+    let ast = artifacts.of_kind(ArtifactKind::Ast).next().unwrap();
+    assert!(ast.src.as_ref().unwrap().ends_with("let.dash"));
+    assert!(ast.contents.as_ref().unwrap().starts_with("ExprListNode"));
+    assert!(ast.path.is_none());
+}
+
+/// Writer whose output can be read after it has been handed to a session
+#[derive(Clone, Default)]
+struct SharedBuf(Arc<Mutex<Vec<u8>>>);
+
+impl io::Write for SharedBuf {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn event_log() {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/ui");
+    let srcs = SrcPool::new(vec![dir.join("let.dash"), dir.join("unknown_item.dash")]).unwrap();
+    let buf = SharedBuf::default();
+    let mut session = Session::new(Arc::new(LanguageConfig::default()), Logger::collect().0);
+    session.set_event_log(buf.clone());
+    let mut asts = session.parse(&srcs).unwrap();
+    session.check(&mut [], asts.as_mut_slice()).unwrap();
+    session.finish();
+
+    let log = String::from_utf8(buf.0.lock().unwrap().clone()).unwrap();
+    let events = log.lines()
+        .map(|l| serde_json::from_str::<serde_json::Value>(l).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        events.iter()
+            .map(|e| format!("{} {}", e["event"].as_str().unwrap(), e["phase"].as_str().unwrap_or("-")))
+            .collect::<Vec<_>>(),
+        [
+            "phase_start parse", "file_done parse", "file_done parse", "phase_end parse",
+            "phase_start check", "file_done check", "file_done check", "phase_end check",
+            "finish -",
+        ]
+    );
+    assert!(events[1]["file"].as_str().unwrap().ends_with("let.dash"));
+    assert_eq!(events[2]["n"], 2);
+    let finish = events.last().unwrap();
+    assert!(finish["errors"].as_u64().unwrap() > 0);
+    assert_eq!(events[7]["errors"], finish["errors"]);
+}
+
+#[test]
+fn debug_log_matches() {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/ui");
+    let srcs = SrcPool::new(vec![dir.join("let.dash")]).unwrap();
+    let buf = SharedBuf::default();
+    let mut session = Session::new(Arc::new(LanguageConfig::default()), Logger::collect().0);
+    session.set_debug_log_matches(buf.clone());
+    session.parse(&srcs).unwrap();
+
+    let log = String::from_utf8(buf.0.lock().unwrap().clone()).unwrap();
+    let lines = log.lines().collect::<Vec<_>>();
+    assert!(lines[0].starts_with("> ExprListNode ("), "{log}");
+    assert!(lines.last().unwrap().starts_with("< ExprListNode matched ("), "{log}");
+    // Rules inside other rules are indented
+    assert!(lines[1].starts_with("  > ScalarExprNode (keyword let at "), "{log}");
+    assert!(lines.iter().any(|l| l.starts_with("      > LetDeclNode (keyword let at ")), "{log}");
+    assert!(lines.iter().any(|l| l.starts_with("      < LetDeclNode matched (")), "{log}");
+    assert_eq!(
+        lines.iter().filter(|l| l.trim_start().starts_with('>')).count(),
+        lines.iter().filter(|l| l.trim_start().starts_with('<')).count(),
+    );
+}
+
+/// Diagnostics and AST dumps of compiling every UI test file
+fn compile_ui_tests() -> (Vec<String>, Vec<String>) {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/ui");
+    let srcs = SrcPool::new_from_dir(dir).unwrap();
+    let (logger, collector) = Logger::collect();
+    let mut session = Session::new(Arc::new(LanguageConfig::default()), logger);
+    session.set_deterministic(true);
+    session.set_emit([ArtifactKind::Tokens, ArtifactKind::Ast], EmitTarget::Memory);
+    session.tokenize(&srcs).unwrap();
+    let mut asts = session.parse(&srcs).unwrap();
+    session.check(&mut [], asts.as_mut_slice()).unwrap();
+    (
+        collector.messages().into_iter().map(|m| m.rendered).collect(),
+        session.finish().into_iter().filter_map(|a| a.contents).collect(),
+    )
+}
+
+#[test]
+fn deterministic() {
+    assert!(compile_ui_tests() == compile_ui_tests(), "output differs between runs");
+}
+
+/// Sources are checked in path order no matter which order the file system 
+/// lists them in
+#[test]
+fn src_files_sorted() {
+    let dir = std::env::temp_dir().join(format!("src_files_sorted_{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("b")).unwrap();
+    for file in ["c.dash", "b/z.dash", "a.dash", "b/a.dash", "notes.txt"] {
+        std::fs::write(dir.join(file), "").unwrap();
+    }
+    let srcs = SrcPool::new_from_dir(dir.clone());
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let names = srcs.unwrap().iter()
+        .map(|src| PathBuf::from(src.name()).strip_prefix(&dir).unwrap().to_path_buf())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["a.dash", "b/a.dash", "b/z.dash", "c.dash"].map(PathBuf::from));
+}
+
+#[test]
+fn symbol_info() {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/ui");
+    let srcs = SrcPool::new(vec![dir.join("doc_comments.dash")]).unwrap();
+    let mut session = Session::new(Arc::new(LanguageConfig::default()), Logger::collect().0);
+    let mut asts = session.parse(&srcs).unwrap();
+    session.check(&mut [], asts.as_mut_slice()).unwrap();
+    let info = |name: &str| session.symbol_info(&FullIdentPath::new([name.into()])).unwrap();
+
+    let add = info("add");
+    assert_eq!(add.signature, "fun add(a: int, b: int = ...) -> int");
+    assert_eq!(add.doc.as_deref(), Some("Add two numbers.\nOverflows wrap around"));
+    assert!(srcs.srcs()[0].data()[add.span.1.clone()].starts_with("fun add"));
+
+    let max = info("MAX_BYTE");
+    assert_eq!(max.signature, "const MAX_BYTE: int = 255");
+    assert_eq!(max.doc.as_deref(), Some("The largest value of a byte"));
+
+    assert_eq!(info("old").doc.as_deref(), Some("Deprecated functions keep their docs"));
+    let show = info("show");
+    assert_eq!(show.signature, "fun show(value: int) -> string\nfun show(value: float) -> string");
+    assert_eq!(show.doc, None);
+    // Variables are gone once their scope has been checked
+    assert!(session.symbol_info(&FullIdentPath::new(["plain".into()])).is_none());
+}
+
+#[test]
+fn workspace_symbols() {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/ui");
+    let srcs = SrcPool::new(vec![dir.join("doc_comments.dash"), dir.join("safe_call.dash")]).unwrap();
+    let mut session = Session::new(Arc::new(LanguageConfig::default()), Logger::collect().0);
+    let mut asts = session.parse(&srcs).unwrap();
+    session.check(&mut [], asts.as_mut_slice()).unwrap();
+    let search = |query: &str| session.workspace_symbols(query).into_iter()
+        .map(|s| s.path.to_string())
+        .collect::<Vec<_>>();
+
+    assert_eq!(search("sh"), ["::show", "::string::shout"]);
+    assert_eq!(search("fw"), ["::string::first_word"]);
+    assert_eq!(search("string::sh"), ["::string::shout"]);
+    assert_eq!(search("MAX"), ["::MAX_BYTE"]);
+    // Builtins aren't declared by any file
+    assert!(search("abs").is_empty());
+}
+
+#[test]
+fn symbol_index() {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/ui");
+    let check = |files: &[&str], index: &mut SymbolIndex| {
+        let srcs = SrcPool::new(files.iter().map(|f| dir.join(f)).collect()).unwrap();
+        let mut session = Session::new(Arc::new(LanguageConfig::default()), Logger::collect().0);
+        let mut asts = session.parse(&srcs).unwrap();
+        session.check(&mut [], asts.as_mut_slice()).unwrap();
+        session.update_symbol_index(index);
+    };
+    let mut index = SymbolIndex::default();
+    check(&["doc_comments.dash", "safe_call.dash"], &mut index);
+
+    let path = std::env::temp_dir().join(format!("symbol_index_{}.json", std::process::id()));
+    index.save(&path).unwrap();
+    let mut index = SymbolIndex::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let (file, add) = index.find("::add").unwrap();
+    assert!(file.ends_with("doc_comments.dash"));
+    assert_eq!((add.kind, add.line, add.column), (SymbolKind::Function, 3, 1));
+    assert_eq!(index.find("::MAX_BYTE").unwrap().1.kind, SymbolKind::Constant);
+
+    // Updating with one file keeps the symbols of the other
+    check(&["safe_call.dash"], &mut index);
+    assert!(index.find("::add").is_some());
+    assert!(index.find("::string::shout").is_some());
+    index.retain_files(|f| !f.ends_with("doc_comments.dash"));
+    assert!(index.find("::add").is_none());
+}
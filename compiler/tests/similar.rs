@@ -0,0 +1,28 @@
+use dash_compiler::shared::similar::{edit_distance, closest_match, fuzzy_score};
+
+#[test]
+fn distances() {
+    assert_eq!(edit_distance("shout", "shout"), 0);
+    assert_eq!(edit_distance("shuot", "shout"), 1);
+    assert_eq!(edit_distance("shot", "shout"), 1);
+    assert_eq!(edit_distance("", "abc"), 3);
+    assert_eq!(edit_distance("kitten", "sitting"), 3);
+}
+
+#[test]
+fn closest() {
+    let names = ["shout", "whisper", "double"];
+    assert_eq!(closest_match("shuot", names), Some("shout"));
+    assert_eq!(closest_match("dubble", names), Some("double"));
+    assert_eq!(closest_match("triple", names), None);
+}
+
+#[test]
+fn fuzzy() {
+    assert_eq!(fuzzy_score("shout", "Shout"), Some(0));
+    assert_eq!(fuzzy_score("sh", "shout"), Some(1));
+    assert_eq!(fuzzy_score("out", "shout"), Some(4));
+    assert!(fuzzy_score("sht", "shout").unwrap() < fuzzy_score("st", "shout_twice").unwrap());
+    assert_eq!(fuzzy_score("tuo", "shout"), None);
+    assert_eq!(fuzzy_score("shouts", "shout"), None);
+}
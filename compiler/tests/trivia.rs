@@ -0,0 +1,46 @@
+use std::sync::Arc;
+use dash_compiler::{
+    tokenize_with_trivia,
+    parser::{config::LanguageConfig, Token, TokenKind, Trivia, TriviaKind},
+    shared::{src::Src, logger::Logger},
+};
+
+const SOURCE: &str = "// leading comment\nlet x = [1,  2]; // trailing\n\n\tfn f(a: int) {\n    // inner\n    a\n}\n// no newline at end";
+
+/// Tokens and trivia in source order, with bracketed expressions taken apart 
+/// so the trivia inside them is used too
+fn pieces<'s>(tokens: Vec<Token<'s>>, trivia: &[Trivia<'s>]) -> String {
+    let mut pieces = vec![];
+    fn push<'s>(token: Token<'s>, pieces: &mut Vec<(usize, String)>) {
+        match token.kind {
+            TokenKind::Parentheses(tree) | TokenKind::Brackets(tree) | TokenKind::Braces(tree) => {
+                let span = token.span.1;
+                pieces.push((span.start, token.raw[..1].to_string()));
+                pieces.push((span.end - 1, token.raw[token.raw.len() - 1..].to_string()));
+                for token in tree {
+                    push(token, pieces);
+                }
+            }
+            _ => pieces.push((token.span.1.start, token.raw.to_string())),
+        }
+    }
+    for token in tokens {
+        push(token, &mut pieces);
+    }
+    pieces.extend(trivia.iter().map(|t| (t.span.1.start, t.raw().to_string())));
+    pieces.sort_by_key(|(start, _)| *start);
+    pieces.into_iter().map(|(_, raw)| raw).collect()
+}
+
+#[test]
+fn trivia_round_trip() {
+    let src = Src::from_memory("test", SOURCE);
+    let (tokens, trivia) = tokenize_with_trivia(&src, Arc::new(LanguageConfig::default()), Logger::new(|_| {}));
+    assert_eq!(pieces(tokens, &trivia), SOURCE);
+
+    let comments = trivia.iter()
+        .filter(|t| t.kind == TriviaKind::Comment)
+        .map(Trivia::raw)
+        .collect::<Vec<_>>();
+    assert_eq!(comments, ["// leading comment", "// trailing", "// inner", "// no newline at end"]);
+}
@@ -0,0 +1,46 @@
+use dash_compiler::{checker::ty::{Ty, ParamTy}, shared::src::{ArcSpan, Src}};
+
+fn param(name: &str, ty: Ty, has_default: bool, decl_span: ArcSpan) -> ParamTy {
+    ParamTy { name: Some(name.into()), ty, has_default, decl_span }
+}
+
+fn fun(params: Vec<ParamTy>, ret_ty: Ty) -> Ty {
+    Ty::Function { params, ret_ty: ret_ty.into() }
+}
+
+#[test]
+fn functions_from_different_declarations() {
+    let src = Src::from_memory("a.dash", "fun add(a: int, b: int = 1) -> int { a + b }");
+    let add = fun(vec![
+        param("a", Ty::Int, false, ArcSpan(src.clone(), 8..14)),
+        param("b", Ty::Int, true, ArcSpan(src, 16..26)),
+    ], Ty::Int);
+    let written = fun(vec![ParamTy::new(None::<String>, Ty::Int), ParamTy::new(None::<String>, Ty::Int)], Ty::Int);
+    assert_eq!(add, written);
+    assert!(add.convertible(&written));
+    assert!(written.convertible(&add));
+}
+
+#[test]
+fn functions_differing_in_types() {
+    let int_to_int = fun(vec![ParamTy::new(Some("x"), Ty::Int)], Ty::Int);
+    let int_to_string = fun(vec![ParamTy::new(Some("x"), Ty::Int)], Ty::String);
+    let two_params = fun(vec![ParamTy::new(Some("x"), Ty::Int), ParamTy::new(Some("y"), Ty::Int)], Ty::Int);
+    assert_ne!(int_to_int, int_to_string);
+    assert!(!int_to_int.convertible(&int_to_string));
+    assert!(!int_to_int.convertible(&two_params));
+}
+
+#[test]
+fn function_params_and_return_types_convert() {
+    let either = Ty::union([Ty::Int, Ty::String]);
+    let takes_either = fun(vec![ParamTy::new(Some("x"), either.clone())], Ty::Int);
+    let takes_int = fun(vec![ParamTy::new(Some("x"), Ty::Int)], Ty::Int);
+    let returns_either = fun(vec![ParamTy::new(Some("x"), Ty::Int)], either);
+    // A function that takes more values can be used where fewer are passed
+    assert!(takes_either.convertible(&takes_int));
+    assert!(!takes_int.convertible(&takes_either));
+    // A function that returns fewer values can be used where more are expected
+    assert!(takes_int.convertible(&returns_either));
+    assert!(!returns_either.convertible(&takes_int));
+}
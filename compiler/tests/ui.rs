@@ -0,0 +1,29 @@
+
+use dash_compiler::shared::snapshot::SnapshotRunner;
+
+/// Compiles every fixture in `tests/ui` and compares the output to the 
+/// golden files next to it. Run with `BLESS=1` to update the golden files
+#[test]
+fn ui() {
+    let mismatches = SnapshotRunner::new(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/ui"))
+        .bless(std::env::var_os("BLESS").is_some())
+        .run()
+        .unwrap();
+    for mismatch in &mismatches {
+        eprintln!("{mismatch}");
+    }
+    assert!(mismatches.is_empty(), "{} snapshots did not match", mismatches.len());
+}
+
+#[test]
+fn invalid_fixture_header() {
+    let dir = std::env::temp_dir().join(format!("invalid_fixture_header_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("bad.dash"), "// edition: 1999\nlet a = 1;\n").unwrap();
+    let res = SnapshotRunner::new(&dir).run();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let err = res.unwrap_err();
+    assert!(err.starts_with("Invalid fixture ") && err.contains("bad.dash"), "{err}");
+    assert!(err.contains("Unknown edition '1999'"), "{err}");
+}
@@ -0,0 +1,22 @@
+#![no_main]
+
+use dash_compiler::{
+    parse, shared::{logger::Logger, src::{Src, decode}}, parser::{parse::NodePool, config::LanguageConfig}
+};
+use libfuzzer_sys::fuzz_target;
+use std::sync::Arc;
+
+// Calls `parse` rather than `parse_no_panic` so that panics actually reach 
+// the fuzzer instead of being turned into diagnostics
+fuzz_target!(|data: &[u8]| {
+    // Input that isn't valid UTF-8 is decoded like a source file on disk, 
+    // with the invalid sequences replaced
+    let (data, _) = decode(data);
+    let logger = Logger::new(|_| {});
+    let _ = parse(
+        Src::from_memory("<fuzz input>", data),
+        Arc::new(LanguageConfig::default()),
+        &mut NodePool::new(),
+        logger
+    );
+});
@@ -0,0 +1,103 @@
+//! Bindings for running the compiler front-end in the browser
+
+use std::sync::Arc;
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+use dash_compiler::{
+    parse_no_panic, check_no_panic, tokenize,
+    parser::{config::{LanguageConfig, Limits}, parse::NodePool},
+    shared::{logger::{Logger, Level, strip_colors}, src::Src},
+};
+
+/// A diagnostic emitted while compiling
+#[derive(Debug, Serialize)]
+pub struct Diagnostic {
+    /// One of `info`, `warning` or `error`
+    pub level: &'static str,
+    pub message: String,
+    /// Byte offsets of the diagnostic's span in the source
+    pub start: usize,
+    pub end: usize,
+    /// 1-based line and column of the start of the span
+    pub line: usize,
+    pub column: usize,
+    /// The full diagnostic as it would be printed to a terminal, with notes
+    pub rendered: String,
+}
+
+/// A token in the source, for syntax highlighting
+#[derive(Debug, Serialize)]
+pub struct TokenInfo {
+    pub kind: &'static str,
+    pub raw: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CompileOutput {
+    pub diagnostics: Vec<Diagnostic>,
+    /// Dump of the AST, if the source could be parsed
+    pub ast: Option<String>,
+    pub tokens: Vec<TokenInfo>,
+}
+
+/// Tokenize, parse and check a source file
+pub fn compile_source(source: &str) -> CompileOutput {
+    let (logger, collector) = Logger::collect();
+    // Anyone can paste code into the playground, so keep it from using up 
+    // the memory of the page
+    let config = LanguageConfig {
+        limits: Limits {
+            max_file_size: 1024 * 1024,
+            max_string_length: 64 * 1024,
+            max_errors: Some(100),
+            ..Limits::default()
+        },
+        ..LanguageConfig::default()
+    };
+    logger.lock().unwrap().set_max_errors(config.limits.max_errors);
+    let config = Arc::new(config);
+    let src = Src::from_memory("<playground>", source);
+
+    // Tokenize separately so error tokens aren't reported twice
+    let tokens = tokenize(&src, config.clone(), Logger::new(|_| {}))
+        .into_iter()
+        .flat_map(|t| t.flatten())
+        .map(|t| TokenInfo {
+            kind: t.kind_name(),
+            raw: t.raw.to_string(),
+            start: t.span.1.start,
+            end: t.span.1.end,
+        })
+        .collect();
+
+    let mut pool = NodePool::new();
+    let ast = parse_no_panic(src, config.clone(), &mut pool, logger.clone()).ok().map(|mut ast| {
+        check_no_panic(&mut ast, &mut pool, config, logger.clone());
+        pool.dump(&ast)
+    });
+    let diagnostics = collector.messages().into_iter()
+        .map(|msg| Diagnostic {
+            level: match msg.level {
+                Level::Info => "info",
+                Level::Warning => "warning",
+                Level::Error => "error",
+            },
+            message: msg.info,
+            start: msg.range.start,
+            end: msg.range.end,
+            line: msg.line,
+            column: msg.column,
+            // Shown as plain text
+            rendered: strip_colors(&msg.rendered),
+        })
+        .collect();
+    CompileOutput { diagnostics, ast, tokens }
+}
+
+/// Compile a source file, returning `{ diagnostics, ast, tokens }`
+#[wasm_bindgen]
+pub fn compile(source: &str) -> Result<JsValue, JsValue> {
+    serde_wasm_bindgen::to_value(&compile_source(source)).map_err(JsValue::from)
+}
@@ -0,0 +1,17 @@
+use dash_wasm::compile_source;
+
+#[test]
+fn compile() {
+    let output = compile_source("let a = (1 + 2);\nlet b: string = a;");
+    assert!(output.ast.is_some());
+    assert_eq!(
+        output.tokens.iter().map(|t| t.kind).collect::<Vec<_>>(),
+        ["keyword", "ident", "punct", "int", "punct", "int", "punct",
+         "keyword", "ident", "punct", "ident", "punct", "ident", "punct"]
+    );
+    let errors = output.diagnostics.iter().filter(|d| d.level == "error").collect::<Vec<_>>();
+    assert_eq!(errors.len(), 1);
+    assert_eq!((errors[0].line, errors[0].column), (2, 1));
+    // `b` is never used
+    assert!(output.diagnostics.iter().any(|d| d.level == "warning" && d.message == "Unused variable b"));
+}